@@ -1,10 +1,23 @@
+use cook_with_rust_parser::cookbook::{self, CookbookOptions};
+use cook_with_rust_parser::csv::CsvOptions;
+use cook_with_rust_parser::format::{format, FormatOptions};
+use cook_with_rust_parser::lint::Linter;
+use cook_with_rust_parser::plan::{CookPlan, PlanOptions};
+use cook_with_rust_parser::render::{to_html, to_markdown, RenderOptions};
+use cook_with_rust_parser::{
+    parse_path, Amount, Collection, ParseOptions, Quantity, Recipe, Severity, ShoppingList,
+};
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::Duration;
 use structopt::StructOpt;
-use cook_with_rust_parser::parse;
 
 #[derive(Debug, StructOpt)]
-#[structopt(name = "crab-soup-cli", about = "CLI for the CookLang description language.")]
+#[structopt(
+    name = "cook-cli",
+    about = "CLI for the CookLang description language."
+)]
 struct Opt {
     #[structopt(subcommand)]
     operation: Operations,
@@ -12,12 +25,80 @@ struct Opt {
 
 #[derive(Debug, StructOpt)]
 enum Operations {
+    /// Dump the parsed recipe as JSON or YAML.
+    Parse {
+        #[structopt(parse(from_os_str))]
+        source: PathBuf,
+        /// `json` or `yaml`.
+        #[structopt(long, default_value = "json", possible_values = &["json", "yaml"])]
+        format: String,
+    },
+    /// Lint the recipes. Fails if there are errors.
+    Check {
+        #[structopt(parse(from_os_str), required = true)]
+        sources: Vec<PathBuf>,
+    },
+    /// Print the shopping list of the recipes.
+    ShoppingList {
+        #[structopt(parse(from_os_str), required = true)]
+        sources: Vec<PathBuf>,
+        /// Leave out ingredients which are optional in every recipe.
+        #[structopt(long)]
+        without_optional: bool,
+        /// Print the list as CSV, e.g. to import it into a spreadsheet.
+        #[structopt(long)]
+        csv: bool,
+    },
+    /// Print the ingredients of the recipe for a number of servings.
+    Scale {
+        #[structopt(parse(from_os_str))]
+        source: PathBuf,
+        #[structopt(short, long)]
+        servings: usize,
+        /// Write the amounts in units which are easier to read, like `1.5 l` for `1500 ml`.
+        #[structopt(long)]
+        humanize: bool,
+    },
+    /// Format the recipes in place.
+    Fmt {
+        #[structopt(parse(from_os_str), required = true)]
+        sources: Vec<PathBuf>,
+        /// Only check if the recipes are formatted, fails if they aren't.
+        #[structopt(long)]
+        check: bool,
+    },
+    /// Render the recipe for reading.
+    Render {
+        #[structopt(parse(from_os_str))]
+        source: PathBuf,
+        /// `text`, `markdown` or `html`.
+        #[structopt(long, default_value = "text", possible_values = &["text", "markdown", "html"])]
+        format: String,
+        /// List the baker's percentages relative to this ingredient, for `markdown`.
+        #[structopt(long)]
+        bakers: Option<String>,
+    },
+    /// Print the recipes of the directory as a cookbook in HTML, ready to print.
+    Cookbook {
+        #[structopt(parse(from_os_str))]
+        directory: PathBuf,
+        #[structopt(long, default_value = "Cookbook")]
+        title: String,
+    },
+    /// Print a timeline for cooking the recipes at once.
+    Plan {
+        #[structopt(parse(from_os_str), required = true)]
+        sources: Vec<PathBuf>,
+        /// Minutes of work per step, before its timers start.
+        #[structopt(long, default_value = "5")]
+        step_minutes: u64,
+    },
     Recipe {
         #[structopt(short, parse(from_os_str))]
         source: PathBuf,
         #[structopt(subcommand)]
-        operation: RecipeOpt
-    }
+        operation: RecipeOpt,
+    },
 }
 
 #[derive(Debug, StructOpt)]
@@ -35,14 +116,175 @@ enum RecipeOpt {
 fn main() {
     let opt = Opt::from_args();
     match opt.operation {
-        Operations::Recipe {
-            operation,
-            source
+        Operations::Parse { source, format } => {
+            let (_, recipe) = read_recipe(&source);
+            let output = match format.as_str() {
+                "yaml" => serde_yaml::to_string(&recipe).expect("Error serializing to YAML."),
+                _ => serde_json::to_string_pretty(&recipe).expect("Error serializing to JSON."),
+            };
+            println!("{}", output);
+        }
+        Operations::Check { sources } => {
+            let linter = Linter::default();
+            let mut errors = false;
+            sources.iter().for_each(|source| {
+                let (inp_recipe, recipe) = read_recipe(source);
+                linter.lint(&recipe).iter().for_each(|diagnostic| {
+                    errors |= diagnostic.severity == Severity::Error;
+                    eprint!("{}: {}", source.display(), diagnostic.render(&inp_recipe));
+                });
+            });
+            if errors {
+                exit(1);
+            }
+        }
+        Operations::ShoppingList {
+            sources,
+            without_optional,
+            csv,
+        } => {
+            let recipes: Vec<Recipe> = sources.iter().map(|source| read_recipe(source).1).collect();
+            let mut list = ShoppingList::from_recipes(&recipes);
+            if without_optional {
+                list.remove_optional();
+            }
+            if csv {
+                return print!("{}", list.to_csv(&CsvOptions::default()));
+            }
+            list.items.values().for_each(|item| {
+                let quantities: Vec<String> = item
+                    .quantities
+                    .quantities
+                    .iter()
+                    .map(Quantity::to_string)
+                    .collect();
+                match quantities.is_empty() {
+                    true => println!("{}", item.name),
+                    false => println!("{}: {}", item.name, quantities.join(" + ")),
+                }
+            });
+        }
+        Operations::Scale {
+            source,
+            servings,
+            humanize,
+        } => {
+            let (_, mut recipe) = read_recipe(&source);
+            let listed = recipe.metadata.servings.clone().unwrap_or_default();
+            recipe
+                .metadata
+                .ingredients
+                .values_mut()
+                .for_each(|ingredient| {
+                    ingredient
+                        .quantities
+                        .quantities
+                        .iter_mut()
+                        .for_each(|quantity| {
+                            quantity.amount =
+                                Amount::Single(scale(&quantity.amount, servings, &listed))
+                        });
+                    ingredient.quantities = ingredient.total();
+                });
+            if humanize {
+                recipe.humanize_units();
+            }
+            recipe.metadata.ingredients.values().for_each(|ingredient| {
+                let quantities: Vec<String> = ingredient
+                    .quantities
+                    .quantities
+                    .iter()
+                    .map(Quantity::to_string)
+                    .collect();
+                match quantities.is_empty() {
+                    true => println!("{}", ingredient.name),
+                    false => println!("{}: {}", ingredient.name, quantities.join(" + ")),
+                }
+            });
+        }
+        Operations::Fmt { sources, check } => {
+            let mut unformatted = false;
+            sources.iter().for_each(|source| {
+                let inp_recipe = match read_to_string(source) {
+                    Ok(inp_recipe) => inp_recipe,
+                    Err(error) => {
+                        eprintln!("{}: {}", source.display(), error);
+                        exit(1);
+                    }
+                };
+                let formatted = format(&inp_recipe, &FormatOptions::default());
+                if formatted == inp_recipe {
+                    return;
+                }
+                if check {
+                    eprintln!("{} is not formatted", source.display());
+                    unformatted = true;
+                } else {
+                    if let Err(error) = std::fs::write(source, formatted) {
+                        eprintln!("{}: {}", source.display(), error);
+                        exit(1);
+                    }
+                }
+            });
+            if unformatted {
+                exit(1);
+            }
+        }
+        Operations::Render {
+            source,
+            format,
+            bakers,
+        } => {
+            let (_, recipe) = read_recipe(&source);
+            let options = RenderOptions {
+                bakers_percentages: bakers,
+                ..RenderOptions::default()
+            };
+            match format.as_str() {
+                "markdown" => print!("{}", to_markdown(&recipe, &options)),
+                "html" => print!("{}", to_html(&recipe)),
+                _ => print!("{}", recipe),
+            }
+        }
+        Operations::Cookbook { directory, title } => {
+            let collection = match Collection::load_dir(&directory) {
+                Ok(collection) => collection,
+                Err(error) => {
+                    eprintln!("{}: {}", directory.display(), error);
+                    exit(1);
+                }
+            };
+            collection
+                .errors
+                .iter()
+                .for_each(|error| eprintln!("{}", error));
+            let options = CookbookOptions {
+                title,
+                ..CookbookOptions::default()
+            };
+            print!("{}", cookbook::to_html(&collection, &options));
+        }
+        Operations::Plan {
+            sources,
+            step_minutes,
         } => {
-            let inp_recipe = read_to_string(source)
-                .expect("Reading input file failed");
-            let recipe = parse(&inp_recipe)
-                .expect("Error during parsing of input file");
+            let recipes: Vec<Recipe> = sources.iter().map(|source| read_recipe(source).1).collect();
+            let options = PlanOptions {
+                step_time: Duration::from_secs(step_minutes * 60),
+            };
+            let plan = CookPlan::from_recipes(&recipes, &options);
+            plan.steps.iter().for_each(|step| {
+                let title = recipes[step.recipe].title().unwrap_or_default();
+                print!("{} {} {}: {}", clock(step.start), title, step.step, step.text);
+                match step.ready > step.end {
+                    true => println!(" (until {})", clock(step.ready)),
+                    false => println!(),
+                }
+            });
+            println!("{} Done", clock(plan.duration));
+        }
+        Operations::Recipe { operation, source } => {
+            let (_, recipe) = read_recipe(&source);
             match operation {
                 RecipeOpt::JSON { path } => {
                     let json = serde_json::to_string(&recipe)
@@ -52,10 +294,49 @@ fn main() {
                 }
                 RecipeOpt::Markdown { path } => {
                     let md = cook_markdown::recipe_to_markdown(&recipe);
-                    std::fs::write(path, md)
-                        .expect("Error during writing of markdown to file");
+                    std::fs::write(path, md).expect("Error during writing of markdown to file");
                 }
             }
         }
     }
 }
+
+/// Read and parse the recipe. Warnings are printed, errors end the program.
+fn read_recipe(source: &Path) -> (String, Recipe) {
+    let output = match parse_path(source, &ParseOptions::default()) {
+        Ok(output) => output,
+        Err(error) => {
+            eprint!("{}", error.render());
+            exit(1);
+        }
+    };
+    let inp_recipe = output.recipe.source.clone();
+    output
+        .warnings
+        .iter()
+        .for_each(|warning| eprint!("{}", warning.render_file(&inp_recipe, source)));
+    (inp_recipe, output.recipe)
+}
+
+/// The time like `1:05`.
+fn clock(time: Duration) -> String {
+    let minutes = time.as_secs() / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// The amount for the servings. Amounts per servings which aren't listed in the metadata are
+/// scaled from the first listed servings.
+fn scale(amount: &Amount, servings: usize, listed: &[usize]) -> f64 {
+    match amount {
+        Amount::Single(value) => *value,
+        Amount::Multi(value) => value * servings as f64,
+        Amount::PerServing(values) => match listed.iter().position(|&s| s == servings) {
+            Some(index) if index < values.len() => values[index],
+            _ => match (values.first(), listed.first()) {
+                (Some(value), Some(&first)) if first > 0 => value * servings as f64 / first as f64,
+                (Some(value), _) => *value,
+                _ => 0.0,
+            },
+        },
+    }
+}
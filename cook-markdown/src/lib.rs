@@ -24,7 +24,7 @@ pub fn recipe_to_markdown(recipe: &Recipe) -> String {
                         Amount::Multi(dd) => {
                             format!("servings * {}", format_amount(dd))
                         }
-                        Amount::Servings(dd) => {
+                        Amount::PerServing(dd) => {
                             let mut servings = String::new();
                             dd.iter().for_each(|a| {
                                 servings.push_str(&format_amount(a));
@@ -57,8 +57,8 @@ pub fn recipe_to_markdown(recipe: &Recipe) -> String {
         });
     }
 
-    recipe.instruction.chars().for_each(|char| {
-        if char == '@' {
+    instruction_parts(&recipe.instruction).for_each(|part| {
+        if part == InstructionPart::Ingredient {
             let insert_ingredient = ingredient_specifier_iter.next().unwrap();
             let ingredient_referenced = recipe.metadata.ingredients.get(&insert_ingredient.ingredient).unwrap();
             let ingredient_name = &ingredient_referenced.name;
@@ -75,7 +75,7 @@ pub fn recipe_to_markdown(recipe: &Recipe) -> String {
                     //format!(" *{}", d)
                     format_amount(d)
                 }
-                Amount::Servings(dd) => {
+                Amount::PerServing(dd) => {
                     let mut servings = String::new();
                     servings.push(' ');
                     dd.iter().for_each(|a| {
@@ -93,20 +93,24 @@ pub fn recipe_to_markdown(recipe: &Recipe) -> String {
                     res
                 }
             };
-            let insert_string = format!("__{} ({}{})__", ingredient_name, ingredient_amount, ingredient_unit);
+            let ingredient_note = match &insert_ingredient.note {
+                None => "".to_string(),
+                Some(note) => format!(", {}", note),
+            };
+            let insert_string = format!("__{} ({}{}{})__", ingredient_name, ingredient_amount, ingredient_unit, ingredient_note);
             result_string += &insert_string;
-        } else if char == '#' {
+        } else if part == InstructionPart::Cookware {
             result_string.push_str("_");
             result_string.push_str(cookware_iter.next().unwrap());
             result_string.push_str("_");
-        } else if char == '~' {
+        } else if part == InstructionPart::Timer {
             let timer = timer_iter.next().unwrap();
             result_string.push_str("__");
             result_string.push_str(&timer.amount.to_string());
             result_string.push(' ');
             result_string.push_str(&timer.unit);
             result_string.push_str("__");
-        } else {
+        } else if let InstructionPart::Text(char) = part {
             result_string.push(char);
         }
     });
@@ -0,0 +1,160 @@
+//! Parser for the CookLang [aisle configuration](https://cooklang.org/docs/spec/#shopping-lists).
+//!
+//! The aisle configuration groups ingredients by the section of the store they can be found in.
+//! Every section starts with its name in square brackets and lists one ingredient per line.
+//! Synonyms of an ingredient are separated by `|`.
+//!
+//! ```text
+//! [fruit and veg]
+//! apple gala | apples
+//! aubergine
+//!
+//! [milk and dairy]
+//! butter
+//! egg | eggs
+//! ```
+
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Name of the bucket for ingredients that are not part of any [Category].
+pub const OTHER: &str = "other";
+
+/// The parsed aisle configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AisleConf {
+    /// Categories in the order they appear in the configuration.
+    pub categories: Vec<Category>,
+}
+
+/// A section of the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Category {
+    /// Name of the category, e.g. `fruit and veg`.
+    pub name: String,
+    /// Ingredients found in this category. Every entry contains the name of the ingredient
+    /// followed by its synonyms.
+    pub ingredients: Vec<Vec<String>>,
+}
+
+impl AisleConf {
+    /// Parse the content of an aisle configuration.
+    pub fn parse(inp: &str) -> Result<AisleConf, Box<dyn std::error::Error>> {
+        let mut categories: Vec<Category> = vec![];
+        for (number, line) in inp.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("Missing ] in category on line {}", number + 1))?;
+                categories.push(Category {
+                    name: name.trim().to_string(),
+                    ingredients: vec![],
+                });
+            } else {
+                let category = categories.last_mut().ok_or_else(|| {
+                    format!("Ingredient outside of a category on line {}", number + 1)
+                })?;
+                let names = line
+                    .split('|')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect();
+                category.ingredients.push(names);
+            }
+        }
+        Ok(AisleConf { categories })
+    }
+
+    /// Name of the category the ingredient belongs to. Names and synonyms are compared
+    /// case-insensitively.
+    pub fn category_of(&self, ingredient: &str) -> Option<&str> {
+        let ingredient = ingredient.trim().to_lowercase();
+        self.categories
+            .iter()
+            .find(|category| {
+                category
+                    .ingredients
+                    .iter()
+                    .flatten()
+                    .any(|name| name.to_lowercase() == ingredient)
+            })
+            .map(|category| category.name.as_str())
+    }
+
+    /// Group the ingredient names by category.
+    ///
+    /// Categories are ordered as in the configuration and only contain the ingredients that
+    /// have been passed in. Unknown ingredients are put into the [OTHER] bucket, which is last.
+    pub fn categorize<'a, I>(&self, ingredients: I) -> IndexMap<String, Vec<String>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut result: IndexMap<String, Vec<String>> = self
+            .categories
+            .iter()
+            .map(|category| (category.name.clone(), vec![]))
+            .collect();
+        let mut other = vec![];
+        ingredients
+            .into_iter()
+            .for_each(|ingredient| match self.category_of(ingredient) {
+                Some(category) => result[category].push(ingredient.to_string()),
+                None => other.push(ingredient.to_string()),
+            });
+        result.retain(|_, ingredients| !ingredients.is_empty());
+        if !other.is_empty() {
+            result.insert(OTHER.to_string(), other);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aisle::{AisleConf, OTHER};
+
+    const CONF: &str = "\
+[fruit and veg]
+apple gala | apples
+aubergine
+
+[milk and dairy]
+butter
+egg | eggs
+";
+
+    #[test]
+    fn parse() {
+        let conf = AisleConf::parse(CONF).unwrap();
+        assert_eq!(conf.categories.len(), 2);
+        assert_eq!(conf.categories[0].name, "fruit and veg");
+        assert_eq!(
+            conf.categories[0].ingredients[0],
+            vec!["apple gala", "apples"]
+        );
+        assert_eq!(conf.category_of("Eggs"), Some("milk and dairy"));
+        assert_eq!(conf.category_of("salt"), None);
+
+        assert!(AisleConf::parse("butter\n[milk]").is_err());
+        assert!(AisleConf::parse("[milk\nbutter").is_err());
+    }
+
+    #[test]
+    fn categorize() {
+        let conf = AisleConf::parse(CONF).unwrap();
+        let categorized = conf.categorize(vec!["salt", "eggs", "apples", "butter"]);
+        let categories: Vec<&String> = categorized.keys().collect();
+        assert_eq!(categories, vec!["fruit and veg", "milk and dairy", OTHER]);
+        assert_eq!(categorized["milk and dairy"], vec!["eggs", "butter"]);
+        assert_eq!(categorized[OTHER], vec!["salt"]);
+    }
+}
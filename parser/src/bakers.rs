@@ -0,0 +1,204 @@
+//! Baker's percentages, the amounts of ingredients relative to the flour.
+//!
+//! [Recipe::bakers_percentages] computes the mass of every ingredient as percentage of the mass
+//! of a base ingredient, usually the flour, which is 100%. Ingredients measured in a unit which
+//! isn't a [mass](crate::units::PhysicalQuantity::Mass) are left out.
+//!
+//! Conversely, a recipe can be written in baker's percentages with the unit `percent`, like
+//! `@water{65%percent}`. [Recipe::resolve_bakers_percentages] turns them into grams for a mass of
+//! the base ingredient.
+//!
+//! ```text
+//! Mix @flour{100%percent}, @water{65%percent}, @salt{2%percent} and @yeast{1%percent}.
+//! ```
+
+use crate::units::{PhysicalQuantity, UnitRegistry};
+use crate::{Amount, Ingredient, Recipe};
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Unit of amounts written as baker's percentages.
+pub const PERCENT: &str = "percent";
+
+/// Baker's percentages of the ingredients of a [Recipe].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BakersPercentages {
+    /// Name of the base ingredient.
+    pub base: String,
+    /// Mass of the base ingredient in gram.
+    pub base_mass: f64,
+    /// Percentage of every ingredient measured by mass by its name, in order of the ingredients.
+    /// The base ingredient is 100.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, f64>")
+    )]
+    pub percentages: IndexMap<String, f64>,
+}
+
+impl Recipe {
+    /// Baker's percentages of the ingredients relative to the base ingredient, using the units
+    /// of the built-in [UnitRegistry].
+    ///
+    /// Only single amounts are taken into account. [None] if the base ingredient isn't measured
+    /// by mass.
+    pub fn bakers_percentages(&self, base: &str) -> Option<BakersPercentages> {
+        self.bakers_percentages_with(base, &UnitRegistry::default())
+    }
+
+    /// Baker's percentages of the ingredients relative to the base ingredient, using the units
+    /// of the registry. See [Recipe::bakers_percentages].
+    pub fn bakers_percentages_with(
+        &self,
+        base: &str,
+        registry: &UnitRegistry,
+    ) -> Option<BakersPercentages> {
+        let base_mass = mass(registry, self.metadata.ingredients.get(base)?)?;
+        if base_mass <= 0.0 {
+            return None;
+        }
+        let percentages = self
+            .metadata
+            .ingredients
+            .values()
+            .filter_map(|ingredient| {
+                let mass = mass(registry, ingredient)?;
+                Some((ingredient.name.clone(), mass / base_mass * 100.0))
+            })
+            .collect();
+        Some(BakersPercentages {
+            base: base.to_string(),
+            base_mass,
+            percentages,
+        })
+    }
+
+    /// Turn amounts in the unit [PERCENT] into grams, with `base_mass` grams of the base
+    /// ingredient.
+    pub fn resolve_bakers_percentages(&mut self, base_mass: f64) {
+        self.resolve_bakers_percentages_with(base_mass, &UnitRegistry::default())
+    }
+
+    /// Turn amounts in the unit [PERCENT] into grams, with `base_mass` grams of the base
+    /// ingredient, written with the symbol the registry uses for grams.
+    pub fn resolve_bakers_percentages_with(&mut self, base_mass: f64, registry: &UnitRegistry) {
+        let gram = registry.normalize("g");
+        let grams = |value: f64| value / 100.0 * base_mass;
+        let is_percent = |unit: &Option<String>| {
+            unit.as_deref()
+                .is_some_and(|unit| unit.eq_ignore_ascii_case(PERCENT))
+        };
+        let metadata = &mut self.metadata;
+        metadata
+            .ingredients_specifiers
+            .iter_mut()
+            .filter(|specifier| is_percent(&specifier.unit))
+            .for_each(|specifier| {
+                specifier.amount_in_step = specifier.amount_in_step.map(grams);
+                specifier.unit = Some(gram.clone());
+            });
+        metadata.ingredients.values_mut().for_each(|ingredient| {
+            ingredient
+                .quantities
+                .quantities
+                .iter_mut()
+                .filter(|quantity| is_percent(&quantity.unit))
+                .for_each(|quantity| {
+                    quantity.amount = quantity.amount.map(grams);
+                    quantity.unit = Some(gram.clone());
+                });
+            if is_percent(&ingredient.unit) {
+                ingredient.amount = ingredient.amount.as_ref().map(|amount| amount.map(grams));
+                ingredient.unit = Some(gram.clone());
+            }
+        });
+    }
+}
+
+/// Mass of the single amounts of the ingredient in gram. [None] if there are none.
+fn mass(registry: &UnitRegistry, ingredient: &Ingredient) -> Option<f64> {
+    let masses: Vec<f64> = ingredient
+        .quantities
+        .quantities
+        .iter()
+        .filter_map(|quantity| {
+            let value = match quantity.amount {
+                Amount::Single(value) => value,
+                _ => return None,
+            };
+            let unit = registry.get(quantity.unit.as_deref()?)?;
+            if unit.quantity != PhysicalQuantity::Mass {
+                return None;
+            }
+            registry.convert(value, &unit.symbol, "g")
+        })
+        .collect();
+    match masses.is_empty() {
+        true => None,
+        false => Some(masses.iter().sum()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::render::{to_markdown, RenderOptions};
+    use crate::units::UnitRegistry;
+
+    #[test]
+    fn bakers_percentages() {
+        let recipe = parse(
+            "Mix @flour{400%g} and @flour{1/10%kg} with @water{325%g}, @salt{10%g}, \
+             @oil{2%tbsp} and @yeast{1%pinch}.\n",
+        )
+        .unwrap();
+        let percentages = recipe.bakers_percentages("flour").unwrap();
+        assert_eq!(percentages.base_mass, 500.0);
+        let percentages: Vec<(&str, f64)> = percentages
+            .percentages
+            .iter()
+            .map(|(name, percentage)| (name.as_str(), *percentage))
+            .collect();
+        assert_eq!(
+            percentages,
+            vec![("flour", 100.0), ("water", 65.0), ("salt", 2.0)]
+        );
+        assert!(recipe.bakers_percentages("oil").is_none());
+        assert!(recipe.bakers_percentages("sugar").is_none());
+
+        let recipe = parse("Mix @flour{500%g} and @salt{10%Gramm}.\n").unwrap();
+        let mut registry = UnitRegistry::default();
+        registry.add_alias("Gramm", "g");
+        assert!(recipe
+            .bakers_percentages("flour")
+            .unwrap()
+            .percentages
+            .get("salt")
+            .is_none());
+        let percentages = recipe.bakers_percentages_with("flour", &registry).unwrap();
+        assert_eq!(percentages.percentages["salt"], 2.0);
+
+        let mut recipe =
+            parse("Mix @flour{100%percent}, @water{65%percent} and @salt{2%Percent}.\n").unwrap();
+        recipe.resolve_bakers_percentages(500.0);
+        let water = &recipe.metadata.ingredients["water"];
+        assert_eq!(water.amount, Some(crate::Amount::Single(325.0)));
+        assert_eq!(water.unit.as_deref(), Some("g"));
+        assert_eq!(
+            recipe.metadata.ingredients_specifiers[2].unit.as_deref(),
+            Some("g")
+        );
+        let percentages = recipe.bakers_percentages("flour").unwrap();
+        assert_eq!(percentages.percentages["salt"], 2.0);
+
+        let options = RenderOptions {
+            bakers_percentages: Some("flour".to_string()),
+            ..RenderOptions::default()
+        };
+        let markdown = to_markdown(&recipe, &options);
+        assert!(markdown.contains("- 325 g water (65%)\n"));
+    }
+}
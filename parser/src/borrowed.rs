@@ -0,0 +1,417 @@
+//! Zero-copy parsing into a [Recipe] borrowing from the source.
+//!
+//! [parse_borrowed] doesn't allocate for names, units, notes and text, which are slices of the
+//! source. Only text containing escaped characters, like `\@`, is copied. This suits servers
+//! parsing many recipes just to read a few parts of them.
+//!
+//! Mentions of ingredients are read by the same code as in [parse_with](crate::parse_with), with
+//! the default [Separators](crate::Separators), so names, amounts and units agree with it.
+//! Compared to it, the borrowed recipe is more raw:
+//!
+//! * ingredients aren't aggregated, [Recipe::ingredients] lists every mentioning,
+//! * the front matter isn't parsed, see [Recipe::front_matter],
+//! * ingredient modifiers and recipe references are always recognized, temperatures are not,
+//! * units aren't normalized and there are no warnings,
+//! * text of a step may be split into several [Item::Text]s.
+
+use crate::{ingredient_parts, timer_value, Amount, CookParser, ParseError, Rule, Separators};
+use pest::iterators::Pair;
+use pest::Parser;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A recipe borrowing from its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe<'a> {
+    /// The source of the recipe.
+    pub source: &'a str,
+    /// The YAML between the `---` lines at the start.
+    pub front_matter: Option<&'a str>,
+    /// Keys and values of the `>>` lines, in order.
+    pub metadata: Vec<(&'a str, &'a str)>,
+    /// Every mentioning of an ingredient, in order.
+    pub ingredients: Vec<Ingredient<'a>>,
+    /// Every mentioning of cookware, in order.
+    pub cookware: Vec<&'a str>,
+    /// Every timer, in order.
+    pub timers: Vec<Timer<'a>>,
+    /// The steps, separated by blank lines.
+    pub steps: Vec<Step<'a>>,
+    /// The sections grouping the steps, see [Section](crate::Section).
+    pub sections: Vec<Section<'a>>,
+}
+
+/// A mentioning of an ingredient, like [IngredientSpecifier](crate::IngredientSpecifier).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ingredient<'a> {
+    /// Name of the ingredient, or path of the referenced recipe.
+    pub name: &'a str,
+    /// The amount, if there is a valid one.
+    pub amount: Option<Amount>,
+    /// Unit of the amount.
+    pub unit: Option<&'a str>,
+    /// Preparation note, like `washed` in `@apples{2}(washed)`.
+    pub note: Option<&'a str>,
+    /// Reuse of an ingredient mentioned before, like `@&pasta water{}`.
+    pub reference: bool,
+    /// Optional ingredient, like `@?parsley{}`.
+    pub optional: bool,
+    /// Ingredient which is not part of the step text, like `@-salt{}`.
+    pub hidden: bool,
+    /// Reference to another recipe, like `@./sauces/Marinara{200%g}`.
+    pub recipe: bool,
+}
+
+/// A timer like `~{10%minutes}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timer<'a> {
+    /// The amount of time.
+    pub amount: f64,
+    /// Unit of the time, empty if there is none.
+    pub unit: &'a str,
+    /// The upper end of a range like `~{1-2%hours}`.
+    pub max: Option<f64>,
+}
+
+/// A step of the [Recipe].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step<'a> {
+    /// Number of the step, starting at 1.
+    pub number: usize,
+    /// Text and mentionings of the step in order.
+    pub items: Vec<Item<'a>>,
+}
+
+/// Part of a [Step].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item<'a> {
+    /// Text of the step.
+    Text(Cow<'a, str>),
+    /// Index in [Recipe::ingredients]. Hidden ingredients are left out.
+    Ingredient(usize),
+    /// Index in [Recipe::cookware].
+    Cookware(usize),
+    /// Index in [Recipe::timers].
+    Timer(usize),
+    /// A note, written as a line starting with `>`.
+    Note(Cow<'a, str>),
+}
+
+/// A group of [Step]s started by a header like `== Dough ==`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section<'a> {
+    /// Name of the section. [None] for the steps before the first header and for headers
+    /// without a name.
+    pub name: Option<Cow<'a, str>>,
+    /// Range of the steps of this section in [Recipe::steps].
+    pub steps: Range<usize>,
+}
+
+/// Parse the source into a [Recipe] borrowing from it. Only fails if the source can't be read
+/// at all, everything that isn't understood is treated as text.
+pub fn parse_borrowed(source: &str) -> Result<Recipe<'_>, ParseError> {
+    let cook_lang = CookParser::parse(Rule::cook_lang, source)?.next().unwrap();
+    let mut recipe = Recipe {
+        source,
+        front_matter: None,
+        metadata: vec![],
+        ingredients: vec![],
+        cookware: vec![],
+        timers: vec![],
+        steps: vec![],
+        sections: vec![],
+    };
+    let mut items = vec![];
+    // Whether the current line has instructions, or is a metadata line, which doesn't end a step.
+    let mut content = false;
+    let mut metadata = false;
+    let mut position = 0;
+    for pair in cook_lang.into_inner() {
+        let span = pair.as_span();
+        source[position..span.start()].matches('\n').for_each(|_| {
+            if !content && !metadata {
+                recipe.push_step(&mut items);
+            }
+            content = false;
+            metadata = false;
+        });
+        position = span.end();
+        match pair.as_rule() {
+            Rule::front_matter => {
+                recipe.front_matter = Some(pair.into_inner().next().unwrap().as_str());
+            }
+            Rule::metadata => {
+                let property = pair.into_inner().next().unwrap();
+                let is_servings = property.as_rule() == Rule::servings;
+                let mut key_value = property.into_inner();
+                let key = if is_servings {
+                    "servings"
+                } else {
+                    key_value.next().unwrap().as_str()
+                };
+                let value = key_value.next().unwrap().as_str().trim();
+                recipe.metadata.push((key, value));
+                metadata = true;
+            }
+            Rule::invalid_metadata => metadata = true,
+            Rule::section => {
+                recipe.push_step(&mut items);
+                let name = pair.into_inner().next().unwrap().as_str();
+                let name = unescape(name.trim());
+                recipe.sections.push(Section {
+                    name: Some(name).filter(|name| !name.is_empty()),
+                    steps: recipe.steps.len()..recipe.steps.len(),
+                });
+                content = true;
+            }
+            Rule::note => {
+                items.push(Item::Note(unescape(pair.as_str()[1..].trim())));
+                content = true;
+            }
+            Rule::line => content = recipe.add_line(pair, &mut items),
+            _ => {}
+        }
+    }
+    recipe.push_step(&mut items);
+    Ok(recipe)
+}
+
+impl<'a> Recipe<'a> {
+    /// Add the items of a line to the current step. Returns whether the line isn't blank.
+    fn add_line(&mut self, line: Pair<'a, Rule>, items: &mut Vec<Item<'a>>) -> bool {
+        let source = self.source;
+        let ingredients = self.ingredients.len();
+        let mut line_items = vec![];
+        let mut position = line.as_span().start();
+        for part in line.clone().into_inner() {
+            let text = &source[position..part.as_span().start()];
+            line_items.push(Item::Text(unescape(text)));
+            position = part.as_span().end();
+            match part.as_rule() {
+                Rule::ingredient => self.add_ingredient(part, &mut line_items),
+                Rule::cookware => {
+                    let name = name(&part);
+                    line_items.push(Item::Cookware(self.cookware.len()));
+                    self.cookware.push(name);
+                }
+                Rule::timer => {
+                    let (amount, max, unit) = timer_value(part).unwrap_or_else(|first| first);
+                    let timer = Timer { amount, unit, max };
+                    line_items.push(Item::Timer(self.timers.len()));
+                    self.timers.push(timer);
+                }
+                Rule::unknown => line_items.push(Item::Text(Cow::Borrowed(part.as_str()))),
+                _ => {}
+            }
+        }
+        let end = line.as_span().end();
+        line_items.push(Item::Text(unescape(&source[position..end])));
+
+        // Leave out empty text and trim the line.
+        line_items.retain(|item| !matches!(item, Item::Text(text) if text.is_empty()));
+        while let Some(Item::Text(text)) = line_items.first_mut() {
+            *text = trim(text, str::trim_start);
+            if !text.is_empty() {
+                break;
+            }
+            line_items.remove(0);
+        }
+        while let Some(Item::Text(text)) = line_items.last_mut() {
+            *text = trim(text, str::trim_end);
+            if !text.is_empty() {
+                break;
+            }
+            line_items.pop();
+        }
+        if line_items.is_empty() {
+            // Hidden ingredients are part of the step, even without items.
+            return self.ingredients.len() > ingredients;
+        }
+        match items.last() {
+            None | Some(Item::Note(_)) => {}
+            Some(_) => items.push(Item::Text(Cow::Borrowed(" "))),
+        }
+        items.extend(line_items);
+        true
+    }
+
+    fn add_ingredient(&mut self, pair: Pair<'a, Rule>, items: &mut Vec<Item<'a>>) {
+        let text = pair.as_str();
+        let braces = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => "",
+        };
+        let parts = ingredient_parts(pair, &Separators::default());
+        let name = match (parts.words.first(), parts.words.last()) {
+            (Some(first), Some(last)) => &self.source[first.start()..last.end()],
+            _ => "",
+        };
+        let mut ingredient = Ingredient {
+            name,
+            amount: parts.amount,
+            unit: parts.unit,
+            note: parts.note,
+            reference: parts.modifiers.contains('&'),
+            optional: parts.modifiers.contains('?'),
+            hidden: parts.modifiers.contains('-'),
+            recipe: parts.recipe,
+        };
+        ingredient.reference &= self
+            .ingredients
+            .iter()
+            .any(|before| !before.recipe && before.name == ingredient.name);
+        if !ingredient.hidden {
+            items.push(Item::Ingredient(self.ingredients.len()));
+        }
+        // An invalid amount is kept as text.
+        if parts.invalid_amount {
+            items.push(Item::Text(Cow::Borrowed(braces)));
+        }
+        self.ingredients.push(ingredient);
+    }
+
+    fn push_step(&mut self, items: &mut Vec<Item<'a>>) {
+        if items.is_empty() {
+            return;
+        }
+        if self.sections.is_empty() {
+            self.sections.push(Section {
+                name: None,
+                steps: 0..0,
+            });
+        }
+        self.steps.push(Step {
+            number: self.steps.len() + 1,
+            items: std::mem::take(items),
+        });
+        self.sections.last_mut().unwrap().steps.end = self.steps.len();
+    }
+}
+
+/// Name of cookware, from the first to the last word.
+fn name<'a>(pair: &Pair<'a, Rule>) -> &'a str {
+    let mut words = pair
+        .clone()
+        .into_inner()
+        .filter(|part| matches!(part.as_rule(), Rule::name | Rule::text));
+    let first = match words.next() {
+        Some(first) => first.as_span(),
+        None => return "",
+    };
+    let end = words
+        .last()
+        .map_or(first.end(), |last| last.as_span().end());
+    &pair.get_input()[first.start()..end]
+}
+
+/// Text with escaped `@`, `#` and `~` resolved, only copied if there are any.
+fn unescape(text: &str) -> Cow<'_, str> {
+    if ["\\@", "\\#", "\\~"]
+        .iter()
+        .any(|escaped| text.contains(escaped))
+    {
+        Cow::Owned(
+            text.replace("\\@", "@")
+                .replace("\\#", "#")
+                .replace("\\~", "~"),
+        )
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Trim text without copying borrowed text.
+fn trim<'a>(text: &Cow<'a, str>, trim: fn(&str) -> &str) -> Cow<'a, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(trim(text)),
+        Cow::Owned(text) => Cow::Owned(trim(text).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::borrowed::{parse_borrowed, Item};
+    use crate::{parse_with, Amount, ParseOptions};
+    use std::borrow::Cow;
+
+    #[test]
+    fn parse_borrowed_recipe() {
+        let source = "\
+>> servings: 2|4
+Mix @flour{200%g} with @?fresh  basil{1/2%bunch}(chopped) // tasty
+and @-salt in a #large bowl{} for ~{2%min}.
+
+== Sauce ==
+Warm @&flour{}, mail \\@chef and @milk{1|2*} with @sugar{1,5%g}.
+> Stir well
+";
+        let recipe = parse_borrowed(source).unwrap();
+        assert_eq!(recipe.metadata, vec![("servings", "2|4")]);
+        let names: Vec<&str> = recipe.ingredients.iter().map(|i| i.name).collect();
+        assert_eq!(
+            names,
+            vec!["flour", "fresh  basil", "salt", "flour", "milk", "sugar"]
+        );
+        let basil = &recipe.ingredients[1];
+        assert_eq!(basil.amount, Some(Amount::Single(0.5)));
+        assert_eq!((basil.unit, basil.note), (Some("bunch"), Some("chopped")));
+        assert!(basil.optional && recipe.ingredients[2].hidden);
+        assert!(recipe.ingredients[3].reference);
+        assert_eq!(recipe.ingredients[4].amount, None);
+        assert!(recipe.steps[1].items.contains(&Item::Text("{1|2*}".into())));
+        let sugar = &recipe.ingredients[5];
+        assert_eq!((&sugar.amount, sugar.unit), (&None, None));
+        assert_eq!(recipe.cookware, vec!["large bowl"]);
+        assert_eq!(
+            (recipe.timers[0].amount, recipe.timers[0].unit),
+            (2.0, "min")
+        );
+        assert_eq!(recipe.sections.len(), 2);
+        assert_eq!(recipe.sections[1].name.as_deref(), Some("Sauce"));
+        assert!(recipe.steps[1]
+            .items
+            .contains(&Item::Text(Cow::Owned(", mail @chef and ".to_string()))));
+        assert_eq!(
+            recipe.steps[1].items.last(),
+            Some(&Item::Note("Stir well".into()))
+        );
+
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let owned = parse_with(source, &options).unwrap().recipe;
+        assert_eq!(owned.steps.len(), recipe.steps.len());
+        let text = |items: &[Item]| {
+            items
+                .iter()
+                .map(|item| match item {
+                    Item::Text(text) => text.to_string(),
+                    Item::Note(note) => format!("[{}]", note),
+                    _ => "_".to_string(),
+                })
+                .collect::<String>()
+        };
+        let owned_text = |items: &[crate::Item]| {
+            items
+                .iter()
+                .map(|item| match item {
+                    crate::Item::Text(text) => text.to_string(),
+                    crate::Item::Note(note) => format!("[{}]", note),
+                    _ => "_".to_string(),
+                })
+                .collect::<String>()
+        };
+        assert_eq!(
+            text(&recipe.steps[0].items),
+            "Mix _ with _ and  in a _ for _."
+        );
+        recipe
+            .steps
+            .iter()
+            .zip(&owned.steps)
+            .for_each(|(step, owned)| {
+                assert_eq!(text(&step.items), owned_text(&owned.items));
+            });
+    }
+}
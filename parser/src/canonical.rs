@@ -0,0 +1,459 @@
+//! Conformance harness for the canonical tests shipped with the [CookLang spec](https://github.com/cooklang/spec).
+//!
+//! The spec repository contains `tests/canonical.yaml` which describes, for a list of named test
+//! cases, the recipe source and the expected structured result. A [Suite] is loaded from that
+//! file, every [Case] is parsed with [crate::parse] and the result is compared against the
+//! expected [CanonicalRecipe].
+//!
+//! The canonical structure is also the JSON shape of other CookLang parsers like cooklang-ts and
+//! cooklang-swift. Use [serialize] or, with the `json` feature, [Recipe::to_canonical_json] to
+//! write a [Recipe] in it.
+//!
+//! Enable the `canonical` feature to use this module from outside of the crate.
+
+use crate::{Amount, Recipe};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Numbers are compared with this tolerance, as the spec rounds fractions like 1/3.
+const TOLERANCE: f64 = 0.001;
+
+/// A full canonical test file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Suite {
+    /// Version of the canonical test format.
+    pub version: u32,
+    /// Test cases indexed by their name.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Case>")
+    )]
+    pub tests: IndexMap<String, Case>,
+}
+
+/// A single canonical test case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Case {
+    /// CookLang source of the test.
+    pub source: String,
+    /// Result expected from parsing [Case::source].
+    pub result: CanonicalRecipe,
+}
+
+/// The structured form of a recipe used by the canonical tests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CanonicalRecipe {
+    /// Steps of the recipe, each made of a list of [Item]s.
+    pub steps: Vec<Vec<Item>>,
+    /// Metadata of the recipe. Older versions of the spec write an empty metadata as `[]`.
+    #[serde(deserialize_with = "deserialize_metadata")]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Value>")
+    )]
+    pub metadata: IndexMap<String, Value>,
+}
+
+/// A part of a step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Item {
+    /// Plain text.
+    Text { value: String },
+    /// Mentioning of an ingredient.
+    Ingredient {
+        name: String,
+        quantity: Value,
+        units: String,
+    },
+    /// Mentioning of a cookware.
+    Cookware {
+        name: String,
+        #[serde(default = "Value::one")]
+        quantity: Value,
+    },
+    /// Mentioning of a timer.
+    Timer {
+        #[serde(default)]
+        name: String,
+        quantity: Value,
+        units: String,
+    },
+}
+
+/// A quantity or metadata value, which is either a number or text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+/// A [Case] of a [Suite] that did not produce the expected result.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    /// Name of the failed test case.
+    pub name: String,
+    /// Human readable reason of the failure.
+    pub reason: String,
+}
+
+impl Suite {
+    /// Load a suite from the content of a `canonical.yaml`.
+    pub fn from_yaml(inp: &str) -> Result<Suite, serde_yaml::Error> {
+        serde_yaml::from_str(inp)
+    }
+
+    /// Run every case of the suite and return the failed ones.
+    pub fn run(&self) -> Vec<Failure> {
+        self.tests
+            .iter()
+            .filter_map(|(name, case)| {
+                case.check().err().map(|reason| Failure {
+                    name: name.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Case {
+    /// Parse the source of this case and compare it to the expected result.
+    pub fn check(&self) -> Result<(), String> {
+        let parsed = catch_unwind(AssertUnwindSafe(|| crate::parse(&self.source)))
+            .map_err(|_| "parser panicked".to_string())?
+            .map_err(|e| format!("parser failed: {}", e))?;
+        let actual = to_canonical(&parsed);
+        if actual.matches(&self.result) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, got {:?}", self.result, actual))
+        }
+    }
+}
+
+impl CanonicalRecipe {
+    /// Compare two canonical recipes, allowing small differences in numbers.
+    pub fn matches(&self, other: &CanonicalRecipe) -> bool {
+        self.steps.len() == other.steps.len()
+            && self.steps.iter().zip(other.steps.iter()).all(|(a, b)| {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.matches(b))
+            })
+            && self.metadata.len() == other.metadata.len()
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| match other.metadata.get(key) {
+                    Some(other) => value.matches(other),
+                    None => false,
+                })
+    }
+}
+
+impl Item {
+    /// Compare two items, allowing small differences in numbers.
+    pub fn matches(&self, other: &Item) -> bool {
+        match (self, other) {
+            (Item::Text { value: a }, Item::Text { value: b }) => a == b,
+            (
+                Item::Ingredient {
+                    name: a_name,
+                    quantity: a_quantity,
+                    units: a_units,
+                },
+                Item::Ingredient {
+                    name: b_name,
+                    quantity: b_quantity,
+                    units: b_units,
+                },
+            ) => a_name == b_name && a_quantity.matches(b_quantity) && a_units == b_units,
+            (
+                Item::Cookware {
+                    name: a_name,
+                    quantity: a_quantity,
+                },
+                Item::Cookware {
+                    name: b_name,
+                    quantity: b_quantity,
+                },
+            ) => a_name == b_name && a_quantity.matches(b_quantity),
+            (
+                Item::Timer {
+                    name: a_name,
+                    quantity: a_quantity,
+                    units: a_units,
+                },
+                Item::Timer {
+                    name: b_name,
+                    quantity: b_quantity,
+                    units: b_units,
+                },
+            ) => a_name == b_name && a_quantity.matches(b_quantity) && a_units == b_units,
+            _ => false,
+        }
+    }
+}
+
+impl Value {
+    fn one() -> Value {
+        Value::Number(1.0)
+    }
+
+    /// Compare two values. Numbers written as text are compared as numbers.
+    pub fn matches(&self, other: &Value) -> bool {
+        match (self.as_number(), other.as_number()) {
+            (Some(a), Some(b)) => (a - b).abs() < TOLERANCE,
+            _ => self.as_text() == other.as_text(),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Text(t) => t.trim().parse().ok(),
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Text(t) => t.clone(),
+        }
+    }
+}
+
+fn deserialize_metadata<'de, D>(deserializer: D) -> Result<IndexMap<String, Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Metadata {
+        Map(IndexMap<String, Value>),
+        List(Vec<(String, Value)>),
+    }
+    Ok(match Metadata::deserialize(deserializer)? {
+        Metadata::Map(map) => map,
+        Metadata::List(list) => list.into_iter().collect(),
+    })
+}
+
+/// Convert a parsed [Recipe] into the structure used by the canonical tests.
+///
+/// Steps are separated by blank lines, lines of the same step are joined by a space and metadata
+/// lines are skipped.
+pub fn to_canonical(recipe: &Recipe) -> CanonicalRecipe {
+    let steps = recipe
+        .steps()
+        .iter()
+        .map(|step| {
+            step.items
+                .iter()
+                .filter_map(|item| match item {
+                    crate::Item::Text(value) => Some(Item::Text {
+                        value: value.clone(),
+                    }),
+                    crate::Item::Ingredient(index) => {
+                        let specifier = &recipe.metadata.ingredients_specifiers[*index];
+                        let units = specifier
+                            .quantity()
+                            .and_then(|quantity| quantity.unit)
+                            .unwrap_or_default();
+                        Some(Item::Ingredient {
+                            name: specifier.ingredient.clone(),
+                            quantity: amount_to_value(&specifier.amount_in_step),
+                            units,
+                        })
+                    }
+                    crate::Item::Cookware(index) => Some(Item::Cookware {
+                        name: recipe.metadata.cookware[*index].clone(),
+                        quantity: Value::one(),
+                    }),
+                    crate::Item::Timer(index) => {
+                        let timer = &recipe.metadata.timer[*index];
+                        Some(Item::Timer {
+                            name: String::new(),
+                            quantity: Value::Number(timer.amount),
+                            units: timer.unit.clone(),
+                        })
+                    }
+                    crate::Item::Temperature { value, unit } => Some(Item::Text {
+                        value: format!("{}{}", value, unit),
+                    }),
+                    crate::Item::Note(_) | crate::Item::RecipeRef { .. } => None,
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut metadata: IndexMap<String, Value> = recipe
+        .metadata
+        .entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), Value::Text(value.to_string())))
+        .collect();
+    if let Some(servings) = &recipe.metadata.servings {
+        let servings: Vec<String> = servings.iter().map(|s| s.to_string()).collect();
+        metadata.insert("servings".to_string(), Value::Text(servings.join("|")));
+    }
+
+    CanonicalRecipe { steps, metadata }
+}
+
+/// Serialize the [Recipe] in the canonical structure, see [to_canonical]. Can be used as
+/// `#[serde(serialize_with = "cook_with_rust_parser::canonical::serialize")]`.
+pub fn serialize<S: Serializer>(recipe: &Recipe, serializer: S) -> Result<S::Ok, S::Error> {
+    to_canonical(recipe).serialize(serializer)
+}
+
+impl Recipe {
+    /// The recipe as JSON in the canonical structure, see [to_canonical].
+    #[cfg(any(test, feature = "json"))]
+    pub fn to_canonical_json(&self) -> String {
+        serde_json::to_string(&to_canonical(self)).unwrap()
+    }
+}
+
+fn amount_to_value(amount: &Amount) -> Value {
+    match amount {
+        Amount::Single(d) if *d == 0.0 => Value::Text("some".to_string()),
+        Amount::Single(d) | Amount::Multi(d) => Value::Number(*d),
+        Amount::PerServing(dd) => {
+            let servings: Vec<String> = dd.iter().map(|d| d.to_string()).collect();
+            Value::Text(servings.join("|"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonical::{to_canonical, Item, Suite};
+    use crate::parse;
+    use serde_json::json;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn inline_suite() {
+        let suite = Suite::from_yaml(
+            r#"
+version: 5
+tests:
+  testBasicDirection:
+    source: |
+      Add a bit of chilli
+    result:
+      steps:
+        -
+          - type: text
+            value: "Add a bit of chilli"
+      metadata: []
+  testIngridentExplicitUnits:
+    source: |
+      @chilli{3%items}
+    result:
+      steps:
+        -
+          - type: ingredient
+            name: "chilli"
+            quantity: 3
+            units: "items"
+      metadata: []
+  testTimerInteger:
+    source: |
+      Fry for ~{10%minutes}
+
+      Serve
+    result:
+      steps:
+        -
+          - type: text
+            value: "Fry for "
+          - type: timer
+            quantity: 10
+            units: "minutes"
+            name: ""
+        -
+          - type: text
+            value: "Serve"
+      metadata: {}
+"#,
+        )
+        .unwrap();
+
+        let failures = suite.run();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+
+    #[test]
+    fn canonical_json() {
+        let recipe = parse(">> servings: 2\nAdd @salt{1%pinch} to the #pot.\n").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&recipe.to_canonical_json()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "steps": [[
+                    {"type": "text", "value": "Add "},
+                    {"type": "ingredient", "name": "salt", "quantity": 1.0, "units": "pinch"},
+                    {"type": "text", "value": " to the "},
+                    {"type": "cookware", "name": "pot", "quantity": 1.0},
+                    {"type": "text", "value": "."},
+                ]],
+                "metadata": {"servings": "2"},
+            })
+        );
+
+        let recipe = parse("Add @flour{1%cup}, then @flour{100%g}.\n").unwrap();
+        let canonical = to_canonical(&recipe);
+        let units: Vec<&str> = canonical.steps[0]
+            .iter()
+            .filter_map(|item| match item {
+                Item::Ingredient { units, .. } => Some(units.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(units, vec!["cup", "g"]);
+    }
+
+    /// Cases of the spec the parser doesn't pass yet. Remove a case once it passes.
+    const KNOWN_FAILURES: &[&str] = &[
+        "testComments",
+        "testCommentsAfterIngredients",
+        "testCommentsWithIngredients",
+        "testEquipmentMultipleWordsWithLeadingNumber",
+        "testEquipmentMultipleWordsWithSpaces",
+        "testEquipmentQuantity",
+        "testEquipmentQuantityMultipleWords",
+        "testEquipmentQuantityOneWord",
+        "testFractionsLike",
+        "testFractionsWithSpaces",
+        "testIngredientMultipleWordsWithLeadingNumber",
+        "testIngredientWithEmoji",
+        "testIngridentExplicitUnitsWithSpaces",
+        "testMetadataMultiwordKey",
+        "testMetadataMultiwordKeyWithSpaces",
+        "testMultipleLines",
+        "testQuantityAsText",
+        "testQuantityDigitalString",
+        "testTimerWithName",
+    ];
+
+    #[test]
+    fn spec_canonical() {
+        let inp = read_to_string("../spec/tests/canonical.yaml").unwrap();
+        let suite = Suite::from_yaml(&inp).unwrap();
+
+        let failures: Vec<_> = suite
+            .run()
+            .into_iter()
+            .filter(|failure| !KNOWN_FAILURES.contains(&failure.name.as_str()))
+            .collect();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+}
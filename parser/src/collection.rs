@@ -0,0 +1,785 @@
+//! Loading a directory of recipes.
+//!
+//! Every `.cook` file in the directory tree is parsed into a [CollectionRecipe]. Files that can't
+//! be read or parsed are reported as [CollectionError]s without stopping the others from loading.
+//! With the `rayon` feature, `Collection::load_dir_parallel` parses the files on multiple threads.
+//!
+//! Images are found next to the recipe file with the same name, like `Pancakes.jpg` for
+//! `Pancakes.cook`. Images of single steps are numbered starting at 1, like `Pancakes.2.jpg`.
+//!
+//! Recipe references like `@./sauces/Marinara{200%g}` are resolved relative to the directory of
+//! the referencing recipe, see [Collection::resolve]. [Collection::flatten] adds the ingredients
+//! of the referenced recipes to the referencing one, e.g. for a shopping list.
+
+use crate::substitutions::merge_ingredients;
+use crate::units::UnitRegistry;
+use crate::{parse_with, Amount, ParseError, ParseOptions, Quantity, Recipe};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// File extension of recipes.
+pub const RECIPE_EXTENSION: &str = "cook";
+
+/// File extensions of images, in order of preference.
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// The recipes of a directory tree.
+#[derive(Debug, Default)]
+pub struct Collection {
+    /// The loaded directory.
+    pub root: PathBuf,
+    /// Recipes ordered by their path.
+    pub recipes: Vec<CollectionRecipe>,
+    /// Problems with single files.
+    pub errors: Vec<CollectionError>,
+}
+
+/// A recipe of a [Collection].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionRecipe {
+    /// Path of the recipe file relative to [Collection::root].
+    pub path: PathBuf,
+    /// The parsed recipe.
+    pub recipe: Recipe,
+    /// Image of the whole recipe.
+    pub image: Option<PathBuf>,
+    /// Images of single steps, by step number.
+    pub step_images: Vec<(usize, PathBuf)>,
+    /// Resolved recipe references in the order they are mentioned. Indices point into
+    /// [Collection::recipes], [None] if the referenced recipe doesn't exist.
+    pub references: Vec<Option<usize>>,
+}
+
+/// A file of a [Collection] that couldn't be loaded completely.
+#[derive(Debug)]
+pub enum CollectionError {
+    /// Reading the file or directory failed.
+    Io { path: PathBuf, error: io::Error },
+    /// Parsing the recipe failed.
+    Parse { path: PathBuf, error: ParseError },
+    /// A recipe reference points to a recipe which is not part of the collection.
+    MissingReference { path: PathBuf, reference: String },
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectionError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            CollectionError::Parse { path, error } => write!(f, "{}: {}", path.display(), error),
+            CollectionError::MissingReference { path, reference } => write!(
+                f,
+                "{}: Referenced recipe {} not found",
+                path.display(),
+                reference
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CollectionError {}
+
+impl CollectionError {
+    /// Path of the file, relative to [Collection::root].
+    pub fn path(&self) -> &Path {
+        match self {
+            CollectionError::Io { path, .. }
+            | CollectionError::Parse { path, .. }
+            | CollectionError::MissingReference { path, .. } => path,
+        }
+    }
+}
+
+/// A change of a [Collection] after [reloading](Collection::reload) a file. Paths are relative
+/// to [Collection::root].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CollectionEvent {
+    /// A new recipe has been loaded.
+    Added(PathBuf),
+    /// A recipe has been loaded again.
+    Updated(PathBuf),
+    /// The file of a recipe is gone.
+    Removed(PathBuf),
+    /// The recipe file couldn't be loaded, see [Collection::errors]. A recipe loaded before is
+    /// kept.
+    Failed(PathBuf),
+}
+
+impl CollectionRecipe {
+    /// Name of the recipe file without the extension.
+    pub fn name(&self) -> &str {
+        self.path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+    }
+
+    /// The [title](Recipe::title) of the recipe, or its [name](CollectionRecipe::name).
+    pub fn title(&self) -> &str {
+        self.recipe.title().unwrap_or_else(|| self.name())
+    }
+
+    /// Paths of the recipes referenced by this recipe, as they are written.
+    pub fn reference_paths(&self) -> impl Iterator<Item = &str> {
+        self.recipe
+            .metadata
+            .ingredients_specifiers
+            .iter()
+            .filter(|specifier| specifier.recipe)
+            .map(|specifier| specifier.ingredient.as_str())
+    }
+}
+
+impl Collection {
+    /// Load all recipes below the directory. Recipe references are enabled, see
+    /// [ParseOptions::extensions].
+    pub fn load_dir(path: impl AsRef<Path>) -> io::Result<Collection> {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        Collection::load_dir_with(path, &options)
+    }
+
+    /// Load all recipes below the directory, parsing them with the options.
+    ///
+    /// Fails only if the directory itself can't be read.
+    pub fn load_dir_with(path: impl AsRef<Path>, options: &ParseOptions) -> io::Result<Collection> {
+        let (mut collection, files) = Collection::list_dir(path)?;
+        let loaded = recipe_paths(&files)
+            .map(|path| load_recipe(&collection.root, path, &files, options))
+            .collect();
+        collection.add_loaded(loaded);
+        Ok(collection)
+    }
+
+    /// Load all recipes below the directory like [Collection::load_dir_with], parsing the files
+    /// on all threads of the rayon thread pool. The result is the same, in the same order.
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn load_dir_parallel(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> io::Result<Collection> {
+        use rayon::prelude::*;
+
+        let (mut collection, files) = Collection::list_dir(path)?;
+        let paths: Vec<&PathBuf> = recipe_paths(&files).collect();
+        let loaded = paths
+            .into_par_iter()
+            .map(|path| load_recipe(&collection.root, path, &files, options))
+            .collect();
+        collection.add_loaded(loaded);
+        Ok(collection)
+    }
+
+    /// An empty collection of the directory and all files below it.
+    fn list_dir(path: impl AsRef<Path>) -> io::Result<(Collection, Vec<PathBuf>)> {
+        let root = path.as_ref().to_path_buf();
+        let mut collection = Collection {
+            root: root.clone(),
+            ..Collection::default()
+        };
+        // Only the root itself failing to read is an error of the whole collection.
+        fs::read_dir(&root)?;
+        let mut files = vec![];
+        collect_files(&root, Path::new(""), &mut files, &mut collection.errors);
+        Ok((collection, files))
+    }
+
+    fn add_loaded(&mut self, loaded: Vec<Result<CollectionRecipe, CollectionError>>) {
+        loaded.into_iter().for_each(|recipe| match recipe {
+            Ok(recipe) => self.recipes.push(recipe),
+            Err(error) => self.errors.push(error),
+        });
+        self.resolve_references();
+    }
+
+    /// Load the recipe file again after it has been changed, added or removed, e.g. when told so
+    /// by a file watcher. A directory is reloaded with all recipes below it. The path is either
+    /// relative to [Collection::root] or below it.
+    ///
+    /// Recipe references are resolved again. Images are only found again with their recipe.
+    pub fn reload(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Vec<CollectionEvent> {
+        let path = path.as_ref();
+        // File watchers might report the canonical path.
+        let canonical = fs::canonicalize(&self.root).ok();
+        let relative = path.strip_prefix(&self.root).ok().or_else(|| {
+            let canonical = canonical.as_ref()?;
+            path.strip_prefix(canonical).ok()
+        });
+        let path = normalize(relative.unwrap_or(path));
+        let mut paths: Vec<PathBuf> = self
+            .recipes
+            .iter()
+            .map(|recipe| recipe.path.clone())
+            .filter(|recipe| recipe.starts_with(&path))
+            .collect();
+        let mut files = vec![];
+        if self.root.join(&path).is_dir() {
+            collect_files(&self.root, &path, &mut files, &mut vec![]);
+        } else if let Some(directory) = path.parent() {
+            // The images of a recipe are next to it.
+            if let Ok(entries) = fs::read_dir(self.root.join(directory)) {
+                let names = entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.file_name());
+                files.extend(names.map(|name| directory.join(name)));
+            }
+            paths.push(path.clone());
+        }
+        recipe_paths(&files)
+            .filter(|file| file.starts_with(&path))
+            .for_each(|file| paths.push(file.clone()));
+        paths.sort();
+        paths.dedup();
+
+        let events: Vec<CollectionEvent> = paths
+            .into_iter()
+            .filter(|path| has_extension(path, &[RECIPE_EXTENSION]))
+            .filter_map(|path| self.reload_file(path, &files, options))
+            .collect();
+        self.errors
+            .retain(|error| !matches!(error, CollectionError::MissingReference { .. }));
+        self.resolve_references();
+        events
+    }
+
+    fn reload_file(
+        &mut self,
+        path: PathBuf,
+        files: &[PathBuf],
+        options: &ParseOptions,
+    ) -> Option<CollectionEvent> {
+        self.errors.retain(|error| error.path() != path);
+        let position = self
+            .recipes
+            .binary_search_by(|recipe| recipe.path.cmp(&path));
+        if !self.root.join(&path).is_file() {
+            self.recipes.remove(position.ok()?);
+            return Some(CollectionEvent::Removed(path));
+        }
+        let event = match (load_recipe(&self.root, &path, files, options), position) {
+            (Ok(recipe), Ok(position)) => {
+                self.recipes[position] = recipe;
+                CollectionEvent::Updated(path)
+            }
+            (Ok(recipe), Err(position)) => {
+                self.recipes.insert(position, recipe);
+                CollectionEvent::Added(path)
+            }
+            (Err(error), _) => {
+                self.errors.push(error);
+                CollectionEvent::Failed(path)
+            }
+        };
+        Some(event)
+    }
+
+    /// The recipe at the path relative to [Collection::root]. The extension can be omitted.
+    pub fn by_path(&self, path: impl AsRef<Path>) -> Option<&CollectionRecipe> {
+        let path = with_recipe_extension(&normalize(path.as_ref()));
+        self.recipes.iter().find(|recipe| recipe.path == path)
+    }
+
+    /// The first recipe with the [title](CollectionRecipe::title), compared case-insensitively.
+    pub fn by_title(&self, title: &str) -> Option<&CollectionRecipe> {
+        let title = title.trim().to_lowercase();
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.title().to_lowercase() == title)
+    }
+
+    /// Recipes with the [tag](crate::Metadata::tags), compared case-insensitively.
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a CollectionRecipe> {
+        self.recipes
+            .iter()
+            .filter(move |recipe| recipe.recipe.metadata.has_tag(tag))
+    }
+
+    /// The recipe referenced with the path from the recipe, like `./sauces/Marinara`.
+    pub fn resolve(&self, from: &CollectionRecipe, reference: &str) -> Option<&CollectionRecipe> {
+        let directory = from.path.parent().unwrap_or_else(|| Path::new(""));
+        self.by_path(directory.join(reference))
+    }
+
+    /// The recipe with the ingredients of the recipes it references added to its
+    /// [ingredients](crate::Metadata::ingredients), also of references in referenced recipes. The
+    /// steps are left as they are. Use it for [shopping lists](crate::shopping) and
+    /// [nutrition facts](crate::nutrition) of the whole dish.
+    ///
+    /// The ingredients of a referenced recipe are scaled to the referenced amount:
+    ///
+    /// * without a unit, the amount is the number of batches, like `@./Dough{2}`,
+    /// * in `servings`, relative to the [servings](crate::Metadata::servings) of the referenced
+    ///   recipe,
+    /// * in another unit, relative to the total of the ingredients in this kind of unit, like
+    ///   `@./sauces/Marinara{200%g}` for half of a sauce with 400 g of ingredients.
+    ///
+    /// References of a recipe outside of the collection are resolved relative to
+    /// [Collection::root]. References which can't be resolved are left out, see
+    /// [CollectionError::MissingReference].
+    pub fn flatten(&self, recipe: &Recipe) -> Recipe {
+        let path = self
+            .recipes
+            .iter()
+            .find(|r| std::ptr::eq(&r.recipe, recipe) || r.recipe == *recipe)
+            .map(|r| r.path.clone())
+            .unwrap_or_default();
+        let mut flattened = recipe.clone();
+        self.add_references(&mut flattened, recipe, &path, 1.0, &mut vec![path.clone()]);
+        flattened
+    }
+
+    /// Add the ingredients referenced by the recipe at the path, scaled by the factor.
+    fn add_references(
+        &self,
+        flattened: &mut Recipe,
+        recipe: &Recipe,
+        path: &Path,
+        factor: f64,
+        visited: &mut Vec<PathBuf>,
+    ) {
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let registry = UnitRegistry::default();
+        let references = recipe
+            .metadata
+            .ingredients_specifiers
+            .iter()
+            .filter(|specifier| specifier.recipe);
+        for specifier in references {
+            let referenced = match self.by_path(directory.join(&specifier.ingredient)) {
+                Some(referenced) if !visited.contains(&referenced.path) => referenced,
+                _ => continue,
+            };
+            let batches = specifier.quantity().map_or(1.0, |quantity| {
+                batches(&referenced.recipe, &quantity, &registry)
+            });
+            let factor = factor * batches;
+            for ingredient in referenced.recipe.metadata.ingredients.values() {
+                let mut ingredient = ingredient.clone();
+                ingredient
+                    .quantities
+                    .quantities
+                    .iter_mut()
+                    .for_each(|quantity| {
+                        quantity.amount = quantity.amount.map(|value| value * factor);
+                    });
+                ingredient.amount = ingredient
+                    .amount
+                    .map(|amount| amount.map(|value| value * factor));
+                ingredient.optional |= specifier.optional;
+                let ingredients = &mut flattened.metadata.ingredients;
+                match ingredients.get_mut(&ingredient.name) {
+                    Some(existing) => merge_ingredients(existing, ingredient),
+                    None => {
+                        ingredients.insert(ingredient.name.clone(), ingredient);
+                    }
+                }
+            }
+            visited.push(referenced.path.clone());
+            self.add_references(
+                flattened,
+                &referenced.recipe,
+                &referenced.path,
+                factor,
+                visited,
+            );
+            visited.pop();
+        }
+    }
+
+    fn resolve_references(&mut self) {
+        let mut errors = vec![];
+        let references: Vec<Vec<Option<usize>>> = self
+            .recipes
+            .iter()
+            .map(|recipe| {
+                recipe
+                    .reference_paths()
+                    .map(|reference| {
+                        let index = self.resolve(recipe, reference).map(|resolved| {
+                            self.recipes
+                                .iter()
+                                .position(|r| r.path == resolved.path)
+                                .unwrap()
+                        });
+                        if index.is_none() {
+                            errors.push(CollectionError::MissingReference {
+                                path: recipe.path.clone(),
+                                reference: reference.to_string(),
+                            });
+                        }
+                        index
+                    })
+                    .collect()
+            })
+            .collect();
+        self.recipes
+            .iter_mut()
+            .zip(references)
+            .for_each(|(recipe, references)| recipe.references = references);
+        self.errors.extend(errors);
+    }
+}
+
+/// How many times the recipe is needed for the referenced quantity. 1 if it can't be told.
+fn batches(recipe: &Recipe, quantity: &Quantity, registry: &UnitRegistry) -> f64 {
+    let value = match &quantity.amount {
+        Amount::Single(value) | Amount::Multi(value) => *value,
+        Amount::PerServing(values) => values.first().copied().unwrap_or_default(),
+    };
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let unit = match quantity.unit.as_deref() {
+        None => return value,
+        Some(unit) => unit,
+    };
+    if unit.eq_ignore_ascii_case("serving") || unit.eq_ignore_ascii_case("servings") {
+        let servings = recipe.metadata.servings.iter().flatten().next();
+        return servings.map_or(value, |servings| value / *servings as f64);
+    }
+    let total: f64 = recipe
+        .metadata
+        .ingredients
+        .values()
+        .flat_map(|ingredient| ingredient.total().quantities)
+        .filter_map(|total| {
+            let amount = match total.amount {
+                Amount::Single(value) => value,
+                _ => return None,
+            };
+            registry.convert(amount, total.unit.as_deref()?, unit)
+        })
+        .sum();
+    if total > 0.0 {
+        value / total
+    } else {
+        1.0
+    }
+}
+
+/// Paths of the recipe files.
+fn recipe_paths(files: &[PathBuf]) -> impl Iterator<Item = &PathBuf> {
+    files
+        .iter()
+        .filter(|path| has_extension(path, &[RECIPE_EXTENSION]))
+}
+
+/// Read and parse the recipe file, finding its images among the files.
+fn load_recipe(
+    root: &Path,
+    path: &Path,
+    files: &[PathBuf],
+    options: &ParseOptions,
+) -> Result<CollectionRecipe, CollectionError> {
+    let source = fs::read_to_string(root.join(path)).map_err(|error| CollectionError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut recipe = parse_with(&source, options)
+        .map_err(|error| CollectionError::Parse {
+            path: path.to_path_buf(),
+            error,
+        })?
+        .recipe;
+    recipe.path = Some(root.join(path));
+    let (image, step_images) = find_images(path, files);
+    recipe.image = image.as_ref().map(|image| root.join(image));
+    recipe.step_images = step_images
+        .iter()
+        .map(|(step, image)| (*step, root.join(image)))
+        .collect();
+    Ok(CollectionRecipe {
+        path: path.to_path_buf(),
+        recipe,
+        image,
+        step_images,
+        references: vec![],
+    })
+}
+
+/// Collect the paths of all files below the directory, relative to the root. Hidden files and
+/// directories are skipped.
+fn collect_files(
+    root: &Path,
+    relative: &Path,
+    files: &mut Vec<PathBuf>,
+    errors: &mut Vec<CollectionError>,
+) {
+    let entries = match fs::read_dir(root.join(relative)) {
+        Ok(entries) => entries,
+        Err(error) => {
+            let path = relative.to_path_buf();
+            errors.push(CollectionError::Io { path, error });
+            return;
+        }
+    };
+    let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = relative.join(&name);
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_files(root, &path, files, errors),
+            Ok(_) => files.push(path),
+            Err(error) => errors.push(CollectionError::Io { path, error }),
+        }
+    }
+}
+
+/// Image and step images of the recipe at the path.
+pub(crate) fn find_images(
+    recipe: &Path,
+    files: &[PathBuf],
+) -> (Option<PathBuf>, Vec<(usize, PathBuf)>) {
+    let stem = recipe.with_extension("");
+    let mut image = None;
+    let mut step_images = vec![];
+    IMAGE_EXTENSIONS.iter().for_each(|extension| {
+        files
+            .iter()
+            .filter(|file| has_extension(file, &[extension]))
+            .for_each(|file| {
+                let file_stem = file.with_extension("");
+                if image.is_none() && file_stem == stem {
+                    image = Some(file.clone());
+                } else if let Some(step) = file_stem
+                    .extension()
+                    .and_then(|step| step.to_str()?.parse::<usize>().ok())
+                    .filter(|_| file_stem.with_extension("") == stem)
+                {
+                    if !step_images.iter().any(|(number, _)| *number == step) {
+                        step_images.push((step, file.clone()));
+                    }
+                }
+            });
+    });
+    step_images.sort_by_key(|(number, _)| *number);
+    (image, step_images)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| {
+            extensions
+                .iter()
+                .any(|expected| extension.eq_ignore_ascii_case(expected))
+        })
+        .unwrap_or(false)
+}
+
+pub(crate) fn with_recipe_extension(path: &Path) -> PathBuf {
+    if has_extension(path, &[RECIPE_EXTENSION]) {
+        path.to_path_buf()
+    } else {
+        let mut path = path.as_os_str().to_owned();
+        path.push(".");
+        path.push(RECIPE_EXTENSION);
+        PathBuf::from(path)
+    }
+}
+
+/// Remove `.` and resolve `..` components.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    path.components().for_each(|component| match component {
+        Component::CurDir => {}
+        Component::ParentDir => {
+            normalized.pop();
+        }
+        component => normalized.push(component),
+    });
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collection::{Collection, CollectionError};
+    use crate::{parse_with, Amount, ParseOptions};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn load_dir() {
+        let root = std::env::temp_dir().join(format!("cook-collection-{}", std::process::id()));
+        let files: &[(&str, &str)] = &[
+            (
+                "Pasta.cook",
+                "---\ntags: [dinner, Italian]\n---\nBoil @pasta{200%g}, add @./sauces/Marinara{200%g}.\n",
+            ),
+            ("Pasta.jpg", ""),
+            ("Pasta.2.png", ""),
+            ("Pasta.1.jpg", ""),
+            (
+                "sauces/Marinara.cook",
+                "---\ntitle: Tomato Sauce\n---\nSimmer @tomatoes{400%g} with @../Pesto.\n",
+            ),
+            ("sauces/Broken.cook", ">> servings: some\n"),
+            (".hidden/Ignored.cook", "Nothing.\n"),
+        ];
+        files.iter().for_each(|(path, content)| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        });
+        let options = crate::ParseOptions {
+            extensions: true,
+            ..crate::ParseOptions::strict()
+        };
+        let collection = Collection::load_dir_with(&root, &options);
+        #[cfg(feature = "rayon")]
+        let parallel = Collection::load_dir_parallel(&root, &options);
+        fs::remove_dir_all(&root).unwrap();
+        let collection = collection.unwrap();
+        #[cfg(feature = "rayon")]
+        {
+            let parallel = parallel.unwrap();
+            assert_eq!(parallel.recipes, collection.recipes);
+            assert_eq!(parallel.errors.len(), collection.errors.len());
+        }
+
+        assert_eq!(collection.recipes.len(), 2);
+        let pasta = collection.by_path("Pasta").unwrap();
+        assert_eq!(pasta.image, Some(PathBuf::from("Pasta.jpg")));
+        assert_eq!(
+            pasta.step_images,
+            vec![
+                (1, PathBuf::from("Pasta.1.jpg")),
+                (2, PathBuf::from("Pasta.2.png"))
+            ]
+        );
+        let marinara = collection.by_title("tomato sauce").unwrap();
+        assert_eq!(marinara.path, Path::new("sauces").join("Marinara.cook"));
+        assert_eq!(pasta.references, vec![Some(1)]);
+        assert_eq!(marinara.references, vec![None]);
+        assert_eq!(
+            collection
+                .with_tag("italian")
+                .map(|r| r.title())
+                .collect::<Vec<_>>(),
+            vec!["Pasta"]
+        );
+
+        assert_eq!(collection.errors.len(), 2);
+        assert!(
+            matches!(&collection.errors[0], CollectionError::Parse { path, .. }
+            if path.ends_with("Broken.cook"))
+        );
+        assert!(
+            matches!(&collection.errors[1], CollectionError::MissingReference { reference, .. }
+            if reference == "../Pesto")
+        );
+    }
+
+    #[test]
+    fn reload() {
+        let root = std::env::temp_dir().join(format!("cook-reload-{}", std::process::id()));
+        fs::create_dir_all(root.join("drinks")).unwrap();
+        fs::write(root.join("Toast.cook"), "Toast the @bread.\n").unwrap();
+        fs::write(root.join("drinks/Tea.cook"), "Steep @tea.\n").unwrap();
+        let mut collection = Collection::load_dir(&root).unwrap();
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::strict()
+        };
+        let path = |path: &str| PathBuf::from(path);
+
+        fs::write(root.join("drinks/Coffee.cook"), "Brew @./Water{1%l}.\n").unwrap();
+        fs::write(root.join("drinks/Coffee.jpg"), "").unwrap();
+        let added = collection.reload(root.join("drinks/Coffee.cook"), &options);
+        fs::write(root.join("Toast.cook"), "Toast the @bread{2}.\n").unwrap();
+        let failed = collection.reload("Toast.cook", &options);
+        let errors = collection.errors.len();
+        fs::write(root.join("Toast.cook"), "Toast the @bread{2%slices}.\n").unwrap();
+        let updated = collection.reload("./Toast.cook", &options);
+        let unchanged = collection.reload("Missing.cook", &options);
+        fs::remove_dir_all(root.join("drinks")).unwrap();
+        let removed = collection.reload("drinks", &options);
+        fs::remove_dir_all(&root).unwrap();
+
+        use crate::collection::CollectionEvent::*;
+        assert_eq!(added, vec![Added(path("drinks/Coffee.cook"))]);
+        assert_eq!(failed, vec![Failed(path("Toast.cook"))]);
+        // The missing reference of the coffee and the missing unit of the toast.
+        assert_eq!(errors, 2);
+        assert_eq!(updated, vec![Updated(path("Toast.cook"))]);
+        assert!(unchanged.is_empty());
+        assert_eq!(
+            removed,
+            vec![
+                Removed(path("drinks/Coffee.cook")),
+                Removed(path("drinks/Tea.cook"))
+            ]
+        );
+        assert!(collection.errors.is_empty());
+        assert_eq!(collection.recipes.len(), 1);
+        assert!(collection.recipes[0].recipe.source.contains("slices"));
+    }
+
+    #[test]
+    fn flatten() {
+        let root = std::env::temp_dir().join(format!("cook-flatten-{}", std::process::id()));
+        let files: &[(&str, &str)] = &[
+            (
+                "Lasagna.cook",
+                "Layer @sheets{12}, @./sauces/Marinara{300%g} and @cheese{200%g}.\n",
+            ),
+            (
+                "sauces/Marinara.cook",
+                ">> servings: 4\nSimmer @tomatoes{400%g} with @oil{50%ml}, @salt and \
+                 @./Pesto{1%serving}.\n",
+            ),
+            (
+                "sauces/Pesto.cook",
+                ">> servings: 2\nBlend @basil{40%g}, @oil{100%ml} and @cheese{20%g}.\n",
+            ),
+        ];
+        files.iter().for_each(|(path, content)| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        });
+        let collection = Collection::load_dir(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let collection = collection.unwrap();
+        assert!(collection.errors.is_empty());
+
+        let lasagna = collection.flatten(&collection.by_path("Lasagna").unwrap().recipe);
+        let ingredients = &lasagna.metadata.ingredients;
+        let names: Vec<&String> = ingredients.keys().collect();
+        assert_eq!(
+            names,
+            vec!["sheets", "cheese", "tomatoes", "oil", "salt", "basil"]
+        );
+        // 300 g of the 400 g of tomatoes, and half of the pesto for one of two servings.
+        assert_eq!(ingredients["tomatoes"].amount, Some(Amount::Single(300.0)));
+        assert_eq!(ingredients["basil"].amount, Some(Amount::Single(15.0)));
+        assert_eq!(ingredients["cheese"].amount, Some(Amount::Single(207.5)));
+        assert_eq!(ingredients["oil"].amount, Some(Amount::Single(75.0)));
+
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let outside = parse_with("Boil @pasta{200%g}, add @./sauces/Pesto{2}.\n", &options)
+            .unwrap()
+            .recipe;
+        let flattened = collection.flatten(&outside);
+        assert_eq!(
+            flattened.metadata.ingredients["basil"].amount,
+            Some(Amount::Single(80.0))
+        );
+    }
+}
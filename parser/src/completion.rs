@@ -0,0 +1,192 @@
+//! Completion of partially typed CookLang, e.g. for a language server.
+//!
+//! [complete] looks at the line up to the cursor and suggests
+//!
+//! * the ingredients of the recipe after an `@`,
+//! * units after the `%` in braces, time units for a timer,
+//! * well-known metadata keys and the keys of the recipe after `>>`.
+
+use crate::diagnostics::Span;
+use crate::units::{PhysicalQuantity, UnitRegistry};
+use crate::{parse_with, ParseOptions, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Kind of a [Completion].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum CompletionKind {
+    /// An ingredient mentioned elsewhere in the recipe.
+    Ingredient,
+    /// A unit of an ingredient or timer.
+    Unit,
+    /// A metadata key.
+    MetadataKey,
+}
+
+/// A suggestion for the text before the cursor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Completion {
+    /// What is suggested.
+    pub kind: CompletionKind,
+    /// The suggested text.
+    pub text: String,
+    /// The typed text which is replaced by [Completion::text].
+    pub span: Span,
+}
+
+/// Metadata keys with a meaning, see [Metadata](crate::Metadata).
+const METADATA_KEYS: [&str; 12] = [
+    "servings",
+    "title",
+    "description",
+    "author",
+    "source",
+    "tags",
+    "time",
+    "prep-time",
+    "cook-time",
+    "course",
+    "cuisine",
+    "difficulty",
+];
+
+const TIME_UNITS: [&str; 4] = ["seconds", "minutes", "hours", "days"];
+
+/// Suggestions for the text before `cursor`, a byte offset into the source. An offset inside of a
+/// char is moved to the start of the char.
+///
+/// Suggestions start with the typed text, compared case-insensitively. Units used in the recipe
+/// are suggested before the other known units.
+pub fn complete(source: &str, cursor: usize) -> Vec<Completion> {
+    let mut cursor = cursor.min(source.len());
+    while !source.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let line_start = source[..cursor].rfind('\n').map_or(0, |i| i + 1);
+    let line = &source[line_start..cursor];
+
+    if let Some(key) = line.trim_start().strip_prefix(">>") {
+        if key.contains(':') {
+            return vec![];
+        }
+        let prefix = key.trim_start();
+        let recipe = parse_without(source, cursor - prefix.len(), cursor);
+        let keys = recipe.iter().flat_map(|recipe| {
+            recipe
+                .metadata
+                .entries
+                .iter()
+                .map(|(key, _)| key.to_string())
+        });
+        let keys = METADATA_KEYS.iter().map(|key| key.to_string()).chain(keys);
+        return completions(CompletionKind::MetadataKey, keys, prefix, cursor);
+    }
+
+    let braces = line.rfind('{').filter(|&open| !line[open..].contains('}'));
+    let unit = braces.and_then(|open| Some((open, open + line[open..].rfind('%')? + 1)));
+    if let Some((open, unit)) = unit {
+        let prefix = &line[unit..];
+        if line[..open].ends_with('~') {
+            let units = TIME_UNITS.iter().map(|unit| unit.to_string());
+            return completions(CompletionKind::Unit, units, prefix, cursor);
+        }
+        let recipe = parse_without(source, cursor - prefix.len(), cursor);
+        let used = recipe.iter().flat_map(|recipe| {
+            recipe
+                .metadata
+                .ingredients_specifiers
+                .iter()
+                .filter_map(|specifier| specifier.unit.clone())
+        });
+        let registry = UnitRegistry::default();
+        let known = registry
+            .units
+            .iter()
+            .filter(|unit| unit.quantity != PhysicalQuantity::Temperature)
+            .map(|unit| unit.symbol.clone());
+        let units: Vec<String> = used.chain(known).collect();
+        return completions(CompletionKind::Unit, units, prefix, cursor);
+    }
+
+    if let Some(at) = line
+        .rfind('@')
+        .filter(|&i| !line[i..].contains(['{', '}', '(']))
+    {
+        let prefix = line[at + 1..].trim_start_matches(['&', '?', '-']);
+        let recipe = parse_without(source, cursor - prefix.len(), cursor);
+        let names = recipe
+            .iter()
+            .flat_map(|recipe| recipe.metadata.ingredients.keys().cloned());
+        return completions(CompletionKind::Ingredient, names, prefix, cursor);
+    }
+    vec![]
+}
+
+/// The recipe without the typed text between `start` and `end`, which would be suggested itself
+/// otherwise.
+fn parse_without(source: &str, start: usize, end: usize) -> Option<Recipe> {
+    let source = format!("{}{}", &source[..start], &source[end..]);
+    parse_with(&source, &ParseOptions::lenient())
+        .ok()
+        .map(|output| output.recipe)
+}
+
+/// Completions of the candidates starting with the prefix, without duplicates.
+fn completions(
+    kind: CompletionKind,
+    candidates: impl IntoIterator<Item = String>,
+    prefix: &str,
+    cursor: usize,
+) -> Vec<Completion> {
+    let prefix_lowercase = prefix.to_lowercase();
+    let span = Span::new(cursor - prefix.len(), cursor);
+    let mut result: Vec<Completion> = vec![];
+    candidates.into_iter().for_each(|text| {
+        let matches = text.to_lowercase().starts_with(&prefix_lowercase);
+        if matches && !text.is_empty() && !result.iter().any(|c| c.text == text) {
+            result.push(Completion { kind, text, span });
+        }
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{complete, CompletionKind};
+
+    fn texts(source: &str) -> Vec<String> {
+        complete(source, source.len())
+            .into_iter()
+            .map(|completion| completion.text)
+            .collect()
+    }
+
+    #[test]
+    fn complete_source() {
+        assert_eq!(
+            texts("Add @tomatoes{2} and @tomato paste{1%tbsp}.\nStir in the @?tom"),
+            vec!["tomatoes", "tomato paste"]
+        );
+        assert_eq!(texts("Add @tomatoes{2} and @pep"), Vec::<String>::new());
+        assert_eq!(
+            texts("Add @flour{200%gram}.\nAdd @sugar{2%g"),
+            vec!["gram", "g"]
+        );
+        assert_eq!(texts("Bake for ~{20%m"), vec!["minutes"]);
+        assert_eq!(
+            texts(">> source: Grandma\n>> s"),
+            vec!["servings", "source"]
+        );
+        assert!(texts(">> title: ").is_empty());
+
+        let completions = complete("Add @sa and @salt.", 7);
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].kind, CompletionKind::Ingredient);
+        assert_eq!((completions[0].span.start, completions[0].span.end), (5, 7));
+        assert_eq!(complete("Add @sü", 7), complete("Add @sü", 6));
+    }
+}
@@ -0,0 +1,221 @@
+//! A cookbook of a whole [Collection], ready to print.
+//!
+//! [to_html] writes a single self-contained HTML document: a title page, a table of contents and
+//! a chapter per [tag](crate::Metadata::tags), with every recipe starting on a new page. The
+//! styles for paged media are embedded, so the document can be printed to PDF from a browser or
+//! converted with tools like WeasyPrint or Pandoc, e.g. to an EPUB.
+//!
+//! A recipe is printed once, in the chapter of its first tag. Recipes without tags are put in a
+//! last chapter. Chapters are in alphabetical order, recipes in the order of the collection.
+
+use crate::collection::{Collection, CollectionRecipe};
+use crate::render::{to_html_with, Html, NO_ATTRIBUTES};
+use crate::QuantityFormatter;
+
+/// Styles of the cookbook. Page numbers in the table of contents need a renderer supporting
+/// `target-counter`, like WeasyPrint.
+const STYLE: &str = "\
+@page { size: A4; margin: 2cm; @bottom-center { content: counter(page); } }
+body { font-family: Georgia, serif; line-height: 1.4; }
+.title-page { break-after: page; text-align: center; padding-top: 30%; }
+.contents { break-after: page; }
+.contents a { color: inherit; text-decoration: none; }
+.contents a::after { content: leader('.') target-counter(attr(href), page); }
+.chapter > h1 { break-before: page; }
+.recipe { break-before: page; }
+.recipe li { break-inside: avoid; }
+.quantity { font-weight: bold; }
+.optional { font-style: italic; }
+";
+
+/// What [to_html] writes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CookbookOptions {
+    /// Title of the cookbook.
+    pub title: String,
+    /// Heading of the table of contents.
+    pub contents: String,
+    /// Name of the chapter of the recipes without tags.
+    pub untagged: String,
+    /// How the amounts are written.
+    pub quantities: QuantityFormatter,
+}
+
+impl Default for CookbookOptions {
+    fn default() -> Self {
+        CookbookOptions {
+            title: "Cookbook".to_string(),
+            contents: "Contents".to_string(),
+            untagged: "Other recipes".to_string(),
+            quantities: QuantityFormatter::default(),
+        }
+    }
+}
+
+/// Render the recipes of the collection as a cookbook. All text is escaped.
+pub fn to_html(collection: &Collection, options: &CookbookOptions) -> String {
+    let chapters = chapters(collection, options);
+    let mut html = Html::default();
+    html.output += "<!DOCTYPE html>\n";
+    html.open("html", NO_ATTRIBUTES);
+    html.open("head", NO_ATTRIBUTES);
+    html.open("meta", &[("charset", "utf-8")]);
+    html.element("title", NO_ATTRIBUTES, &options.title);
+    html.open("style", NO_ATTRIBUTES);
+    html.output += STYLE;
+    html.close("style");
+    html.close("head");
+    html.open("body", NO_ATTRIBUTES);
+
+    html.open("section", &[("class", "title-page")]);
+    html.element("h1", NO_ATTRIBUTES, &options.title);
+    html.close("section");
+
+    html.open("nav", &[("class", "contents")]);
+    html.element("h1", NO_ATTRIBUTES, &options.contents);
+    html.open("ol", NO_ATTRIBUTES);
+    chapters
+        .iter()
+        .enumerate()
+        .for_each(|(chapter, (name, recipes))| {
+            html.open("li", NO_ATTRIBUTES);
+            let href = format!("#chapter-{}", chapter + 1);
+            html.element("a", &[("href", href)], name);
+            html.open("ol", NO_ATTRIBUTES);
+            recipes.iter().for_each(|(index, recipe)| {
+                html.open_inline("li", NO_ATTRIBUTES);
+                let href = format!("#recipe-{}", index + 1);
+                html.element_inline("a", &[("href", href)], recipe.title());
+                html.close("li");
+            });
+            html.close("ol");
+            html.close("li");
+        });
+    html.close("ol");
+    html.close("nav");
+
+    chapters
+        .iter()
+        .enumerate()
+        .for_each(|(chapter, (name, recipes))| {
+            let id = format!("chapter-{}", chapter + 1);
+            html.open("section", &[("class", "chapter"), ("id", &id)]);
+            html.element("h1", NO_ATTRIBUTES, name);
+            recipes.iter().for_each(|(index, recipe)| {
+                html.open("div", &[("id", format!("recipe-{}", index + 1))]);
+                // Recipes without a title are named like their file.
+                let mut recipe = recipe.recipe.clone();
+                if recipe.metadata.title().is_none() {
+                    let name = collection.recipes[*index].name().to_string();
+                    recipe.metadata.entries.insert("title".to_string(), name);
+                }
+                html.output += &to_html_with(&recipe, &options.quantities);
+                html.close("div");
+            });
+            html.close("section");
+        });
+
+    html.close("body");
+    html.close("html");
+    html.output
+}
+
+/// The chapters by name with the recipes and their index in the collection.
+fn chapters<'a>(
+    collection: &'a Collection,
+    options: &'a CookbookOptions,
+) -> Vec<(&'a str, Vec<(usize, &'a CollectionRecipe)>)> {
+    let mut chapters: Vec<(&str, Vec<(usize, &CollectionRecipe)>)> = vec![];
+    let mut untagged = vec![];
+    collection
+        .recipes
+        .iter()
+        .enumerate()
+        .for_each(|(index, recipe)| {
+            let tag = match recipe.recipe.metadata.tags().first() {
+                Some(tag) => *tag,
+                None => return untagged.push((index, recipe)),
+            };
+            match chapters
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            {
+                Some((_, recipes)) => recipes.push((index, recipe)),
+                None => chapters.push((tag, vec![(index, recipe)])),
+            }
+        });
+    chapters.sort_by_key(|(name, _)| name.to_lowercase());
+    if !untagged.is_empty() {
+        chapters.push((&options.untagged, untagged));
+    }
+    chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collection::{Collection, CollectionRecipe};
+    use crate::cookbook::{to_html, CookbookOptions};
+    use crate::parse;
+    use std::path::PathBuf;
+
+    #[test]
+    fn cookbook() {
+        let recipe = |path: &str, source: &str| CollectionRecipe {
+            path: PathBuf::from(path),
+            recipe: parse(source).unwrap(),
+            image: None,
+            step_images: vec![],
+            references: vec![],
+        };
+        let collection = Collection {
+            root: PathBuf::from("recipes"),
+            recipes: vec![
+                recipe("Tea.cook", "Boil @water{1%l}.\n"),
+                recipe(
+                    "Soup.cook",
+                    "---\ntitle: Tomato Soup\ntags: [starters]\n---\nBlend @tomatoes{4}.\n",
+                ),
+                recipe(
+                    "Pie.cook",
+                    "---\ntags: [Desserts, baking]\n---\nBake the @apples{3} & @dough.\n",
+                ),
+            ],
+            errors: vec![],
+        };
+        let options = CookbookOptions {
+            title: "Family <Recipes>".to_string(),
+            ..CookbookOptions::default()
+        };
+        let html = to_html(&collection, &options);
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n"));
+        assert!(html.contains("<title>Family &lt;Recipes&gt;</title>"));
+        assert!(html.contains(
+            "\
+<li>
+<a href=\"#chapter-1\">Desserts</a>
+<ol>
+<li><a href=\"#recipe-3\">Pie</a></li>
+</ol>
+</li>
+<li>
+<a href=\"#chapter-2\">starters</a>
+<ol>
+<li><a href=\"#recipe-2\">Tomato Soup</a></li>
+</ol>
+</li>
+<li>
+<a href=\"#chapter-3\">Other recipes</a>
+<ol>
+<li><a href=\"#recipe-1\">Tea</a></li>
+</ol>
+</li>
+"
+        ));
+        assert!(html.contains(
+            "<section class=\"chapter\" id=\"chapter-3\">\n<h1>Other recipes</h1>\n\
+             <div id=\"recipe-1\">\n<article class=\"recipe\">\n<h1>Tea</h1>\n"
+        ));
+        assert!(html.contains("Bake the <span class=\"ingredient\""));
+        assert!(html.ends_with("</section>\n</body>\n</html>\n"));
+    }
+}
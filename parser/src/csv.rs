@@ -0,0 +1,223 @@
+//! Export of shopping lists and ingredients as CSV, e.g. to import them into a spreadsheet or a
+//! grocery app.
+//!
+//! Every quantity is written on a row of its own, so `300 g` and `2 tbsp` of the same ingredient
+//! are two rows. Ingredients without a quantity have a single row with empty quantity and unit.
+//! Fields are quoted as described in RFC 4180.
+//!
+//! ```
+//! use cook_with_rust_parser::csv::CsvOptions;
+//! use cook_with_rust_parser::{parse, ShoppingList};
+//!
+//! let recipe = parse("Add @flour{200%g}, @eggs{2} and @salt.\n").unwrap();
+//! let csv = ShoppingList::from_recipes(vec![&recipe]).to_csv(&CsvOptions::default());
+//! assert_eq!(csv, "name,quantity,unit\nflour,200,g\neggs,2,\nsalt,,\n");
+//! ```
+
+use crate::aisle::{AisleConf, OTHER};
+use crate::{GroupedQuantity, QuantityFormatter, Recipe, ShoppingList};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A column of the CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Column {
+    /// Name of the ingredient.
+    Name,
+    /// The amount, like `1.5`.
+    Quantity,
+    /// Unit of the amount.
+    Unit,
+    /// Category of the ingredient in the [aisle configuration](CsvOptions::aisle), or
+    /// [OTHER].
+    Aisle,
+    /// Titles of the recipes using the ingredient, separated by `; `.
+    Recipe,
+}
+
+impl Column {
+    /// Name of the column in the header.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Quantity => "quantity",
+            Column::Unit => "unit",
+            Column::Aisle => "aisle",
+            Column::Recipe => "recipe",
+        }
+    }
+}
+
+/// What [ShoppingList::to_csv] and [Recipe::ingredients_csv] write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// The columns in order. Defaults to name, quantity and unit.
+    pub columns: Vec<Column>,
+    /// Write the names of the columns on the first row. Defaults to `true`.
+    pub header: bool,
+    /// Separator of the fields. Defaults to `,`.
+    pub separator: char,
+    /// Categories of the [Column::Aisle].
+    pub aisle: AisleConf,
+    /// How the amounts are written. Defaults to decimals with a `.`, which spreadsheets read as
+    /// numbers.
+    pub quantities: QuantityFormatter,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            columns: vec![Column::Name, Column::Quantity, Column::Unit],
+            header: true,
+            separator: ',',
+            aisle: AisleConf::default(),
+            quantities: QuantityFormatter::default(),
+        }
+    }
+}
+
+impl ShoppingList {
+    /// The items as CSV, in the order of the list. The recipes of an item are its
+    /// [recipes](crate::ShoppingItem::recipes).
+    pub fn to_csv(&self, options: &CsvOptions) -> String {
+        let mut csv = Csv::new(options);
+        self.items
+            .values()
+            .for_each(|item| csv.rows(&item.name, &item.quantities, &item.recipes.join("; ")));
+        csv.output
+    }
+}
+
+impl Recipe {
+    /// The ingredients of the recipe as CSV, with their [total](crate::Ingredient::total)
+    /// quantities. The recipe of every ingredient is the [title](Recipe::title) of the recipe.
+    pub fn ingredients_csv(&self, options: &CsvOptions) -> String {
+        let mut csv = Csv::new(options);
+        let title = self.title().unwrap_or_default();
+        self.metadata
+            .ingredients
+            .values()
+            .for_each(|ingredient| csv.rows(&ingredient.name, &ingredient.total(), title));
+        csv.output
+    }
+}
+
+/// Writer of the rows.
+struct Csv<'a> {
+    options: &'a CsvOptions,
+    output: String,
+}
+
+impl<'a> Csv<'a> {
+    fn new(options: &'a CsvOptions) -> Csv<'a> {
+        let mut csv = Csv {
+            options,
+            output: String::new(),
+        };
+        if options.header {
+            let header: Vec<&str> = options.columns.iter().map(Column::header).collect();
+            csv.row(&header);
+        }
+        csv
+    }
+
+    /// A row per quantity of the ingredient.
+    fn rows(&mut self, name: &str, quantities: &GroupedQuantity, recipes: &str) {
+        let aisle = self.options.aisle.category_of(name).unwrap_or(OTHER);
+        let quantities: Vec<(String, &str)> = quantities
+            .quantities
+            .iter()
+            .map(|quantity| {
+                let amount = self.options.quantities.amount(&quantity.amount);
+                (amount, quantity.unit.as_deref().unwrap_or_default())
+            })
+            .collect();
+        let empty = [(String::new(), "")];
+        let quantities = match quantities.is_empty() {
+            true => &empty[..],
+            false => &quantities[..],
+        };
+        quantities.iter().for_each(|(amount, unit)| {
+            let fields: Vec<&str> = self
+                .options
+                .columns
+                .iter()
+                .map(|column| match column {
+                    Column::Name => name,
+                    Column::Quantity => amount,
+                    Column::Unit => unit,
+                    Column::Aisle => aisle,
+                    Column::Recipe => recipes,
+                })
+                .collect();
+            self.row(&fields);
+        });
+    }
+
+    fn row(&mut self, fields: &[&str]) {
+        let separator = self.options.separator;
+        fields.iter().enumerate().for_each(|(index, field)| {
+            if index > 0 {
+                self.output.push(separator);
+            }
+            let quote = field.contains([separator, '"', '\n', '\r']);
+            match quote {
+                true => {
+                    self.output.push('"');
+                    self.output.push_str(&field.replace('"', "\"\""));
+                    self.output.push('"');
+                }
+                false => self.output.push_str(field),
+            }
+        });
+        self.output.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aisle::AisleConf;
+    use crate::csv::{Column, CsvOptions};
+    use crate::{parse, ShoppingList};
+
+    #[test]
+    fn csv() {
+        let first = "---\ntitle: Pasta, quick\n---\nCook @pasta{200%g} with @salt.\n";
+        let second = "---\ntitle: Salad \"Nizza\"\n---\nAdd @pasta{100%g}, @olives{2%tbsp}.\n";
+        let (first, second) = (parse(first).unwrap(), parse(second).unwrap());
+        let options = CsvOptions {
+            columns: vec![
+                Column::Name,
+                Column::Quantity,
+                Column::Aisle,
+                Column::Recipe,
+            ],
+            aisle: AisleConf::parse("[dry goods]\npasta\n").unwrap(),
+            ..CsvOptions::default()
+        };
+        let list = ShoppingList::from_recipes(vec![&first, &second]);
+        assert_eq!(
+            list.to_csv(&options),
+            "\
+name,quantity,aisle,recipe
+pasta,300,dry goods,\"Pasta, quick; Salad \"\"Nizza\"\"\"
+salt,,other,\"Pasta, quick\"
+olives,2,other,\"Salad \"\"Nizza\"\"\"
+"
+        );
+
+        let options = CsvOptions {
+            header: false,
+            separator: ';',
+            columns: vec![Column::Recipe, Column::Name, Column::Unit],
+            ..CsvOptions::default()
+        };
+        let recipe = parse(">> title: Soup\nAdd @water{1%l}, then @water{2%cups}.\n").unwrap();
+        assert_eq!(
+            recipe.ingredients_csv(&options),
+            "Soup;water;l\nSoup;water;cups\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Diagnostics reported while parsing a recipe.
+//!
+//! Recoverable issues are collected as warnings in [crate::ParseOutput]. Issues that stop the
+//! parsing are returned as a [crate::ParseError].
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// A range of bytes in the source of a recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Span {
+    /// Offset of the first byte.
+    pub start: usize,
+    /// Offset after the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span.
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+impl<'i> From<pest::Span<'i>> for Span {
+    fn from(span: pest::Span<'i>) -> Self {
+        Span::new(span.start(), span.end())
+    }
+}
+
+/// How severe a [Diagnostic] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Severity {
+    /// The recipe could be parsed, but probably not as intended.
+    Warning,
+    /// The recipe could not be parsed.
+    Error,
+}
+
+/// An issue found in the source of a recipe.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Diagnostic {
+    /// How severe the issue is.
+    pub severity: Severity,
+    /// Description of the issue.
+    pub message: String,
+    /// Where in the source the issue is.
+    pub span: Span,
+    /// Suggestion on how to fix the issue.
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a new warning.
+    pub fn warning(span: Span, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+            hint: None,
+        }
+    }
+
+    /// Create a new error.
+    pub fn error(span: Span, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            hint: None,
+        }
+    }
+
+    /// Add a hint on how to fix the issue.
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Diagnostic {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Line and column of the start of the span, both starting at 1.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..line_start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Render the diagnostic for humans, showing the offending line of the source with the span
+    /// underlined.
+    ///
+    /// ```text
+    /// warning: Unparseable amount is treated as text
+    ///  --> 1:10
+    ///   |
+    /// 1 | Add @salt{a pinch}
+    ///   |          ^^^^^^^^^
+    ///   = hint: Amounts are numbers like 2, 1/2 or 1|2, followed by an optional %unit
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        self.render_at(source, "")
+    }
+
+    /// Render the diagnostic like [Diagnostic::render], with the file in the location, like
+    /// `--> Pancakes.cook:1:10`.
+    pub fn render_file(&self, source: &str, path: &Path) -> String {
+        self.render_at(source, &format!("{}:", path.display()))
+    }
+
+    fn render_at(&self, source: &str, file: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let (line, column) = self.line_col(source);
+        let end = self.span.end.clamp(start, line_end);
+        let width = source[start..end].chars().count().max(1);
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        let mut result = format!("{}: {}\n", self.severity, self.message);
+        result += &format!("{}--> {}{}:{}\n", gutter, file, line, column);
+        result += &format!("{} |\n", gutter);
+        result += &format!(
+            "{} | {}\n",
+            number,
+            source[line_start..line_end].trim_end_matches('\r')
+        );
+        result += &format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        );
+        if let Some(hint) = &self.hint {
+            result += &format!("{} = hint: {}\n", gutter, hint);
+        }
+        result
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}..{}",
+            self.severity, self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{Diagnostic, Span};
+
+    #[test]
+    fn render() {
+        let source = ">> servings: 2\nAdd @salt{a pinch}\n";
+        let diagnostic = Diagnostic::warning(Span::new(24, 33), "Unparseable amount")
+            .with_hint("Use a number");
+        assert_eq!(diagnostic.line_col(source), (2, 10));
+        assert_eq!(
+            diagnostic.render(source),
+            "\
+warning: Unparseable amount
+ --> 2:10
+  |
+2 | Add @salt{a pinch}
+  |          ^^^^^^^^^
+  = hint: Use a number
+"
+        );
+
+        let at_end = Diagnostic::error(Span::new(3, 3), "Unexpected end");
+        assert!(at_end.render("Add").ends_with("1 | Add\n  |    ^\n"));
+    }
+}
@@ -0,0 +1,268 @@
+//! Dietary classification of recipes, like vegetarian or gluten-free.
+//!
+//! The diet configuration lists the ingredients of categories like [MEAT] or [GLUTEN] in the
+//! format of the [aisle configuration](crate::aisle). [Recipe::classify] looks up the ingredients
+//! of a recipe in the categories and derives the flags of a [Classification] from the categories
+//! it contains. Names are compared after [normalizing](crate::names::NameNormalizer), so
+//! `Almonds` is found as `almond`.
+//!
+//! ```text
+//! [meat]
+//! bacon
+//! chicken | chicken breast
+//!
+//! [nuts]
+//! almond | almond flour
+//! ```
+//!
+//! The [default](DietConf::default) configuration knows common ingredients of every category.
+
+use crate::aisle::{AisleConf, Category};
+use crate::names::NameNormalizer;
+use crate::Recipe;
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Category of meat and poultry, not vegetarian.
+pub const MEAT: &str = "meat";
+/// Category of fish and seafood, not vegetarian.
+pub const FISH: &str = "fish";
+/// Category of milk products, not vegan.
+pub const DAIRY: &str = "dairy";
+/// Category of eggs, not vegan.
+pub const EGGS: &str = "eggs";
+/// Category of other animal products like honey, not vegan.
+pub const ANIMAL: &str = "animal";
+/// Category of ingredients containing gluten.
+pub const GLUTEN: &str = "gluten";
+/// Category of nuts.
+pub const NUTS: &str = "nuts";
+
+const DEFAULT_CONF: &str = "\
+[meat]
+bacon
+beef | ground beef | minced beef
+chicken | chicken breast | chicken thigh
+duck
+ham
+lamb
+pork | pork belly
+prosciutto
+salami
+sausage
+turkey
+veal
+chicken stock | chicken broth
+beef stock | beef broth
+gelatin | gelatine
+
+[fish]
+anchovy
+cod
+crab
+fish sauce
+lobster
+mussel
+prawn
+salmon
+sardine
+scallop
+shrimp
+tuna
+
+[dairy]
+butter
+buttermilk
+cheese | cheddar | feta | mozzarella | parmesan | ricotta
+cream | double cream | heavy cream | sour cream | whipping cream
+cream cheese
+creme fraiche
+ghee
+milk | whole milk
+yogurt | yoghurt
+
+[eggs]
+egg | egg white | egg yolk
+mayonnaise
+
+[animal]
+honey
+
+[gluten]
+barley
+bread | breadcrumb | bread crumb
+bulgur
+couscous
+flour | all-purpose flour | plain flour | wheat flour | bread flour
+pasta | spaghetti | noodle | penne | lasagna sheet
+rye
+semolina
+soy sauce
+spelt
+wheat
+
+[nuts]
+almond | almond flour | ground almond
+cashew
+hazelnut
+macadamia
+peanut | peanut butter
+pecan
+pine nut
+pistachio
+walnut
+";
+
+/// Ingredient categories for [Recipe::classify].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DietConf {
+    /// Categories in the order they appear in the configuration. An ingredient may be part of
+    /// several categories.
+    pub categories: Vec<Category>,
+    /// How the names of the ingredients are compared, the default folds plurals.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub names: NameNormalizer,
+}
+
+impl Default for DietConf {
+    /// Configuration with common ingredients of the well-known categories.
+    fn default() -> Self {
+        DietConf::parse(DEFAULT_CONF).unwrap()
+    }
+}
+
+impl DietConf {
+    /// Parse the content of a diet configuration.
+    pub fn parse(inp: &str) -> Result<DietConf, Box<dyn std::error::Error>> {
+        let categories = AisleConf::parse(inp)?.categories;
+        Ok(DietConf {
+            categories,
+            names: NameNormalizer::default(),
+        })
+    }
+
+    /// Names of the categories the ingredient belongs to.
+    pub fn categories_of(&self, ingredient: &str) -> Vec<&str> {
+        self.categories
+            .iter()
+            .filter(|category| {
+                category
+                    .ingredients
+                    .iter()
+                    .flatten()
+                    .any(|name| self.names.same(name, ingredient))
+            })
+            .map(|category| category.name.as_str())
+            .collect()
+    }
+}
+
+/// Dietary flags of a recipe.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Classification {
+    /// No ingredient is [MEAT] or [FISH].
+    pub vegetarian: bool,
+    /// Vegetarian and no ingredient is [DAIRY], [EGGS] or [ANIMAL].
+    pub vegan: bool,
+    /// No ingredient contains [GLUTEN].
+    pub gluten_free: bool,
+    /// An ingredient is one of the [NUTS].
+    pub contains_nuts: bool,
+    /// Names of the ingredients by the categories they belong to, in the order of the
+    /// configuration.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Vec<String>>")
+    )]
+    pub categories: IndexMap<String, Vec<String>>,
+}
+
+impl Classification {
+    /// Whether an ingredient belongs to the category.
+    pub fn contains(&self, category: &str) -> bool {
+        self.categories.contains_key(category)
+    }
+}
+
+impl Recipe {
+    /// Dietary flags of the recipe, using the [default](DietConf::default) categories.
+    pub fn classify(&self) -> Classification {
+        self.classify_with(&DietConf::default())
+    }
+
+    /// Dietary flags of the recipe, using the categories of the configuration.
+    ///
+    /// Optional ingredients are left out, the recipe can be cooked without them.
+    pub fn classify_with(&self, conf: &DietConf) -> Classification {
+        let mut categories: IndexMap<String, Vec<String>> = IndexMap::new();
+        conf.categories.iter().for_each(|category| {
+            categories.entry(category.name.clone()).or_default();
+        });
+        self.metadata
+            .ingredients
+            .values()
+            .filter(|ingredient| !ingredient.optional)
+            .for_each(|ingredient| {
+                conf.categories_of(&ingredient.name)
+                    .into_iter()
+                    .for_each(|category| categories[category].push(ingredient.name.clone()));
+            });
+        categories.retain(|_, ingredients| !ingredients.is_empty());
+        let mut classification = Classification {
+            categories,
+            ..Classification::default()
+        };
+        classification.vegetarian = ![MEAT, FISH]
+            .iter()
+            .any(|category| classification.contains(category));
+        classification.vegan = classification.vegetarian
+            && ![DAIRY, EGGS, ANIMAL]
+                .iter()
+                .any(|category| classification.contains(category));
+        classification.gluten_free = !classification.contains(GLUTEN);
+        classification.contains_nuts = classification.contains(NUTS);
+        classification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diet::{DietConf, DAIRY, NUTS};
+    use crate::{parse_with, ParseOptions};
+
+    #[test]
+    fn classify() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let classify = |source: &str| parse_with(source, &options).unwrap().recipe.classify();
+        let salad = classify("Toss @lettuce{1}, @Walnuts{50%g} and @?bacon{2%slices}.\n");
+        assert!(salad.vegetarian && salad.vegan && salad.gluten_free && salad.contains_nuts);
+        assert_eq!(salad.categories[NUTS], vec!["Walnuts"]);
+
+        let cake = classify("Mix @flour{200%g}, @eggs{2} and @butter{100%g}.\n");
+        assert!(cake.vegetarian && !cake.vegan && !cake.gluten_free && !cake.contains_nuts);
+        assert_eq!(cake.categories[DAIRY], vec!["butter"]);
+
+        let mut conf = DietConf::parse("[meat]\nchorizo\n").unwrap();
+        conf.names
+            .synonyms
+            .insert("sobrassada".to_string(), "chorizo".to_string());
+        assert_eq!(conf.categories_of("Sobrassada"), vec!["meat"]);
+        let recipe = parse_with("Fry @chorizo{200%g} in @butter.\n", &options)
+            .unwrap()
+            .recipe;
+        let classification = recipe.classify_with(&conf);
+        assert!(!classification.vegetarian && classification.gluten_free);
+        assert_eq!(
+            DietConf::default().categories_of("ground almonds"),
+            vec![NUTS]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Differences between two versions of a recipe.
+//!
+//! [diff] compares the metadata, the total quantities of the ingredients and the text of the
+//! steps, so a change summary reads like `flour: 200 g -> 250 g` rather than a text diff.
+//! Steps are matched by their text, an edited step is reported as changed if it's in the place
+//! of a removed one.
+//!
+//! ```
+//! use cook_with_rust_parser::diff::diff;
+//! use cook_with_rust_parser::parse;
+//!
+//! let old = parse("Mix @flour{200%g} and @water{100%ml}.\n\nBake.\n").unwrap();
+//! let new = parse("Mix @flour{250%g} and @milk{100%ml}.\n\nBake.\n").unwrap();
+//! let changes = diff(&old, &new).to_string();
+//! assert!(changes.contains("~ flour: 200 g -> 250 g\n"));
+//! assert!(changes.contains("+ milk: 100 ml\n"));
+//! ```
+
+use crate::render::step_text;
+use crate::steps::Step;
+use crate::{Quantity, Recipe};
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How a part of the recipe changed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Change<T> {
+    /// Only part of the new recipe.
+    Added(T),
+    /// Only part of the old recipe.
+    Removed(T),
+    /// Part of both, with a different value.
+    Changed {
+        /// The value in the old recipe.
+        old: T,
+        /// The value in the new recipe.
+        new: T,
+    },
+}
+
+/// A changed step.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StepChange {
+    /// Number of the step in the old recipe, [None] if it's added.
+    pub old_number: Option<usize>,
+    /// Number of the step in the new recipe, [None] if it's removed.
+    pub new_number: Option<usize>,
+    /// The text of the step, including its notes.
+    pub text: Change<String>,
+}
+
+/// Differences between two recipes, see [diff].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecipeDiff {
+    /// Changed metadata by key. Servings are compared as `servings`, of repeated keys the last
+    /// value is compared.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Change<String>>")
+    )]
+    pub metadata: IndexMap<String, Change<String>>,
+    /// Ingredients with changed total quantities by name. See
+    /// [Ingredient::total](crate::Ingredient::total).
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Change<Vec<Quantity>>>")
+    )]
+    pub ingredients: IndexMap<String, Change<Vec<Quantity>>>,
+    /// Added, removed and edited steps, in order.
+    pub steps: Vec<StepChange>,
+}
+
+impl RecipeDiff {
+    /// Whether the recipes are the same.
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.ingredients.is_empty() && self.steps.is_empty()
+    }
+}
+
+/// Differences from the old to the new recipe.
+pub fn diff(old: &Recipe, new: &Recipe) -> RecipeDiff {
+    RecipeDiff {
+        metadata: changes(&metadata(old), &metadata(new), |a, b| a == b),
+        ingredients: changes(&ingredients(old), &ingredients(new), |a, b| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b))
+        }),
+        steps: step_changes(old, new),
+    }
+}
+
+/// The metadata by key, starting with the servings as written.
+pub(crate) fn metadata(recipe: &Recipe) -> IndexMap<String, String> {
+    let metadata = &recipe.metadata;
+    let mut result = IndexMap::new();
+    if !metadata.servings_labels.is_empty() {
+        result.insert("servings".to_string(), metadata.servings_labels.join("|"));
+    } else if let Some(servings) = &metadata.servings {
+        let servings: Vec<String> = servings.iter().map(usize::to_string).collect();
+        result.insert("servings".to_string(), servings.join("|"));
+    }
+    metadata.entries.iter().for_each(|(key, value)| {
+        result.insert(key.to_string(), value.to_string());
+    });
+    result
+}
+
+fn ingredients(recipe: &Recipe) -> IndexMap<String, Vec<Quantity>> {
+    recipe
+        .metadata
+        .ingredients
+        .values()
+        .map(|ingredient| (ingredient.name.clone(), ingredient.total().quantities))
+        .collect()
+}
+
+/// Changes of the values by key, in the order of the old and then the added keys.
+fn changes<T: Clone>(
+    old: &IndexMap<String, T>,
+    new: &IndexMap<String, T>,
+    same: impl Fn(&T, &T) -> bool,
+) -> IndexMap<String, Change<T>> {
+    let mut result = IndexMap::new();
+    old.iter().for_each(|(key, old)| match new.get(key) {
+        None => {
+            result.insert(key.clone(), Change::Removed(old.clone()));
+        }
+        Some(new) if !same(old, new) => {
+            let change = Change::Changed {
+                old: old.clone(),
+                new: new.clone(),
+            };
+            result.insert(key.clone(), change);
+        }
+        Some(_) => {}
+    });
+    new.iter()
+        .filter(|(key, _)| !old.contains_key(*key))
+        .for_each(|(key, new)| {
+            result.insert(key.clone(), Change::Added(new.clone()));
+        });
+    result
+}
+
+/// Text of the step with its notes on lines of their own.
+fn text(recipe: &Recipe, step: &Step) -> String {
+    let mut text = step_text(recipe, step);
+    step.notes().for_each(|note| {
+        text += "\n> ";
+        text += note;
+    });
+    text
+}
+
+/// Changes of the steps, matched by the longest common subsequence of their texts.
+fn step_changes(old: &Recipe, new: &Recipe) -> Vec<StepChange> {
+    let old: Vec<String> = old.steps.iter().map(|step| text(old, step)).collect();
+    let new: Vec<String> = new.steps.iter().map(|step| text(new, step)).collect();
+    let mut changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    let end = (old.len(), new.len());
+    for (matched_i, matched_j) in common_subsequence(&old, &new).into_iter().chain(Some(end)) {
+        let removed: Vec<usize> = (i..matched_i).collect();
+        let added: Vec<usize> = (j..matched_j).collect();
+        push_changes(&mut changes, &removed, &added, &old, &new);
+        i = matched_i + 1;
+        j = matched_j + 1;
+    }
+    changes
+}
+
+/// Index pairs of the longest common subsequence of the lists, in order.
+pub(crate) fn common_subsequence<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    // common[i][j] is the length of the common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Pair the removed and added steps between two matching steps as edits.
+fn push_changes(
+    changes: &mut Vec<StepChange>,
+    removed: &[usize],
+    added: &[usize],
+    old: &[String],
+    new: &[String],
+) {
+    let count = removed.len().max(added.len());
+    for index in 0..count {
+        let change = match (removed.get(index), added.get(index)) {
+            (Some(&i), Some(&j)) => StepChange {
+                old_number: Some(i + 1),
+                new_number: Some(j + 1),
+                text: Change::Changed {
+                    old: old[i].clone(),
+                    new: new[j].clone(),
+                },
+            },
+            (Some(&i), None) => StepChange {
+                old_number: Some(i + 1),
+                new_number: None,
+                text: Change::Removed(old[i].clone()),
+            },
+            (None, Some(&j)) => StepChange {
+                old_number: None,
+                new_number: Some(j + 1),
+                text: Change::Added(new[j].clone()),
+            },
+            (None, None) => unreachable!(),
+        };
+        changes.push(change);
+    }
+}
+
+fn quantities(quantities: &[Quantity]) -> String {
+    let quantities: Vec<String> = quantities.iter().map(Quantity::to_string).collect();
+    quantities.join(" + ")
+}
+
+impl fmt::Display for RecipeDiff {
+    /// One line per change, starting with `+` for added, `-` for removed and `~` for changed
+    /// parts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, change) in &self.metadata {
+            match change {
+                Change::Added(value) => writeln!(f, "+ >> {}: {}", key, value)?,
+                Change::Removed(value) => writeln!(f, "- >> {}: {}", key, value)?,
+                Change::Changed { old, new } => writeln!(f, "~ >> {}: {} -> {}", key, old, new)?,
+            }
+        }
+        for (name, change) in &self.ingredients {
+            match change {
+                Change::Added(new) => writeln!(f, "+ {}: {}", name, quantities(new))?,
+                Change::Removed(old) => writeln!(f, "- {}: {}", name, quantities(old))?,
+                Change::Changed { old, new } => {
+                    writeln!(f, "~ {}: {} -> {}", name, quantities(old), quantities(new))?
+                }
+            }
+        }
+        for step in &self.steps {
+            let number = step.new_number.or(step.old_number).unwrap_or_default();
+            match &step.text {
+                Change::Added(text) => writeln!(f, "+ step {}: {}", number, text)?,
+                Change::Removed(text) => writeln!(f, "- step {}: {}", number, text)?,
+                Change::Changed { new, .. } => writeln!(f, "~ step {}: {}", number, new)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{diff, Change, StepChange};
+    use crate::parse;
+
+    #[test]
+    fn recipe_diff() {
+        let old = parse(
+            ">> servings: 2\n>> source: Grandma\nPreheat the #oven to 180°C.\n\nMix @flour{200%g}, \
+             @sugar{50%g} and @eggs{2}.\n\nBake for ~{30%min}.\n",
+        )
+        .unwrap();
+        let new = parse(
+            ">> servings: 4\n>> course: dessert\nPreheat the #oven to 180°C.\n\nMix @flour{400%g} \
+             and @eggs{2}.\n> Don't overmix.\n\nAdd @berries{1%cup}.\n\nBake for ~{30%min}.\n",
+        )
+        .unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.metadata.keys().collect::<Vec<_>>(),
+            vec!["servings", "source", "course"]
+        );
+        assert_eq!(
+            changes.ingredients.keys().collect::<Vec<_>>(),
+            vec!["flour", "sugar", "berries"]
+        );
+        assert!(matches!(changes.ingredients["sugar"], Change::Removed(_)));
+        assert_eq!(
+            changes.steps[0],
+            StepChange {
+                old_number: Some(2),
+                new_number: Some(2),
+                text: Change::Changed {
+                    old: "Mix flour, sugar and eggs.".to_string(),
+                    new: "Mix flour and eggs.\n> Don't overmix.".to_string(),
+                },
+            }
+        );
+        assert_eq!(
+            changes.steps[1].text,
+            Change::Added("Add berries.".to_string())
+        );
+        assert_eq!(changes.steps.len(), 2);
+        assert_eq!(
+            changes.to_string(),
+            "\
+~ >> servings: 2 -> 4
+- >> source: Grandma
++ >> course: dessert
+~ flour: 200 g -> 400 g
+- sugar: 50 g
++ berries: 1 cup
+~ step 2: Mix flour and eggs.
+> Don't overmix.
++ step 3: Add berries.
+"
+        );
+        assert!(diff(&old, &old.clone()).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Updates of a recipe which is being edited, e.g. in a language server.
+//!
+//! A [Document] keeps the source, its [tokens](crate::tokens) and the last parse. Only the
+//! tokens are updated incrementally: an edit tokenizes the lines it touches again and shifts the
+//! spans of the tokens after it. The [Recipe](crate::Recipe) isn't, as ingredients and metadata
+//! depend on the whole source; it is parsed again as a whole when it's asked for, so a burst of
+//! edits like typing only pays for a single parse.
+
+use crate::diagnostics::Span;
+use crate::tokens::{tokenize, Token};
+use crate::{parse_with, ParseError, ParseOptions, ParseOutput};
+use std::ops::Range;
+
+/// A recipe which is being edited.
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    options: ParseOptions,
+    tokens: Vec<Token>,
+    parsed: Option<Result<ParseOutput, ParseError>>,
+}
+
+impl Document {
+    /// Create a document with the source, parsed with the options.
+    pub fn new(source: impl Into<String>, options: ParseOptions) -> Document {
+        let source = source.into();
+        Document {
+            tokens: tokenize(&source),
+            source,
+            options,
+            parsed: None,
+        }
+    }
+
+    /// The current source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Tokens of the current source, see [tokenize].
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Replace the bytes in `range` with `text`.
+    ///
+    /// Returns the span of the lines which were tokenized again, in the new source. Tokens after
+    /// the span are moved along. An edit of the front matter tokenizes the whole source again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or doesn't lie on char boundaries, like
+    /// [String::replace_range].
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Span {
+        assert!(range.start <= range.end && range.end <= self.source.len());
+        let line_start = self.source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let old_end = self.source[range.end..]
+            .find('\n')
+            .map_or(self.source.len(), |i| range.end + i + 1);
+        let old_front_matter = front_matter_end(&self.source);
+        self.source.replace_range(range.clone(), text);
+        self.parsed = None;
+        let new_end = old_end + text.len() - (range.end - range.start);
+
+        if line_start < old_front_matter.max(front_matter_end(&self.source)) {
+            self.tokens = tokenize(&self.source);
+            return Span::new(0, self.source.len());
+        }
+        let delta = new_end as isize - old_end as isize;
+        let tokens = tokenize(&self.source[line_start..new_end])
+            .into_iter()
+            .map(|token| shift(token, line_start as isize));
+        let first = self
+            .tokens
+            .partition_point(|token| token.span.start < line_start);
+        let last = self
+            .tokens
+            .partition_point(|token| token.span.start < old_end);
+        self.tokens[last..]
+            .iter_mut()
+            .for_each(|token| *token = shift(*token, delta));
+        self.tokens.splice(first..last, tokens);
+        Span::new(line_start, new_end)
+    }
+
+    /// Result of parsing the whole current source, which is cached until the next edit.
+    pub fn parse(&mut self) -> Result<&ParseOutput, &ParseError> {
+        let (source, options) = (&self.source, &self.options);
+        self.parsed
+            .get_or_insert_with(|| parse_with(source, options))
+            .as_ref()
+    }
+}
+
+/// Offset after the front matter, or 0 if there is none.
+pub(crate) fn front_matter_end(source: &str) -> usize {
+    let mut lines = source.split_inclusive('\n');
+    let mut offset = match lines.next() {
+        Some(line) if line.trim_end() == "---" => line.len(),
+        _ => return 0,
+    };
+    for line in lines {
+        offset += line.len();
+        if line.trim_end() == "---" {
+            return offset;
+        }
+    }
+    0
+}
+
+/// Move the token by `delta` bytes.
+fn shift(mut token: Token, delta: isize) -> Token {
+    token.span.start = (token.span.start as isize + delta) as usize;
+    token.span.end = (token.span.end as isize + delta) as usize;
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::Document;
+    use crate::tokens::tokenize;
+    use crate::ParseOptions;
+
+    #[test]
+    fn edit() {
+        let source = "\
+---
+title: Tea
+---
+Boil @water{1%l}.
+
+Steep the @tea{2%tsp} for ~{3%min}.
+Serve in a #cup.
+";
+        let mut document = Document::new(source, ParseOptions::default());
+        assert_eq!(document.tokens(), tokenize(source).as_slice());
+        assert_eq!(
+            document.parse().unwrap().recipe.metadata.ingredients.len(),
+            2
+        );
+
+        let start = document.source().find("@tea").unwrap();
+        let span = document.edit(start..start + 4, "@green tea");
+        assert_eq!(
+            &document.source()[span.start..span.end],
+            "Steep the @green tea{2%tsp} for ~{3%min}.\n"
+        );
+        assert_eq!(document.tokens(), tokenize(document.source()).as_slice());
+        let recipe = &document.parse().unwrap().recipe;
+        assert!(recipe.metadata.ingredients.contains_key("green tea"));
+
+        let end = document.source().len();
+        document.edit(end..end, "Add @honey{1%tsp}.\n");
+        assert_eq!(document.tokens(), tokenize(document.source()).as_slice());
+        let title = document.source().find("Tea").unwrap();
+        let span = document.edit(title..title + 3, "Chai");
+        assert_eq!((span.start, span.end), (0, document.source().len()));
+        assert_eq!(document.tokens(), tokenize(document.source()).as_slice());
+        assert_eq!(
+            document.parse().unwrap().recipe.metadata.title(),
+            Some("Chai")
+        );
+    }
+}
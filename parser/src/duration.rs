@@ -0,0 +1,179 @@
+//! Estimating how long it takes to cook a [Recipe].
+//!
+//! The estimate is based on the timers of the steps. By default, timers run one after another,
+//! but the timers of a step or of a section can be taken to run in parallel, see [Overlap].
+//!
+//! [Recipe::timer_schedule] tells when every timer fires, counted from the start of the recipe,
+//! so an app can set up its notifications ahead of time.
+
+use crate::{Item, Recipe, Step};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Which timers run at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Overlap {
+    /// Every timer runs after the previous one.
+    #[default]
+    Sequential,
+    /// The timers of a step run in parallel, so a step takes as long as its longest timer.
+    Step,
+    /// The timers of a section run in parallel, so a section takes as long as its longest timer.
+    Section,
+}
+
+/// A timer of [Recipe::timer_schedule].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ScheduledTimer {
+    /// Index of the timer in [Metadata::timer](crate::Metadata::timer).
+    pub timer: usize,
+    /// Number of the step mentioning the timer.
+    pub step: usize,
+    /// Time from the start of the recipe until the timer is started.
+    pub start: Duration,
+    /// Time from the start of the recipe until the timer fires.
+    pub end: Duration,
+}
+
+impl Recipe {
+    /// When the timers start and fire, counted from the start of the recipe, in order. The
+    /// steps and their timers are taken to run one after another, like with
+    /// [Overlap::Sequential], and a range like `~{1-2%hours}` fires at its lower end.
+    ///
+    /// Timers with a unit which isn't a time unit are left out.
+    pub fn timer_schedule(&self) -> Vec<ScheduledTimer> {
+        let mut start = Duration::ZERO;
+        let mut schedule = vec![];
+        for step in &self.steps {
+            for timer in step.timers() {
+                if let Ok(duration) = self.metadata.timer[timer].duration() {
+                    schedule.push(ScheduledTimer {
+                        timer,
+                        step: step.number,
+                        start,
+                        end: start.saturating_add(duration),
+                    });
+                    start = start.saturating_add(duration);
+                }
+            }
+        }
+        schedule
+    }
+
+    /// Estimated duration of the recipe with sequential timers. See
+    /// [Recipe::estimated_duration_with].
+    pub fn estimated_duration(&self) -> Duration {
+        self.estimated_duration_with(Overlap::Sequential)
+    }
+
+    /// Estimated duration of the recipe: the duration of its timers, or its
+    /// [time](crate::Metadata::time) if that is longer. Timers with a unit which isn't a time
+    /// unit are left out.
+    pub fn estimated_duration_with(&self, overlap: Overlap) -> Duration {
+        let timers = match overlap {
+            Overlap::Sequential => sum(self.steps.iter().map(|step| self.step_duration(step))),
+            Overlap::Step => sum(self
+                .steps
+                .iter()
+                .map(|step| self.timers(step).max().unwrap_or_default())),
+            Overlap::Section => sum(self.sections().iter().map(|section| {
+                let steps = self.section_steps(section).iter();
+                steps
+                    .flat_map(|step| self.timers(step))
+                    .max()
+                    .unwrap_or_default()
+            })),
+        };
+        self.metadata.time().map_or(timers, |time| time.max(timers))
+    }
+
+    /// Sum of the timers of the step.
+    pub fn step_duration(&self, step: &Step) -> Duration {
+        sum(self.timers(step))
+    }
+
+    fn timers<'a>(&'a self, step: &'a Step) -> impl Iterator<Item = Duration> + 'a {
+        step.items.iter().filter_map(move |item| match item {
+            Item::Timer(index) => self.metadata.timer[*index].duration().ok(),
+            _ => None,
+        })
+    }
+}
+
+/// Sum of the durations, saturating at the longest [Duration] instead of overflowing.
+pub(crate) fn sum(durations: impl Iterator<Item = Duration>) -> Duration {
+    durations.fold(Duration::ZERO, Duration::saturating_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duration::Overlap;
+    use crate::parse;
+    use std::time::Duration;
+
+    #[test]
+    fn estimated_duration() {
+        let recipe = parse(
+            "\
+Boil the @pasta for ~{10%minutes} and the @sauce for ~{15%min}.
+
+Let it rest for ~{1/2%hour}.
+
+== Dessert ==
+
+Chill the @cream for ~{2%hours}. Whip it for ~{90%sec}.
+",
+        )
+        .unwrap();
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        assert_eq!(recipe.step_duration(&recipe.steps[0]), minutes(25));
+        assert_eq!(
+            recipe.estimated_duration(),
+            minutes(175) + Duration::from_secs(90)
+        );
+        assert_eq!(recipe.estimated_duration_with(Overlap::Step), minutes(165));
+        assert_eq!(
+            recipe.estimated_duration_with(Overlap::Section),
+            minutes(150)
+        );
+
+        let recipe = parse("---\ntime: 1 hour\n---\nBake for ~{20%minutes}.\n").unwrap();
+        assert_eq!(recipe.estimated_duration(), minutes(60));
+        assert_eq!(
+            parse("Serve.\n").unwrap().estimated_duration(),
+            Duration::ZERO
+        );
+        let source = "Wait ~{200000000000000%days} and ~{200000000000000%days}.\n";
+        let recipe = parse(source).unwrap();
+        assert_eq!(recipe.estimated_duration(), Duration::MAX);
+        assert_eq!(recipe.step_duration(&recipe.steps[0]), Duration::MAX);
+        assert_eq!(recipe.timer_schedule()[1].end, Duration::MAX);
+    }
+
+    #[test]
+    fn timer_schedule() {
+        let recipe = parse(
+            "Boil the @pasta for ~{10%minutes} and the @sauce for ~{15%min}.\n\nServe.\n\n\
+             Chill for ~{3%hours}, with ~{2%spoons} of @cream, rest ~{1-2%hours}.\n",
+        )
+        .unwrap();
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let schedule: Vec<_> = recipe
+            .timer_schedule()
+            .iter()
+            .map(|timer| (timer.timer, timer.step, timer.start, timer.end))
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![
+                (0, 1, minutes(0), minutes(10)),
+                (1, 1, minutes(10), minutes(25)),
+                (2, 3, minutes(25), minutes(205)),
+                (4, 3, minutes(205), minutes(265)),
+            ]
+        );
+    }
+}
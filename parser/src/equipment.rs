@@ -0,0 +1,111 @@
+//! The cookware needed for a [Recipe], see [Recipe::equipment_list].
+
+use crate::Recipe;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Words for the size of cookware, like in `#large bowl{}`.
+const SIZES: [&str; 6] = ["small", "medium", "large", "big", "little", "extra-large"];
+
+/// A piece of cookware of [Recipe::equipment_list].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Equipment {
+    /// Name of the cookware without its size, like `bowl`.
+    pub name: String,
+    /// The size, like `large`, if the name starts with one.
+    pub size: Option<String>,
+    /// How many are needed at the same time.
+    pub count: usize,
+}
+
+impl fmt::Display for Equipment {
+    /// The cookware like `2 × large bowl`, without the count if a single one is needed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count > 1 {
+            write!(f, "{} × ", self.count)?;
+        }
+        if let Some(size) = &self.size {
+            write!(f, "{} ", size)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Recipe {
+    /// The cookware of the recipe, in order of the first mentioning. Names are compared
+    /// case-insensitively, the first spelling is kept.
+    ///
+    /// Cookware mentioned several times in a step is needed several times, like the two bowls in
+    /// `Whisk the eggs in a #large bowl{} and the cream in another #large bowl{}.`, while
+    /// mentions in different steps are taken to use the same one.
+    pub fn equipment_list(&self) -> Vec<Equipment> {
+        let mut names: Vec<&str> = vec![];
+        self.metadata.cookware.iter().for_each(|name| {
+            if !names.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        });
+        names
+            .into_iter()
+            .map(|name| {
+                let count = self
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        self.step_cookware(step)
+                            .iter()
+                            .filter(|cookware| cookware.eq_ignore_ascii_case(name))
+                            .count()
+                    })
+                    .max()
+                    .unwrap_or_default()
+                    .max(1);
+                let (size, name) = match name.split_once(' ') {
+                    Some((size, rest)) if SIZES.contains(&size.to_lowercase().as_str()) => {
+                        (Some(size.to_string()), rest)
+                    }
+                    _ => (None, name),
+                };
+                Equipment {
+                    name: name.to_string(),
+                    size,
+                    count,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equipment::Equipment;
+    use crate::parse;
+
+    #[test]
+    fn equipment_list() {
+        let recipe = parse(
+            "\
+Whisk the @eggs{2} in a #large bowl{} and the @cream in another #large bowl{}.
+
+Fold both into a #Large bowl{} and bake in the #oven.
+
+Serve on a #plate.
+",
+        )
+        .unwrap();
+        let equipment = recipe.equipment_list();
+        assert_eq!(
+            equipment[0],
+            Equipment {
+                name: "bowl".to_string(),
+                size: Some("large".to_string()),
+                count: 2,
+            }
+        );
+        let written: Vec<String> = equipment.iter().map(Equipment::to_string).collect();
+        assert_eq!(written, vec!["2 × large bowl", "oven", "plate"]);
+    }
+}
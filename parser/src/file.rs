@@ -0,0 +1,167 @@
+//! Reading recipes from files.
+//!
+//! [parse_path] reads a `.cook` file, keeps its path in [Recipe::path] and finds its images like
+//! a [Collection](crate::Collection) does: `Pancakes.jpg` next to `Pancakes.cook` is the image of
+//! the recipe, `Pancakes.2.jpg` the image of its second step. Errors name the file.
+//!
+//! ```no_run
+//! use cook_with_rust_parser::parse_file;
+//!
+//! match parse_file("recipes/Pancakes.cook") {
+//!     Ok(recipe) => println!("{:?}", recipe.image),
+//!     Err(error) => eprint!("{}", error.render()),
+//! }
+//! ```
+
+use crate::collection::find_images;
+use crate::{parse_with, ParseError, ParseOptions, ParseOutput, Recipe};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Error returned if a recipe file can't be read or parsed.
+#[derive(Debug)]
+pub enum FileError {
+    /// Reading the file failed.
+    Io { path: PathBuf, error: io::Error },
+    /// Parsing the recipe failed.
+    Parse {
+        path: PathBuf,
+        source: String,
+        error: Box<ParseError>,
+    },
+}
+
+impl FileError {
+    /// Path of the file.
+    pub fn path(&self) -> &Path {
+        match self {
+            FileError::Io { path, .. } | FileError::Parse { path, .. } => path,
+        }
+    }
+
+    /// Render the error for humans. Parse errors show the offending line like
+    /// [Diagnostic::render_file](crate::Diagnostic::render_file).
+    pub fn render(&self) -> String {
+        match self {
+            FileError::Io { .. } => format!("error: {}\n", self),
+            FileError::Parse {
+                path,
+                source,
+                error,
+            } => error.diagnostic.render_file(source, path),
+        }
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            FileError::Parse { path, error, .. } => write!(
+                f,
+                "{}:{}:{}: {}",
+                path.display(),
+                error.line,
+                error.column,
+                error.diagnostic.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+/// Read the file and parse it into a [Recipe], like [parse](crate::parse). See [parse_path].
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Recipe, FileError> {
+    Ok(parse_path(path, &ParseOptions::default())?.recipe)
+}
+
+/// Read the file and parse it with the options, like [parse_with]. The path is kept in
+/// [Recipe::path], so recipes without a title are named like their file, see [Recipe::title].
+/// Images next to the file are set as [Recipe::image] and [Recipe::step_images].
+pub fn parse_path(
+    path: impl AsRef<Path>,
+    options: &ParseOptions,
+) -> Result<ParseOutput, FileError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|error| FileError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut output = match parse_with(&source, options) {
+        Ok(output) => output,
+        Err(error) => {
+            return Err(FileError::Parse {
+                path: path.to_path_buf(),
+                source,
+                error: Box::new(error),
+            })
+        }
+    };
+    let (image, step_images) = find_images(path, &siblings(path));
+    let recipe = &mut output.recipe;
+    recipe.path = Some(path.to_path_buf());
+    recipe.image = image;
+    recipe.step_images = step_images;
+    Ok(output)
+}
+
+/// The files in the directory of the path. Empty if the directory can't be read.
+fn siblings(path: &Path) -> Vec<PathBuf> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let entries = match directory.as_os_str().is_empty() {
+        true => fs::read_dir("."),
+        false => fs::read_dir(directory),
+    };
+    entries
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| directory.join(entry.file_name()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_path, FileError, ParseOptions};
+    use std::fs;
+
+    #[test]
+    fn parse_path_with_images() {
+        let root = std::env::temp_dir().join(format!("cook-file-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        [
+            "Pancakes.jpg",
+            "Pancakes.2.png",
+            "Pancakes.x.jpg",
+            "Waffles.jpg",
+        ]
+        .iter()
+        .for_each(|image| fs::write(root.join(image), "").unwrap());
+        let path = root.join("Pancakes.cook");
+        fs::write(&path, "Mix @flour{200%g}.\n\nFry in a #pan.\n").unwrap();
+        let invalid = root.join("Invalid.cook");
+        fs::write(&invalid, "Mix\n@flour{200}.\n").unwrap();
+
+        let recipe = parse_path(&path, &ParseOptions::default()).map(|output| output.recipe);
+        let error = parse_path(&invalid, &ParseOptions::strict()).unwrap_err();
+        let missing = parse_path(root.join("Missing.cook"), &ParseOptions::default());
+        fs::remove_dir_all(&root).unwrap();
+
+        let recipe = recipe.unwrap();
+        assert_eq!(recipe.path, Some(path));
+        assert_eq!(recipe.title(), Some("Pancakes"));
+        assert_eq!(recipe.image, Some(root.join("Pancakes.jpg")));
+        assert_eq!(recipe.step_images, vec![(2, root.join("Pancakes.2.png"))]);
+
+        assert_eq!(error.path(), invalid);
+        let location = format!("{}:2:", invalid.display());
+        assert!(error.to_string().starts_with(&location));
+        assert!(error.render().contains(&format!("--> {}", location)));
+        assert!(matches!(missing, Err(FileError::Io { .. })));
+    }
+}
@@ -0,0 +1,360 @@
+//! Formatter for CookLang source, e.g. to run in a pre-commit hook.
+//!
+//! [format] only changes the layout of a recipe, never its meaning:
+//!
+//! * metadata is written as `>> key: value` and moved to the top, servings first,
+//! * whitespace is trimmed and runs of spaces and blank lines are collapsed, except in names,
+//! * amounts are written like `1/2` and braces are only kept where they are needed,
+//! * sections, notes and comments are written as `== name ==`, `> note` and `// comment`.
+
+use crate::writer::number_to_cooklang;
+use crate::{number_value, CookParser, Rule};
+use pest::iterators::Pair;
+use pest::Parser;
+
+/// Options of [format].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /// Move the metadata lines to the top of the recipe. Defaults to `true`.
+    pub metadata_first: bool,
+    /// Sort the metadata keys alphabetically, after the servings. Only applies if
+    /// [metadata_first](FormatOptions::metadata_first) is set. Defaults to `false`.
+    pub sort_metadata: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            metadata_first: true,
+            sort_metadata: false,
+        }
+    }
+}
+
+/// Format CookLang source. Source which can't be parsed is returned unchanged.
+pub fn format(source: &str, options: &FormatOptions) -> String {
+    let cook_lang = match CookParser::parse(Rule::cook_lang, source) {
+        Ok(mut pairs) => pairs.next().unwrap(),
+        Err(_) => return source.to_string(),
+    };
+    let mut front_matter = None;
+    let mut metadata: Vec<(String, String)> = vec![];
+    let mut lines: Vec<String> = vec![];
+    let mut current = String::new();
+    let mut position = 0;
+    let mut skip_to = 0;
+    for pair in cook_lang.into_inner() {
+        let span = pair.as_span();
+        if pair.as_rule() == Rule::EOI || span.start() < skip_to {
+            continue;
+        }
+        match source[position..span.start()].matches('\n').count() {
+            0 if current.is_empty() => {}
+            0 => current.push(' '),
+            newlines => {
+                lines.push(std::mem::take(&mut current));
+                if newlines > 1 {
+                    lines.push(String::new());
+                }
+            }
+        }
+        position = span.end();
+        match pair.as_rule() {
+            Rule::front_matter => front_matter = Some(pair.as_str().trim_end().to_string()),
+            Rule::metadata => {
+                // The value ends at a comment, which is kept after it.
+                let line_end = source[span.end()..]
+                    .find('\n')
+                    .map_or(source.len(), |end| span.end() + end);
+                let property = pair.into_inner().next().unwrap();
+                let is_servings = property.as_rule() == Rule::servings;
+                let mut key_value = property.into_inner();
+                let key = if is_servings {
+                    "servings"
+                } else {
+                    key_value.next().unwrap().as_str()
+                };
+                let value = key_value.next().unwrap().as_str();
+                let comment = source[span.end()..line_end].trim();
+                let comment = Some(comment).filter(|comment| comment.starts_with("//"));
+                let mut line = format!(">> {}: {}", key, collapse(value.trim()));
+                if let Some(comment) = comment {
+                    line.push(' ');
+                    line.push_str(&format_comment(comment));
+                }
+                skip_to = line_end;
+                position = line_end;
+                if options.metadata_first {
+                    metadata.push((key.to_string(), line));
+                    // The line is moved as a whole, including its line break.
+                    position = (line_end + 1).min(source.len());
+                } else {
+                    current.push_str(&line);
+                }
+            }
+            Rule::section => {
+                let name = pair.into_inner().next().unwrap().as_str().trim();
+                if name.is_empty() {
+                    current.push_str("==");
+                } else {
+                    current.push_str(&format!("== {} ==", name));
+                }
+            }
+            Rule::note => {
+                let note = pair.as_str()[1..].trim();
+                current.push_str(format!("> {}", collapse(note)).trim_end());
+            }
+            Rule::comment => current.push_str(&format_comment(pair.as_str())),
+            Rule::line => current.push_str(&format_line(source, pair)),
+            _ => current.push_str(pair.as_str().trim()),
+        }
+    }
+    lines.push(current);
+
+    let mut result = String::new();
+    if let Some(front_matter) = front_matter {
+        result.push_str(&front_matter);
+        result.push('\n');
+    }
+    if options.sort_metadata {
+        metadata.sort_by(|(a, _), (b, _)| (a != "servings", a).cmp(&(b != "servings", b)));
+    } else {
+        metadata.sort_by_key(|(key, _)| key != "servings");
+    }
+    metadata.iter().for_each(|(_, line)| {
+        result.push_str(line);
+        result.push('\n');
+    });
+    if !metadata.is_empty() {
+        result.push('\n');
+    }
+    let mut blank = true;
+    lines.iter().map(|line| line.trim_end()).for_each(|line| {
+        if !line.is_empty() || !blank {
+            result.push_str(line);
+            result.push('\n');
+        }
+        blank = line.is_empty();
+    });
+    while result.ends_with("\n\n") {
+        result.pop();
+    }
+    result
+}
+
+/// A comment like `// text`.
+fn format_comment(comment: &str) -> String {
+    let text = comment.trim_start_matches('/').trim();
+    if text.is_empty() {
+        "//".to_string()
+    } else {
+        format!("// {}", text)
+    }
+}
+
+/// A line of instructions, with the text between the mentions kept apart from the whitespace.
+fn format_line(source: &str, line: Pair<Rule>) -> String {
+    let mut result = String::new();
+    let mut position = line.as_span().start();
+    let end = line.as_span().end();
+    for part in line.into_inner() {
+        result.push_str(&collapse(&source[position..part.as_span().start()]));
+        position = part.as_span().end();
+        match part.as_rule() {
+            Rule::ingredient => {
+                let needs_braces = multi_word_follows(&source[position..]);
+                result.push_str(&format_ingredient(part, needs_braces));
+            }
+            Rule::cookware => {
+                let words: Vec<&str> = part.into_inner().map(|word| word.as_str()).collect();
+                result.push('#');
+                result.push_str(&words.join(" "));
+                if words.len() > 1 {
+                    result.push_str("{}");
+                }
+            }
+            Rule::timer => {
+                result.push('~');
+                result.push_str(&format_amount(part));
+            }
+            Rule::comment => {
+                let trimmed = result.trim_end().len();
+                result.truncate(trimmed);
+                result.push(' ');
+                result.push_str(&format_comment(part.as_str()));
+            }
+            _ => result.push_str(part.as_str()),
+        }
+    }
+    result.push_str(&collapse(&source[position..end]));
+    result.trim().to_string()
+}
+
+/// An ingredient like `@?fresh basil{1%bunch}(chopped)`.
+fn format_ingredient(ingredient: Pair<Rule>, needs_braces: bool) -> String {
+    let source = ingredient.as_str();
+    let mut modifiers = String::new();
+    let mut words = vec![];
+    let mut note = None;
+    let mut invalid = None;
+    let mut has_amount = false;
+    ingredient
+        .clone()
+        .into_inner()
+        .for_each(|part| match part.as_rule() {
+            Rule::ingredient_modifier => modifiers.push_str(part.as_str()),
+            Rule::name | Rule::recipe_path | Rule::text => words.push(part.as_str()),
+            Rule::number => has_amount = true,
+            Rule::modified => note = Some(part.as_str()),
+            Rule::invalid_amount => invalid = Some(part.as_str().to_string()),
+            _ => {}
+        });
+    let name = words.join(" ");
+    let braces = if let Some(invalid) = invalid {
+        invalid
+    } else if has_amount {
+        format_amount(ingredient)
+    } else if source.contains("{}") && (name.contains(' ') || needs_braces) {
+        "{}".to_string()
+    } else {
+        String::new()
+    };
+    format!(
+        "@{}{}{}{}",
+        modifiers,
+        name,
+        braces,
+        note.unwrap_or_default()
+    )
+}
+
+/// The number like `1/2`. Decimals which aren't a fraction are kept as written, so `1,15` keeps
+/// its decimal separator.
+fn format_number(number: Pair<Rule>) -> String {
+    let formatted = number_to_cooklang(number_value(number.clone()));
+    match formatted.contains('.') {
+        true => number.as_str().to_string(),
+        false => formatted,
+    }
+}
+
+/// The amount in braces of an ingredient or timer, like `{1/2*%kg}`.
+fn format_amount(pair: Pair<Rule>) -> String {
+    let mut amount = String::from("{");
+    pair.into_inner().for_each(|part| match part.as_rule() {
+        Rule::number => amount.push_str(&format_number(part)),
+        Rule::ingredient_separator => amount.push('|'),
+        Rule::scaling => amount.push('*'),
+        Rule::unit => {
+            amount.push('%');
+            amount.push_str(part.as_str());
+        }
+        Rule::timer_part => {
+            if !amount.ends_with('{') {
+                amount.push(' ');
+            }
+            part.into_inner().for_each(|part| match part.as_rule() {
+                Rule::timer_range => {
+                    let numbers: Vec<String> = part
+                        .into_inner()
+                        .map(format_number)
+                        .collect();
+                    amount.push_str(&numbers.join("-"));
+                }
+                Rule::number => amount.push_str(&format_number(part)),
+                _ => {
+                    amount.push('%');
+                    amount.push_str(part.as_str());
+                }
+            });
+        }
+        _ => {}
+    });
+    amount.push('}');
+    amount
+}
+
+/// Whether the text after a mention would turn into part of its name without the braces, like
+/// ` and pepper{}` after `@salt{}`.
+fn multi_word_follows(rest: &str) -> bool {
+    let rest =
+        rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ');
+    rest.starts_with('{')
+}
+
+/// Collapse runs of spaces into a single space.
+fn collapse(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    text.chars().for_each(|c| {
+        if c != ' ' || !result.ends_with(' ') {
+            result.push(c);
+        }
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{format, FormatOptions};
+    use crate::parse;
+
+    #[test]
+    fn format_source() {
+        let source = "
+\
+Mix   the @flour{} with @milk{2/4%l}  and @salt{} and pepper{}.   // keep it smooth
+>>title:Crêpes
+Heat a #frying pan{} with @butter{}(melted), bake for ~{02%minutes}.
+Rest ~{ 1h30m }, or ~{1 - 2%hours}.
+
+=Serving=
+>   Serve warm
+>> servings: 4|6
+//done
+";
+        let formatted = format(source, &FormatOptions::default());
+        assert_eq!(
+            formatted,
+            "\
+>> servings: 4|6
+>> title: Crêpes
+
+Mix the @flour with @milk{1/2%l} and @salt{} and pepper{}. // keep it smooth
+Heat a #frying pan{} with @butter(melted), bake for ~{2%minutes}.
+Rest ~{1%h 30%m}, or ~{1-2%hours}.
+
+== Serving ==
+> Serve warm
+// done
+"
+        );
+        assert_eq!(format(&formatted, &FormatOptions::default()), formatted);
+
+        let before = parse(source).unwrap();
+        let after = parse(&formatted).unwrap();
+        assert_eq!(before.metadata.ingredients, after.metadata.ingredients);
+        assert_eq!(before.metadata.cookware, after.metadata.cookware);
+        assert_eq!(before.metadata.timer, after.metadata.timer);
+        assert_eq!(before.steps.len(), after.steps.len());
+        let source = "Add @oil{0,15%l} and @milk{1,5%l}.\n";
+        assert_eq!(
+            format(source, &FormatOptions::default()),
+            "Add @oil{0,15%l} and @milk{3/2%l}.\n"
+        );
+
+        let options = FormatOptions {
+            metadata_first: false,
+            ..FormatOptions::default()
+        };
+        let source = "Boil @water.\n>>source : grandma\n";
+        assert_eq!(
+            format(source, &options),
+            "Boil @water.\n>> source: grandma\n"
+        );
+        let source = "Boil @water.\n>>source:https://example.com/tea   //from the web\n";
+        assert_eq!(
+            format(source, &options),
+            "Boil @water.\n>> source: https://example.com/tea // from the web\n"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! YAML front matter.
+//!
+//! A recipe can start with metadata in YAML, delimited by `---` lines:
+//!
+//! ```text
+//! ---
+//! servings: 2|4
+//! tags: [breakfast, quick]
+//! ---
+//! Crack the @eggs{2}.
+//! ```
+//!
+//! Scalars are converted to strings and lists of scalars are joined with the
+//! [list separator](crate::Separators::list), like `, `. Metadata from
+//! `>>` lines takes precedence over the front matter.
+//!
+//! Parsing the YAML requires the `front-matter` feature. Without it, front matter is ignored with
+//! a warning.
+
+#[cfg(any(test, feature = "front-matter"))]
+use crate::servings_number;
+#[cfg(any(test, feature = "front-matter"))]
+use serde_yaml::Value;
+
+/// Metadata read from the front matter.
+#[derive(Debug, Default)]
+pub(crate) struct FrontMatter {
+    pub servings: Option<Vec<usize>>,
+    pub servings_labels: Vec<String>,
+    pub entries: Vec<(String, String)>,
+}
+
+/// Parse the YAML between the `---` lines.
+#[cfg(not(any(test, feature = "front-matter")))]
+pub(crate) fn parse(_yaml: &str, _list_separator: char) -> Result<FrontMatter, String> {
+    Err("Front matter requires the front-matter feature".to_string())
+}
+
+/// Parse the YAML between the `---` lines.
+#[cfg(any(test, feature = "front-matter"))]
+pub(crate) fn parse(yaml: &str, list_separator: char) -> Result<FrontMatter, String> {
+    let mut front_matter = FrontMatter::default();
+    if yaml.trim().is_empty() {
+        return Ok(front_matter);
+    }
+    let mapping = match serde_yaml::from_str(yaml).map_err(|e| e.to_string())? {
+        Value::Mapping(mapping) => mapping,
+        Value::Null => return Ok(front_matter),
+        _ => return Err("Front matter is not a mapping".to_string()),
+    };
+    for (key, value) in mapping {
+        let key = scalar_to_string(&key).ok_or("Front matter keys have to be scalars")?;
+        if key == "servings" {
+            let labels = servings_labels(&value)?;
+            let servings: Option<Vec<usize>> =
+                labels.iter().map(|label| servings_number(label)).collect();
+            front_matter.servings =
+                Some(servings.ok_or("Servings have to contain a number, like 2 people")?);
+            front_matter.servings_labels = labels;
+        } else {
+            let value = value_to_string(&value, list_separator)
+                .ok_or_else(|| format!("Unsupported value for {} in front matter", key))?;
+            front_matter.entries.push((key, value));
+        }
+    }
+    Ok(front_matter)
+}
+
+/// Labels of the servings given as a number, a list or a string like `2 people|4 people`.
+#[cfg(any(test, feature = "front-matter"))]
+fn servings_labels(value: &Value) -> Result<Vec<String>, String> {
+    let labels = match value {
+        Value::String(servings) => servings
+            .split('|')
+            .map(|serving| Some(serving.trim().to_string()))
+            .collect(),
+        Value::Sequence(servings) => servings.iter().map(scalar_to_string).collect::<Option<_>>(),
+        value => scalar_to_string(value).map(|serving| vec![serving]),
+    };
+    labels.ok_or_else(|| "Servings are numbers separated by |, like 2|4".to_string())
+}
+
+#[cfg(any(test, feature = "front-matter"))]
+fn value_to_string(value: &Value, list_separator: char) -> Option<String> {
+    match value {
+        Value::Sequence(values) => {
+            let values: Option<Vec<String>> = values.iter().map(scalar_to_string).collect();
+            Some(values?.join(&format!("{} ", list_separator)))
+        }
+        value => scalar_to_string(value),
+    }
+}
+
+#[cfg(any(test, feature = "front-matter"))]
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::front_matter::parse;
+    use crate::{parse_with, ParseOptions};
+
+    #[test]
+    fn front_matter() {
+        let front_matter = parse("servings: 2 people|4 people\n", ',').unwrap();
+        assert_eq!(front_matter.servings_labels, vec!["2 people", "4 people"]);
+        let yaml = "servings: [2, 4]\ntags: [quick, 1]\nvegan: true\n";
+        let front_matter = parse(yaml, ',').unwrap();
+        assert_eq!(front_matter.servings, Some(vec![2, 4]));
+        assert_eq!(
+            front_matter.entries,
+            vec![
+                ("tags".to_string(), "quick, 1".to_string()),
+                ("vegan".to_string(), "true".to_string())
+            ]
+        );
+        assert!(parse("servings: some\n", ',').is_err());
+        assert!(parse("- a list\n", ',').is_err());
+
+        let source = "---\nservings: 2|4\ntitle: Eggs\nsource: book\n---\n>> source: web\nCrack the @eggs.\n";
+        let output = parse_with(source, &ParseOptions::strict()).unwrap();
+        let recipe = output.recipe;
+        assert!(output.warnings.is_empty());
+        assert_eq!(recipe.metadata.servings, Some(vec![2, 4]));
+        assert_eq!(recipe.metadata.entries["title"], "Eggs");
+        assert_eq!(recipe.metadata.entries["source"], "web");
+        assert!(recipe.instruction.starts_with(">> source: web\nCrack"));
+
+        let output = parse_with("---\ntitle: [\n---\nStir.\n", &ParseOptions::default()).unwrap();
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.recipe.steps().len(), 1);
+    }
+}
@@ -0,0 +1,208 @@
+//! Shopping lists for task and grocery apps like Todoist, AnyList or Bring.
+//!
+//! A [GroceryList] has a section per aisle with the items to buy and whether they are checked
+//! off. It can be written as a Markdown task list, which these apps import when pasted, or as
+//! JSON with the `json` feature:
+//!
+//! ```
+//! use cook_with_rust_parser::aisle::AisleConf;
+//! use cook_with_rust_parser::{parse, QuantityFormatter, ShoppingList};
+//!
+//! let recipe = parse("Mix @flour{200%g} with @eggs{2} and @salt.\n").unwrap();
+//! let aisle = AisleConf::parse("[baking]\nflour\n").unwrap();
+//! let list = ShoppingList::from_recipes(vec![&recipe]);
+//! let mut groceries = list.to_grocery_list(&aisle, &QuantityFormatter::default());
+//! groceries.check("salt");
+//! assert_eq!(
+//!     groceries.to_text(),
+//!     "## baking\n- [ ] 200 g flour\n\n## other\n- [ ] 2 eggs\n- [x] salt\n"
+//! );
+//! ```
+
+use crate::aisle::AisleConf;
+use crate::{QuantityFormatter, ShoppingList};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A shopping list grouped by aisle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GroceryList {
+    /// The sections in the order of the aisle configuration.
+    pub sections: Vec<GrocerySection>,
+}
+
+/// The items of an aisle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GrocerySection {
+    /// Name of the aisle, like `fruit and veg`.
+    pub name: String,
+    /// The items in the order of the shopping list.
+    pub items: Vec<GroceryItem>,
+}
+
+/// An item to buy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GroceryItem {
+    /// Name of the ingredient.
+    pub name: String,
+    /// The quantities, like `300 g + 2 tbsp`. Empty if there is none.
+    pub quantity: String,
+    /// The item has been bought or is at hand.
+    pub checked: bool,
+}
+
+impl ShoppingList {
+    /// The items grouped by the categories of the aisle configuration, unchecked. See
+    /// [ShoppingList::categorize].
+    pub fn to_grocery_list(
+        &self,
+        aisle: &AisleConf,
+        quantities: &QuantityFormatter,
+    ) -> GroceryList {
+        let sections = self
+            .categorize(aisle)
+            .into_iter()
+            .map(|(name, items)| GrocerySection {
+                name,
+                items: items
+                    .into_iter()
+                    .map(|item| {
+                        let written: Vec<String> = item
+                            .quantities
+                            .quantities
+                            .iter()
+                            .map(|quantity| quantities.quantity(quantity))
+                            .collect();
+                        GroceryItem {
+                            name: item.name.clone(),
+                            quantity: written.join(" + "),
+                            checked: false,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        GroceryList { sections }
+    }
+}
+
+impl GroceryList {
+    /// Check off the items with the name, compared case-insensitively. Returns whether there was
+    /// such an item.
+    pub fn check(&mut self, name: &str) -> bool {
+        let mut found = false;
+        self.items_mut()
+            .filter(|item| item.name.to_lowercase() == name.to_lowercase())
+            .for_each(|item| {
+                item.checked = true;
+                found = true;
+            });
+        found
+    }
+
+    /// Uncheck all items.
+    pub fn uncheck_all(&mut self) {
+        self.items_mut().for_each(|item| item.checked = false);
+    }
+
+    /// All items, section after section.
+    pub fn items(&self) -> impl Iterator<Item = &GroceryItem> {
+        self.sections.iter().flat_map(|section| &section.items)
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut GroceryItem> {
+        self.sections
+            .iter_mut()
+            .flat_map(|section| &mut section.items)
+    }
+
+    /// The list as Markdown task list, with a heading per section and items like
+    /// `- [ ] 200 g flour`.
+    pub fn to_text(&self) -> String {
+        let sections: Vec<String> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let mut text = format!("## {}\n", section.name);
+                section.items.iter().for_each(|item| {
+                    let check = if item.checked { 'x' } else { ' ' };
+                    match item.quantity.is_empty() {
+                        true => text += &format!("- [{}] {}\n", check, item.name),
+                        false => {
+                            text += &format!("- [{}] {} {}\n", check, item.quantity, item.name)
+                        }
+                    }
+                });
+                text
+            })
+            .collect();
+        sections.join("\n")
+    }
+
+    /// The list as JSON, like
+    /// `{"sections":[{"name":"other","items":[{"name":"salt","quantity":"","checked":false}]}]}`.
+    #[cfg(any(all(test, feature = "serde"), feature = "json"))]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aisle::AisleConf;
+    use crate::grocery::{GroceryItem, GroceryList, GrocerySection};
+    use crate::{parse, QuantityFormatter, ShoppingList};
+
+    #[test]
+    fn grocery_list() {
+        let first = parse("Cook @pasta{200%g} with @Salt and @tomatoes{400%g}.\n").unwrap();
+        let second = parse("Add @tomatoes{2%tbsp} and @pasta{100%g}.\n").unwrap();
+        let list = ShoppingList::from_recipes(vec![&first, &second]);
+        let aisle = AisleConf::parse("[vegetables]\ntomatoes\n[spices]\nsalt\n").unwrap();
+        let mut groceries = list.to_grocery_list(&aisle, &QuantityFormatter::default());
+        assert!(groceries.check("salt"));
+        assert!(!groceries.check("basil"));
+        let item = |name: &str, quantity: &str, checked| GroceryItem {
+            name: name.to_string(),
+            quantity: quantity.to_string(),
+            checked,
+        };
+        assert_eq!(
+            groceries,
+            GroceryList {
+                sections: vec![
+                    GrocerySection {
+                        name: "vegetables".to_string(),
+                        items: vec![item("tomatoes", "400 g + 2 tbsp", false)],
+                    },
+                    GrocerySection {
+                        name: "spices".to_string(),
+                        items: vec![item("Salt", "", true)],
+                    },
+                    GrocerySection {
+                        name: "other".to_string(),
+                        items: vec![item("pasta", "300 g", false)],
+                    },
+                ],
+            }
+        );
+        assert_eq!(groceries.items().filter(|item| item.checked).count(), 1);
+        assert!(groceries.to_text().starts_with(
+            "## vegetables\n- [ ] 400 g + 2 tbsp tomatoes\n\n## spices\n- [x] Salt\n"
+        ));
+        #[cfg(feature = "serde")]
+        assert!(groceries.to_json().starts_with(
+            "{\"sections\":[{\"name\":\"vegetables\",\"items\":[{\"name\":\"tomatoes\",\
+             \"quantity\":\"400 g + 2 tbsp\",\"checked\":false}]}"
+        ));
+
+        groceries.uncheck_all();
+        assert!(groceries.items().all(|item| !item.checked));
+    }
+}
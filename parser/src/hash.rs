@@ -0,0 +1,132 @@
+//! Hash over the content of a recipe.
+
+use crate::diff::metadata;
+use crate::steps::Item;
+use crate::Recipe;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64 bit FNV-1a, which unlike [std::collections::hash_map::DefaultHasher] is the same on every
+/// platform and Rust version.
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(FNV_OFFSET)
+    }
+}
+
+impl Fnv {
+    /// Add the field, followed by a separator so `ab`, `c` differs from `a`, `bc`.
+    pub(crate) fn field(&mut self, field: &str) {
+        for byte in field.bytes().chain(Some(0)) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+impl Recipe {
+    /// A hash over the metadata and the steps of the recipe, to detect changes of its content.
+    ///
+    /// Comments, blank lines and the whitespace of the text don't change the hash, nor does
+    /// writing the metadata in a front matter instead of `>>` lines. The hash is the same on
+    /// every platform, so it can be stored, e.g. by sync tools and caches.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = Fnv::default();
+        metadata(self).iter().for_each(|(key, value)| {
+            hash.field(">>");
+            hash.field(key);
+            hash.field(value);
+        });
+        let metadata = &self.metadata;
+        self.sections.iter().for_each(|section| {
+            hash.field("==");
+            hash.field(section.name.as_deref().unwrap_or_default());
+            hash.field(&format!("{:?}", section.steps));
+        });
+        for step in &self.steps {
+            hash.field("step");
+            for item in &step.items {
+                match item {
+                    Item::Text(text) => {
+                        let words: Vec<&str> = text.split_whitespace().collect();
+                        hash.field(&words.join(" "));
+                    }
+                    Item::Ingredient(index) => {
+                        let specifier = &metadata.ingredients_specifiers[*index];
+                        let unit = specifier.unit.as_deref().or_else(|| {
+                            metadata
+                                .ingredients
+                                .get(specifier.ingredient_key())
+                                .and_then(|ingredient| ingredient.unit.as_deref())
+                        });
+                        hash.field("@");
+                        hash.field(&specifier.ingredient);
+                        hash.field(&format!("{:?}", specifier.amount_in_step));
+                        hash.field(unit.unwrap_or_default());
+                        hash.field(specifier.note.as_deref().unwrap_or_default());
+                        let flags = [specifier.optional, specifier.hidden, specifier.reference];
+                        hash.field(&format!("{:?}", flags));
+                    }
+                    Item::Cookware(index) => {
+                        hash.field("#");
+                        hash.field(&metadata.cookware[*index]);
+                    }
+                    Item::Timer(index) => {
+                        let timer = &metadata.timer[*index];
+                        hash.field("~");
+                        hash.field(&format!("{:?} {:?}", timer.amount, timer.max));
+                        hash.field(&timer.unit);
+                    }
+                    Item::Note(note) => {
+                        hash.field(">");
+                        hash.field(note.trim());
+                    }
+                    Item::RecipeRef { path, amount } => {
+                        hash.field("@./");
+                        hash.field(path);
+                        hash.field(&format!("{:?}", amount));
+                    }
+                    Item::Temperature { value, unit } => {
+                        hash.field("°");
+                        hash.field(&format!("{:?}", value));
+                        hash.field(unit);
+                    }
+                }
+            }
+        }
+        hash.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn content_hash() {
+        let hash = |source: &str| parse(source).unwrap().content_hash();
+        let recipe = hash(">> servings: 2\nMix @flour{200%g} and @water{100%ml}.\n\nBake.\n");
+        assert_eq!(
+            recipe,
+            hash(
+                ">> servings: 2\n\n// the dough\nMix   @flour{200%g}\nand @water{100%ml}.\n\n\n\
+                 Bake. // for now\n"
+            )
+        );
+        assert_ne!(
+            recipe,
+            hash(">> servings: 2\nMix @flour{250%g} and @water{100%ml}.\n\nBake.\n")
+        );
+        assert_ne!(
+            recipe,
+            hash(">> servings: 4\nMix @flour{200%g} and @water{100%ml}.\n\nBake.\n")
+        );
+        assert_ne!(
+            recipe,
+            hash(">> servings: 2\nMix @flour{200%g} and @water{100%ml}. Bake.\n")
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Export of the timers of a [Recipe] as iCalendar events.
+
+use crate::render::step_text;
+use crate::time::iso_duration;
+use crate::{Item, Recipe};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl Recipe {
+    /// An iCalendar (RFC 5545) calendar for cooking the recipe from `start` on.
+    ///
+    /// The calendar has an event for the whole recipe, lasting its
+    /// [time](crate::Metadata::time) or the sum of its timers, and an event with an alarm at its
+    /// end for every timer. The timers are assumed to run one after another in the order of the
+    /// steps. Timers with a unit which isn't a time unit are left out.
+    pub fn to_ical(&self, start: SystemTime) -> String {
+        let start = start
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let title = self.title().unwrap_or("Recipe");
+        let mut events = vec![];
+        let mut offset = 0;
+        self.steps.iter().for_each(|step| {
+            step.items.iter().for_each(|item| {
+                let timer = match item {
+                    Item::Timer(index) => &self.metadata.timer[*index],
+                    _ => return,
+                };
+                let seconds = match timer.duration() {
+                    Ok(duration) => duration.as_secs_f64().round() as u64,
+                    Err(_) => return,
+                };
+                let text = step_text(self, step);
+                events.push(vec![
+                    format!("UID:{}-timer-{}@cook-with-rust", start, events.len() + 1),
+                    format!("DTSTAMP:{}", format_time(start)),
+                    format!("DTSTART:{}", format_time(start.saturating_add(offset))),
+                    format!("DURATION:{}", iso_duration(Duration::from_secs(seconds))),
+                    format!(
+                        "SUMMARY:{}",
+                        escape(&format!("{}: step {}", title, step.number))
+                    ),
+                    format!("DESCRIPTION:{}", escape(&text)),
+                    "BEGIN:VALARM".to_string(),
+                    "ACTION:DISPLAY".to_string(),
+                    "TRIGGER;RELATED=END:PT0S".to_string(),
+                    format!("DESCRIPTION:{}", escape(&text)),
+                    "END:VALARM".to_string(),
+                ]);
+                offset = offset.saturating_add(seconds);
+            });
+        });
+        let total = self.metadata.time().map_or(offset, |time| time.as_secs());
+        events.insert(
+            0,
+            vec![
+                format!("UID:{}-recipe@cook-with-rust", start),
+                format!("DTSTAMP:{}", format_time(start)),
+                format!("DTSTART:{}", format_time(start)),
+                format!("DURATION:{}", iso_duration(Duration::from_secs(total))),
+                format!("SUMMARY:{}", escape(title)),
+            ],
+        );
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//cook-with-rust//EN".to_string(),
+        ];
+        events.into_iter().for_each(|event| {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.extend(event);
+            lines.push("END:VEVENT".to_string());
+        });
+        lines.push("END:VCALENDAR".to_string());
+        lines.iter().map(|line| fold(line) + "\r\n").collect()
+    }
+}
+
+/// A UTC date and time like `20211027T183000Z`.
+fn format_time(seconds: u64) -> String {
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // Civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Escape text for a property value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a line into lines of at most 75 bytes, continued by lines starting with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    line.chars().for_each(|c| {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    });
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ical::format_time;
+    use crate::parse;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn to_ical() {
+        let recipe = parse(
+            "\
+---
+title: Soft Eggs
+---
+Boil the @eggs{2} for ~{6%minutes}.
+
+Cool them in @cold water for ~{1%min}, then peel them carefully without breaking the yolk.
+",
+        )
+        .unwrap();
+        let ical = recipe.to_ical(UNIX_EPOCH + Duration::from_secs(1635359400));
+        let lines: Vec<&str> = ical.split("\r\n").collect();
+        assert_eq!(lines[0], "BEGIN:VCALENDAR");
+        assert!(lines.contains(&"SUMMARY:Soft Eggs"));
+        assert!(lines.contains(&"DURATION:PT7M"));
+        assert!(lines.contains(&"DTSTART:20211027T183000Z"));
+        assert!(lines.contains(&"DTSTART:20211027T183600Z"));
+        assert!(lines.contains(&"SUMMARY:Soft Eggs: step 2"));
+        assert!(lines.contains(&"TRIGGER;RELATED=END:PT0S"));
+        let description =
+            "DESCRIPTION:Cool them in cold water for 1 min\\, then peel them carefully wi";
+        assert_eq!(lines[lines.len() - 6], description);
+        assert_eq!(lines[lines.len() - 5], " thout breaking the yolk.");
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 3);
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+
+        assert_eq!(format_time(951782400), "20000229T000000Z");
+        let source = "Wait ~{200000000000000%days} and ~{200000000000000%days}.\n";
+        let ical = parse(source).unwrap().to_ical(UNIX_EPOCH);
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 3);
+    }
+}
@@ -0,0 +1,480 @@
+//! Conversion from and to the JSON formats of other recipe managers, to migrate collections into
+//! CookLang and back.
+//!
+//! - [Paprika](https://www.paprikaapp.com/): a single recipe of an export. The
+//!   `.paprikarecipe` files of an export are gzipped and have to be decompressed first.
+//! - [Mealie](https://mealie.io/): a recipe of the API, `/api/recipes/{slug}`.
+//! - [Tandoor](https://tandoor.dev/): a recipe of the API, `/api/recipe/{id}/`.
+//!
+//! Like [Recipe::from_json_ld], ingredients are mentioned in the first step containing their
+//! name. Requires the `interop` feature.
+
+use crate::json_ld::{format_duration, ingredient_mention, instructions, mention, strings, Draft};
+use crate::render::{ingredient_text, step_text, step_text_with};
+use crate::time::{parse_duration, parse_iso_duration};
+use crate::{Amount, IngredientSpecifier, QuantityFormatter, Recipe, ShoppingList};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::Duration;
+
+/// Convert a recipe exported by Paprika.
+pub fn from_paprika(json: &str) -> Result<Recipe, Box<dyn Error>> {
+    let json: Value = serde_json::from_str(json)?;
+    let registry = Default::default();
+    let mut draft = Draft {
+        ingredients: lines(&json["ingredients"])
+            .iter()
+            .filter_map(|ingredient| ingredient_mention(ingredient, &registry))
+            .collect(),
+        steps: instructions(Some(&json["directions"])),
+        yields: strings(Some(&json["servings"])),
+        entries: vec![],
+    };
+    draft.insert("title", strings(Some(&json["name"])));
+    draft.insert("description", strings(Some(&json["description"])));
+    let source = strings(Some(&json["source_url"]));
+    if source.is_empty() {
+        draft.insert("source", strings(Some(&json["source"])));
+    } else {
+        draft.insert("source", source);
+    }
+    draft.insert("tags", strings(Some(&json["categories"])));
+    draft.insert("difficulty", strings(Some(&json["difficulty"])));
+    draft.insert_time("time", duration(&json["total_time"]));
+    draft.insert_time("prep time", duration(&json["prep_time"]));
+    draft.insert_time("cook time", duration(&json["cook_time"]));
+    draft.into_recipe()
+}
+
+/// Convert a recipe into the format of a Paprika export.
+pub fn to_paprika(recipe: &Recipe) -> Value {
+    to_paprika_with(recipe, &QuantityFormatter::default())
+}
+
+/// Convert a recipe into the format of a Paprika export like [to_paprika], with the amounts
+/// written by the formatter.
+pub fn to_paprika_with(recipe: &Recipe, quantities: &QuantityFormatter) -> Value {
+    let metadata = &recipe.metadata;
+    let list = ShoppingList::from_recipes(vec![recipe]);
+    let ingredients: Vec<String> = list
+        .items
+        .values()
+        .map(|item| ingredient_text(recipe, item, quantities))
+        .collect();
+    let mut directions = vec![];
+    recipe.sections().iter().for_each(|section| {
+        if let Some(name) = &section.name {
+            directions.push(name.clone());
+        }
+        recipe
+            .section_steps(section)
+            .iter()
+            .for_each(|step| directions.push(step_text_with(recipe, step, quantities)));
+    });
+    json!({
+        "name": metadata.title().unwrap_or_default(),
+        "description": metadata.get("description").unwrap_or_default(),
+        "ingredients": ingredients.join("\n"),
+        "directions": directions.join("\n\n"),
+        "servings": servings(recipe),
+        "total_time": metadata.time().map(format_duration).unwrap_or_default(),
+        "prep_time": metadata.prep_time().map(format_duration).unwrap_or_default(),
+        "cook_time": metadata.cook_time().map(format_duration).unwrap_or_default(),
+        "categories": metadata.tags(),
+        "difficulty": metadata.difficulty().unwrap_or_default(),
+        "source_url": metadata.get("source").unwrap_or_default(),
+    })
+}
+
+/// Convert a recipe of the Mealie API.
+pub fn from_mealie(json: &str) -> Result<Recipe, Box<dyn Error>> {
+    let json: Value = serde_json::from_str(json)?;
+    let registry = Default::default();
+    let ingredients = json["recipeIngredient"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let mut yields = strings(Some(&json["recipeYield"]));
+    if yields.is_empty() {
+        yields = strings(Some(&json["recipeServings"]));
+    }
+    let mut draft = Draft {
+        ingredients: ingredients
+            .iter()
+            .filter_map(|ingredient| match &ingredient["food"]["name"] {
+                Value::String(food) => mention(
+                    food,
+                    ingredient["quantity"].as_f64(),
+                    ingredient["unit"]["name"].as_str(),
+                    ingredient["note"].as_str(),
+                ),
+                _ => {
+                    let text = ["originalText", "display", "note"]
+                        .iter()
+                        .find_map(|key| ingredient[*key].as_str())
+                        .or_else(|| ingredient.as_str())?;
+                    ingredient_mention(text, &registry)
+                }
+            })
+            .collect(),
+        steps: vec![],
+        yields,
+        entries: vec![],
+    };
+    if let Some(steps) = json["recipeInstructions"].as_array() {
+        steps.iter().for_each(|step| {
+            let title = step["title"].as_str().map(str::trim);
+            let title = title.filter(|title| !title.is_empty()).map(str::to_string);
+            let mut texts = instructions(Some(&step["text"])).into_iter();
+            if let Some((_, text)) = texts.next() {
+                draft.steps.push((title, text));
+            }
+            draft.steps.extend(texts);
+        });
+    }
+    draft.insert("title", strings(Some(&json["name"])));
+    draft.insert("description", strings(Some(&json["description"])));
+    draft.insert("source", strings(Some(&json["orgURL"])));
+    draft.insert("tags", strings(Some(&json["tags"])));
+    draft.insert("course", strings(Some(&json["recipeCategory"])));
+    draft.insert_time("time", duration(&json["totalTime"]));
+    draft.insert_time("prep time", duration(&json["prepTime"]));
+    draft.insert_time("cook time", duration(&json["performTime"]));
+    draft.into_recipe()
+}
+
+/// Convert a recipe into the format of the Mealie API.
+pub fn to_mealie(recipe: &Recipe) -> Value {
+    to_mealie_with(recipe, &QuantityFormatter::default())
+}
+
+/// Convert a recipe into the format of the Mealie API like [to_mealie], with the amounts written
+/// by the formatter.
+pub fn to_mealie_with(recipe: &Recipe, quantities: &QuantityFormatter) -> Value {
+    let metadata = &recipe.metadata;
+    let list = ShoppingList::from_recipes(vec![recipe]);
+    let mut ingredients = vec![];
+    list.items.values().for_each(|item| {
+        let note = metadata.ingredients[&item.name].notes.join(", ");
+        let display = ingredient_text(recipe, item, quantities);
+        let mut quantities: Vec<(Option<f64>, Option<&str>)> = item
+            .quantities
+            .quantities
+            .iter()
+            .map(|quantity| (amount(&quantity.amount), quantity.unit.as_deref()))
+            .collect();
+        if quantities.is_empty() {
+            quantities.push((None, None));
+        }
+        quantities.iter().for_each(|(quantity, unit)| {
+            ingredients.push(json!({
+                "quantity": quantity,
+                "unit": unit.map(|unit| json!({"name": unit})),
+                "food": {"name": item.name},
+                "note": note,
+                "display": display,
+            }));
+        });
+    });
+    let mut steps = vec![];
+    recipe.sections().iter().for_each(|section| {
+        let mut title = section.name.clone().unwrap_or_default();
+        recipe.section_steps(section).iter().for_each(|step| {
+            steps.push(json!({
+                "title": std::mem::take(&mut title),
+                "text": step_text_with(recipe, step, quantities),
+            }));
+        });
+    });
+    let names = |names: Vec<&str>| -> Vec<Value> {
+        names.iter().map(|name| json!({"name": name})).collect()
+    };
+    json!({
+        "name": metadata.title(),
+        "description": metadata.get("description"),
+        "recipeYield": servings(recipe),
+        "totalTime": metadata.time().map(format_duration),
+        "prepTime": metadata.prep_time().map(format_duration),
+        "performTime": metadata.cook_time().map(format_duration),
+        "tags": names(metadata.tags()),
+        "recipeCategory": names(metadata.course().into_iter().collect()),
+        "orgURL": metadata.get("source"),
+        "recipeIngredient": ingredients,
+        "recipeInstructions": steps,
+    })
+}
+
+/// Convert a recipe of the Tandoor API.
+pub fn from_tandoor(json: &str) -> Result<Recipe, Box<dyn Error>> {
+    let json: Value = serde_json::from_str(json)?;
+    let mut yields = vec![];
+    if let Some(servings) = json["servings"].as_u64() {
+        yields.push(match json["servings_text"].as_str().map(str::trim) {
+            Some(text) if !text.is_empty() => format!("{} {}", servings, text),
+            _ => servings.to_string(),
+        });
+    }
+    let mut draft = Draft {
+        yields,
+        ..Draft::default()
+    };
+    if let Some(steps) = json["steps"].as_array() {
+        steps.iter().for_each(|step| {
+            let name = step["name"].as_str().map(str::trim);
+            let name = name.filter(|name| !name.is_empty()).map(str::to_string);
+            let mut texts = instructions(Some(&step["instruction"])).into_iter();
+            if let Some((_, text)) = texts.next() {
+                draft.steps.push((name, text));
+            }
+            draft.steps.extend(texts);
+            let ingredients = step["ingredients"].as_array().into_iter().flatten();
+            ingredients
+                .filter(|ingredient| ingredient["is_header"] != true)
+                .filter_map(|ingredient| {
+                    mention(
+                        ingredient["food"]["name"].as_str()?,
+                        ingredient["amount"].as_f64(),
+                        ingredient["unit"]["name"].as_str(),
+                        ingredient["note"].as_str(),
+                    )
+                })
+                .for_each(|ingredient| draft.ingredients.push(ingredient));
+        });
+    }
+    draft.insert("title", strings(Some(&json["name"])));
+    draft.insert("description", strings(Some(&json["description"])));
+    draft.insert("source", strings(Some(&json["source_url"])));
+    draft.insert("tags", strings(Some(&json["keywords"])));
+    let minutes = |value: &Value| value.as_u64().filter(|m| *m > 0).map(|m| m * 60);
+    draft.insert_time(
+        "prep time",
+        minutes(&json["working_time"]).map(Duration::from_secs),
+    );
+    draft.insert_time(
+        "cook time",
+        minutes(&json["waiting_time"]).map(Duration::from_secs),
+    );
+    draft.into_recipe()
+}
+
+/// Convert a recipe into the format of the Tandoor API. Every step lists the ingredients
+/// mentioned in it, hidden ingredients are listed in the first step.
+pub fn to_tandoor(recipe: &Recipe) -> Value {
+    let metadata = &recipe.metadata;
+    let specifier = |specifier: &IngredientSpecifier| {
+        let unit = specifier.unit.as_deref().or_else(|| {
+            metadata
+                .ingredients
+                .get(specifier.ingredient_key())
+                .and_then(|ingredient| ingredient.unit.as_deref())
+        });
+        json!({
+            "food": {"name": specifier.ingredient},
+            "unit": unit.map(|unit| json!({"name": unit})),
+            "amount": amount(&specifier.amount_in_step).unwrap_or(0.0),
+            "note": specifier.note.clone().unwrap_or_default(),
+        })
+    };
+    let mut hidden: Vec<Value> = metadata
+        .ingredients_specifiers
+        .iter()
+        .filter(|ingredient| ingredient.hidden)
+        .map(specifier)
+        .collect();
+    let mut steps = vec![];
+    recipe.sections().iter().for_each(|section| {
+        let mut name = section.name.clone().unwrap_or_default();
+        recipe.section_steps(section).iter().for_each(|step| {
+            let mut ingredients = std::mem::take(&mut hidden);
+            step.ingredients().for_each(|index| {
+                ingredients.push(specifier(&metadata.ingredients_specifiers[index]));
+            });
+            steps.push(json!({
+                "name": std::mem::take(&mut name),
+                "instruction": step_text(recipe, step),
+                "ingredients": ingredients,
+            }));
+        });
+    });
+    let minutes = |time: Option<Duration>| time.map_or(0, |time| time.as_secs() / 60);
+    json!({
+        "name": metadata.title(),
+        "description": metadata.get("description"),
+        "servings": metadata.servings.as_ref().and_then(|servings| servings.first()),
+        "working_time": minutes(metadata.prep_time()),
+        "waiting_time": minutes(metadata.cook_time()),
+        "keywords": metadata
+            .tags()
+            .iter()
+            .map(|tag| json!({"name": tag}))
+            .collect::<Vec<_>>(),
+        "source_url": metadata.get("source"),
+        "steps": steps,
+    })
+}
+
+/// Lines of a text, like the ingredients of Paprika.
+fn lines(value: &Value) -> Vec<String> {
+    let text = value.as_str().unwrap_or_default();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A duration given as ISO 8601, as text like `1 hour 30 minutes`, or as minutes.
+fn duration(value: &Value) -> Option<Duration> {
+    match value {
+        Value::String(text) => parse_iso_duration(text).or_else(|| parse_duration(text)),
+        Value::Number(minutes) => Duration::try_from_secs_f64(minutes.as_f64()? * 60.0).ok(),
+        _ => None,
+    }
+    .filter(|duration| !duration.is_zero())
+}
+
+/// The servings of the recipe, like `2|4` or `2 people|4 people`.
+fn servings(recipe: &Recipe) -> String {
+    let metadata = &recipe.metadata;
+    if !metadata.servings_labels.is_empty() {
+        return metadata.servings_labels.join("|");
+    }
+    let servings = metadata.servings.iter().flatten();
+    let servings: Vec<String> = servings.map(|serving| serving.to_string()).collect();
+    servings.join("|")
+}
+
+/// A single number for the amount. Of amounts for each serving, the first one is used.
+fn amount(amount: &Amount) -> Option<f64> {
+    match amount {
+        Amount::Single(amount) | Amount::Multi(amount) => Some(*amount),
+        Amount::PerServing(amounts) => amounts.first().copied(),
+    }
+    .filter(|amount| *amount > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interop::{
+        from_mealie, from_paprika, from_tandoor, to_mealie, to_paprika, to_tandoor,
+    };
+    use crate::parse;
+    use serde_json::json;
+
+    #[test]
+    fn paprika() {
+        let json = json!({
+            "name": "Tomato Soup",
+            "ingredients": "2 cans tomatoes\n1 onion, diced\n\nsalt",
+            "directions": "Fry the onion.\nAdd the tomatoes and simmer.",
+            "servings": "4 people",
+            "prep_time": "10 mins",
+            "cook_time": "1 hour",
+            "total_time": "",
+            "categories": ["Soup", "Vegan"],
+            "source": "Example",
+            "source_url": "https://example.com/soup",
+        });
+        let recipe = from_paprika(&json.to_string()).unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.title(), Some("Tomato Soup"));
+        assert_eq!(metadata.servings, Some(vec![4]));
+        assert_eq!(metadata.tags(), vec!["Soup", "Vegan"]);
+        assert_eq!(metadata.get("source"), Some("https://example.com/soup"));
+        assert_eq!(metadata.time().unwrap().as_secs(), 70 * 60);
+        assert_eq!(
+            metadata.ingredients["tomatoes"].unit.as_deref(),
+            Some("cans")
+        );
+        assert_eq!(metadata.ingredients["onion"].notes, vec!["diced"]);
+        assert_eq!(recipe.steps.len(), 2);
+
+        let exported = to_paprika(&recipe);
+        assert_eq!(
+            exported["ingredients"],
+            "1 onion (diced)\nsalt\n2 cans tomatoes"
+        );
+        assert_eq!(
+            exported["directions"],
+            "Fry the onion.\n\nAdd the tomatoes and simmer."
+        );
+        assert_eq!(exported["prep_time"], "10 min");
+        assert_eq!(exported["servings"], "4 people");
+
+        for total_time in [json!(-5), json!(1e300)] {
+            let json = json!({"name": "Soup", "total_time": total_time});
+            let recipe = from_paprika(&json.to_string()).unwrap();
+            assert_eq!(recipe.metadata.time(), None);
+        }
+    }
+
+    #[test]
+    fn mealie() {
+        let json = json!({
+            "name": "Pesto",
+            "recipeYield": "2 servings",
+            "totalTime": "PT15M",
+            "tags": [{"name": "Italian", "slug": "italian"}],
+            "recipeIngredient": [
+                {"quantity": 50.0, "unit": {"name": "g"}, "food": {"name": "basil"}, "note": ""},
+                {"quantity": 0.0, "unit": null, "food": null, "note": "1 clove garlic"},
+            ],
+            "recipeInstructions": [
+                {"title": "Pesto", "text": "Blend the basil and garlic."},
+                {"title": "", "text": "Serve."},
+            ],
+        });
+        let recipe = from_mealie(&json.to_string()).unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.servings, Some(vec![2]));
+        assert_eq!(metadata.time().unwrap().as_secs(), 15 * 60);
+        assert_eq!(
+            metadata.ingredients["garlic"].unit.as_deref(),
+            Some("clove")
+        );
+        assert_eq!(recipe.sections[0].name.as_deref(), Some("Pesto"));
+
+        let exported = to_mealie(&recipe);
+        assert_eq!(exported["tags"], json!([{"name": "Italian"}]));
+        assert_eq!(exported["recipeIngredient"][0]["quantity"], 50.0);
+        assert_eq!(exported["recipeIngredient"][0]["unit"]["name"], "g");
+        assert_eq!(exported["recipeInstructions"][0]["title"], "Pesto");
+        assert_eq!(exported["recipeInstructions"][1]["text"], "Serve.");
+    }
+
+    #[test]
+    fn tandoor() {
+        let json = json!({
+            "name": "Rice",
+            "servings": 2,
+            "servings_text": "bowls",
+            "working_time": 5,
+            "waiting_time": 20,
+            "keywords": [{"name": "side", "label": "side"}],
+            "steps": [
+                {
+                    "name": "",
+                    "instruction": "Rinse the rice.",
+                    "ingredients": [
+                        {"food": {"name": "rice"}, "unit": {"name": "g"}, "amount": 200, "note": ""},
+                        {"food": {"name": "water"}, "unit": {"name": "ml"}, "amount": 400},
+                    ],
+                },
+                {"name": "Cooking", "instruction": "Simmer for 20 minutes.", "ingredients": []},
+            ],
+        });
+        let recipe = from_tandoor(&json.to_string()).unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.servings_labels, vec!["2 bowls"]);
+        assert_eq!(metadata.time().unwrap().as_secs(), 25 * 60);
+        assert!(metadata.ingredients_specifiers[1].hidden);
+
+        let recipe = parse(">> servings: 2\nCook @rice{200%g} in @water{400%ml}.\n").unwrap();
+        let exported = to_tandoor(&recipe);
+        assert_eq!(exported["servings"], 2);
+        let ingredients = &exported["steps"][0]["ingredients"];
+        assert_eq!(ingredients[1]["food"]["name"], "water");
+        assert_eq!(ingredients[1]["amount"], 400.0);
+        assert_eq!(exported["steps"][0]["instruction"], "Cook rice in water.");
+    }
+}
@@ -0,0 +1,610 @@
+//! [schema.org/Recipe](https://schema.org/Recipe) structured data in JSON-LD.
+//!
+//! Requires the `json-ld` feature.
+
+use crate::render::{ingredient_text, step_text_with};
+use crate::time::{iso_duration, parse_iso_duration, parse_number};
+use crate::units::{PhysicalQuantity, UnitRegistry};
+use crate::writer::number_to_cooklang;
+use crate::{parse_with, servings_number, ParseOptions, QuantityFormatter, Recipe, ShoppingList};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Units which are recognized in ingredients besides the units of the default [UnitRegistry].
+const COUNT_UNITS: &[&str] = &[
+    "bunch", "can", "clove", "cup", "handful", "pinch", "piece", "slice", "sprig", "stick",
+];
+
+impl Recipe {
+    /// The recipe as schema.org structured data, to be embedded into a web page in a
+    /// `<script type="application/ld+json">` element.
+    ///
+    /// Steps of named sections are grouped into a `HowToSection`. Times, yield and keywords are
+    /// taken from the well-known [Metadata](crate::Metadata) keys.
+    pub fn to_json_ld(&self) -> Value {
+        self.to_json_ld_with(&QuantityFormatter::default())
+    }
+
+    /// The recipe as schema.org structured data like [Recipe::to_json_ld], with the amounts of
+    /// the ingredients and steps written by the formatter.
+    pub fn to_json_ld_with(&self, quantities: &QuantityFormatter) -> Value {
+        let metadata = &self.metadata;
+        let mut json = Map::new();
+        json.insert("@context".to_string(), json!("https://schema.org"));
+        json.insert("@type".to_string(), json!("Recipe"));
+        if let Some(title) = metadata.title() {
+            json.insert("name".to_string(), json!(title));
+        }
+        if let Some(description) = metadata.get("description") {
+            json.insert("description".to_string(), json!(description));
+        }
+        if let Some(author) = metadata.get("author") {
+            json.insert(
+                "author".to_string(),
+                json!({"@type": "Person", "name": author}),
+            );
+        }
+        let times = [
+            ("totalTime", metadata.time()),
+            ("prepTime", metadata.prep_time()),
+            ("cookTime", metadata.cook_time()),
+        ];
+        times.iter().for_each(|(key, time)| {
+            if let Some(time) = time {
+                json.insert(key.to_string(), json!(iso_duration(*time)));
+            }
+        });
+        let servings: Vec<String> = if !metadata.servings_labels.is_empty() {
+            metadata.servings_labels.clone()
+        } else {
+            let servings = metadata.servings.iter().flatten();
+            servings.map(|serving| serving.to_string()).collect()
+        };
+        if !servings.is_empty() {
+            json.insert("recipeYield".to_string(), json!(servings));
+        }
+        let tags = metadata.tags();
+        if !tags.is_empty() {
+            json.insert("keywords".to_string(), json!(tags.join(", ")));
+        }
+        if let Some(course) = metadata.course() {
+            json.insert("recipeCategory".to_string(), json!(course));
+        }
+        if let Some(cuisine) = metadata.cuisine() {
+            json.insert("recipeCuisine".to_string(), json!(cuisine));
+        }
+
+        let list = ShoppingList::from_recipes(vec![self]);
+        let ingredients: Vec<String> = list
+            .items
+            .values()
+            .map(|item| ingredient_text(self, item, quantities))
+            .collect();
+        json.insert("recipeIngredient".to_string(), json!(ingredients));
+        let mut tools: Vec<&String> = vec![];
+        metadata.cookware.iter().for_each(|name| {
+            if !tools.contains(&name) {
+                tools.push(name);
+            }
+        });
+        if !tools.is_empty() {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|name| json!({"@type": "HowToTool", "name": name}))
+                .collect();
+            json.insert("tool".to_string(), json!(tools));
+        }
+
+        let mut instructions = vec![];
+        self.sections().iter().for_each(|section| {
+            let steps: Vec<Value> = self
+                .section_steps(section)
+                .iter()
+                .map(|step| {
+                    let text = step_text_with(self, step, quantities);
+                    json!({"@type": "HowToStep", "text": text})
+                })
+                .collect();
+            match &section.name {
+                Some(name) => instructions.push(json!({
+                    "@type": "HowToSection",
+                    "name": name,
+                    "itemListElement": steps,
+                })),
+                None => instructions.extend(steps),
+            }
+        });
+        json.insert("recipeInstructions".to_string(), json!(instructions));
+        Value::Object(json)
+    }
+
+    /// Convert schema.org structured data, e.g. scraped from a recipe website, into a recipe.
+    ///
+    /// The recipe can be nested in a list or an `@graph`. Amounts and units are extracted from
+    /// the ingredients on a best-effort basis, like `1 1/2 cups flour, sifted`. Every ingredient
+    /// is mentioned in the first step whose text contains its name, and as a hidden ingredient in
+    /// the first step otherwise. The [source](Recipe::source) is regenerated CookLang.
+    pub fn from_json_ld(json: &str) -> Result<Recipe, Box<dyn std::error::Error>> {
+        let value: Value = serde_json::from_str(json)?;
+        let json = find_recipe(&value).ok_or("No schema.org Recipe found")?;
+
+        let registry = UnitRegistry::default();
+        let mut draft = Draft {
+            ingredients: strings(json.get("recipeIngredient"))
+                .iter()
+                .filter_map(|ingredient| ingredient_mention(ingredient, &registry))
+                .collect(),
+            steps: instructions(json.get("recipeInstructions")),
+            yields: strings(json.get("recipeYield")),
+            entries: vec![],
+        };
+        let properties = [
+            ("title", "name"),
+            ("description", "description"),
+            ("author", "author"),
+            ("source", "url"),
+            ("tags", "keywords"),
+            ("course", "recipeCategory"),
+            ("cuisine", "recipeCuisine"),
+        ];
+        properties.iter().for_each(|(key, property)| {
+            draft.insert(key, strings(json.get(*property)));
+        });
+        let times = [
+            ("time", "totalTime"),
+            ("prep time", "prepTime"),
+            ("cook time", "cookTime"),
+        ];
+        times.iter().for_each(|(key, property)| {
+            let time = strings(json.get(*property));
+            let time = time.first().and_then(|time| parse_iso_duration(time));
+            draft.insert_time(key, time);
+        });
+        draft.into_recipe()
+    }
+}
+
+/// A recipe converted from another format, before it is turned into CookLang.
+#[derive(Debug, Default)]
+pub(crate) struct Draft {
+    /// Names of the ingredients and their mentions, see [mention].
+    pub ingredients: Vec<(String, String)>,
+    /// Text of the steps, with the name of the section they start.
+    pub steps: Vec<(Option<String>, String)>,
+    /// Labels of the servings, like `4 people`.
+    pub yields: Vec<String>,
+    /// Metadata entries.
+    pub entries: Vec<(String, String)>,
+}
+
+impl Draft {
+    /// Add a metadata entry, if there are values.
+    pub fn insert(&mut self, key: &str, values: Vec<String>) {
+        if !values.is_empty() {
+            self.entries.push((key.to_string(), values.join(", ")));
+        }
+    }
+
+    /// Add a metadata entry for the duration, like `prep time: 10 min`.
+    pub fn insert_time(&mut self, key: &str, time: Option<Duration>) {
+        self.insert(key, time.map(format_duration).into_iter().collect());
+    }
+
+    /// Mention every ingredient in the first step whose text contains its name, and as a hidden
+    /// ingredient in the first step otherwise, and parse the result.
+    pub fn into_recipe(self) -> Result<Recipe, Box<dyn std::error::Error>> {
+        let Draft {
+            mut ingredients,
+            mut steps,
+            yields,
+            entries,
+        } = self;
+        steps.iter_mut().for_each(|(_, step)| {
+            let text = escape(step);
+            let mut mentions: Vec<(usize, usize, String)> = vec![];
+            ingredients.retain(|(name, mention)| {
+                let found = find_word(&text, name).filter(|&start| {
+                    let end = start + name.len();
+                    mentions.iter().all(|(s, e, _)| end <= *s || start >= *e)
+                });
+                match found {
+                    Some(start) => mentions.push((start, start + name.len(), mention.clone())),
+                    None => return true,
+                }
+                false
+            });
+            mentions.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+            *step = text;
+            mentions.iter().for_each(|(start, end, mention)| {
+                step.replace_range(start..end, mention);
+            });
+        });
+        let hidden: Vec<String> = ingredients
+            .iter()
+            .map(|(_, mention)| format!("@-{}", &mention[1..]))
+            .collect();
+        match steps.first_mut() {
+            Some((_, step)) => hidden
+                .iter()
+                .for_each(|mention| *step += &format!(" {}", mention)),
+            None => steps.push((None, hidden.join(" "))),
+        }
+        let mut source = String::new();
+        steps.iter().for_each(|(section, step)| {
+            if let Some(section) = section {
+                source += &format!("== {} ==\n\n", section);
+            }
+            source += step;
+            source += "\n\n";
+        });
+
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::lenient()
+        };
+        let mut recipe = parse_with(&source, &options)?.recipe;
+        let metadata = &mut recipe.metadata;
+        let mut yields: Vec<String> = yields
+            .iter()
+            .flat_map(|serving| serving.split('|'))
+            .map(|serving| serving.trim().to_string())
+            .filter(|serving| !serving.is_empty())
+            .collect();
+        // Descriptions of the same yield, like `4` and `4 pancakes`, are one serving.
+        let mut numbers = vec![];
+        yields.retain(|serving| match servings_number(serving) {
+            Some(number) if numbers.contains(&number) => false,
+            number => {
+                numbers.extend(number);
+                true
+            }
+        });
+        let servings: Option<Vec<usize>> = yields
+            .iter()
+            .map(|serving| servings_number(serving))
+            .collect();
+        match servings {
+            Some(servings) if !servings.is_empty() => {
+                metadata.servings = Some(servings);
+                metadata.servings_labels = yields;
+            }
+            _ if !yields.is_empty() => metadata.entries.insert("yield", yields.join(", ")),
+            _ => {}
+        }
+        metadata.entries.extend(entries);
+        recipe.source = recipe.to_cooklang();
+        Ok(recipe)
+    }
+}
+
+/// The first object with the type `Recipe`.
+fn find_recipe(value: &Value) -> Option<&Map<String, Value>> {
+    match value {
+        Value::Array(values) => values.iter().find_map(find_recipe),
+        Value::Object(object) => {
+            let is_recipe = match object.get("@type") {
+                Some(Value::String(kind)) => kind == "Recipe",
+                Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "Recipe"),
+                _ => false,
+            };
+            if is_recipe {
+                Some(object)
+            } else {
+                object.get("@graph").and_then(find_recipe)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Text of a property, which can be a string, a number, a list or an object with a `name`.
+pub(crate) fn strings(value: Option<&Value>) -> Vec<String> {
+    let mut result = vec![];
+    match value {
+        Some(Value::String(text)) => {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                result.push(text);
+            }
+        }
+        Some(Value::Number(number)) => result.push(number.to_string()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .for_each(|value| result.extend(strings(Some(value)))),
+        Some(Value::Object(object)) => result.extend(strings(object.get("name"))),
+        _ => {}
+    }
+    result
+}
+
+/// Steps of the instructions with the name of their section.
+pub(crate) fn instructions(value: Option<&Value>) -> Vec<(Option<String>, String)> {
+    let mut steps = vec![];
+    let text = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match value {
+        Some(Value::String(instructions)) => instructions
+            .lines()
+            .map(text)
+            .filter(|step| !step.is_empty())
+            .for_each(|step| steps.push((None, step))),
+        Some(Value::Array(values)) => values
+            .iter()
+            .for_each(|value| steps.extend(instructions(Some(value)))),
+        Some(Value::Object(object)) => match object.get("itemListElement") {
+            Some(elements) => {
+                let name = object.get("name").and_then(Value::as_str).map(text);
+                let mut section = instructions(Some(elements));
+                if let Some((first, _)) = section.first_mut() {
+                    *first = first.take().or(name);
+                }
+                steps.extend(section);
+            }
+            None => {
+                let step = object.get("text").or_else(|| object.get("name"));
+                steps.extend(instructions(step));
+            }
+        },
+        _ => {}
+    }
+    steps
+}
+
+/// Name of the ingredient and its mention, parsed from text like `1 1/2 cups flour, sifted`.
+pub(crate) fn ingredient_mention(
+    ingredient: &str,
+    registry: &UnitRegistry,
+) -> Option<(String, String)> {
+    let (ingredient, note) = match ingredient.split_once(',') {
+        Some((ingredient, note)) => (ingredient, Some(note.trim())),
+        None => (ingredient, None),
+    };
+    let mut words = ingredient.split_whitespace().peekable();
+    let mut amount = None;
+    while let Some(number) = words.peek().and_then(|word| parse_amount(word)) {
+        amount = Some(amount.unwrap_or(0.0) + number);
+        words.next();
+    }
+    let unit = match (amount, words.peek()) {
+        (Some(_), Some(word)) if is_unit(word, registry) => words.next(),
+        _ => None,
+    };
+    if words.peek() == Some(&"of") {
+        words.next();
+    }
+    let name: String = words.collect::<Vec<_>>().join(" ");
+    let (name, note) = match name.split_once('(') {
+        Some((name, inner)) => {
+            let inner = inner.trim_end_matches(')').trim();
+            let note = note.map_or(inner.to_string(), |note| format!("{}, {}", inner, note));
+            (name.to_string(), Some(note))
+        }
+        None => (name, note.map(str::to_string)),
+    };
+    mention(&name, amount, unit, note.as_deref())
+}
+
+/// Name of the ingredient and its CookLang mention, like `flour` and `@flour{3/2%cups}(sifted)`.
+/// Characters which can't be part of a name or note are left out.
+pub(crate) fn mention(
+    name: &str,
+    amount: Option<f64>,
+    unit: Option<&str>,
+    note: Option<&str>,
+) -> Option<(String, String)> {
+    let name = text(name);
+    if name.is_empty() {
+        return None;
+    }
+    let mut mention = format!("@{}{{", name);
+    if let Some(amount) = amount.filter(|amount| *amount > 0.0) {
+        mention += &number_to_cooklang(amount);
+        let unit = unit.map(|unit| unit.trim().trim_end_matches('.'));
+        if let Some(unit) = unit.filter(|unit| is_cooklang_unit(unit)) {
+            mention += &format!("%{}", unit);
+        }
+    }
+    mention += "}";
+    if let Some(note) = note.map(text).filter(|note| !note.is_empty()) {
+        mention += &format!("({})", note);
+    }
+    Some((name, mention))
+}
+
+/// A number like `2`, `1/2` or `½`.
+fn parse_amount(word: &str) -> Option<f64> {
+    let fraction = match word {
+        "¼" => 0.25,
+        "½" => 0.5,
+        "¾" => 0.75,
+        "⅓" => 1.0 / 3.0,
+        "⅔" => 2.0 / 3.0,
+        word => return parse_number(word).filter(|number| number.is_finite()),
+    };
+    Some(fraction)
+}
+
+/// Whether the word is a unit which can be written in CookLang.
+fn is_unit(word: &str, registry: &UnitRegistry) -> bool {
+    let unit = word.trim_end_matches('.');
+    let known = registry
+        .get(unit)
+        .is_some_and(|unit| unit.quantity != PhysicalQuantity::Temperature)
+        || COUNT_UNITS.iter().any(|count| {
+            let unit = unit.to_lowercase();
+            unit == *count || unit.strip_suffix('s') == Some(count)
+        });
+    known && is_cooklang_unit(unit)
+}
+
+/// Whether the unit can be written in an amount, which only allows letters and digits.
+fn is_cooklang_unit(unit: &str) -> bool {
+    !unit.is_empty() && unit.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Words of the text, without the characters a name or note can't contain.
+fn text(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => c,
+            _ => ' ',
+        })
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A duration like `90 min`, which [Metadata::time](crate::Metadata::time) can read.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    match duration.as_secs() {
+        seconds if seconds % 60 == 0 => format!("{} min", seconds / 60),
+        seconds => format!("{} s", seconds),
+    }
+}
+
+/// Escape the characters which would start a mention.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    text.chars().for_each(|c| {
+        if c == '@' || c == '#' || c == '~' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    });
+    escaped
+}
+
+/// Start of the first occurrence of the name as whole words, compared case-insensitively.
+fn find_word(text: &str, name: &str) -> Option<usize> {
+    let lowercase = text.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '\\');
+    lowercase
+        .match_indices(&name)
+        .map(|(start, _)| start)
+        .find(|&start| {
+            !is_word(lowercase[..start].chars().last())
+                && !is_word(lowercase[start + name.len()..].chars().next())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Item, QuantityFormatter, Recipe};
+    use serde_json::json;
+
+    #[test]
+    fn to_json_ld() {
+        let recipe = parse(
+            "\
+---
+title: Pancakes
+tags: [breakfast, quick]
+prep time: 10 min
+cook time: 1/2 hour
+---
+>> servings: 2|4
+Mix @flour{125%g} and @milk{1/4%l} in a #bowl.
+
+== Frying ==
+Fry for ~{2%minutes}.
+",
+        )
+        .unwrap();
+        assert_eq!(
+            recipe.to_json_ld(),
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Recipe",
+                "name": "Pancakes",
+                "totalTime": "PT40M",
+                "prepTime": "PT10M",
+                "cookTime": "PT30M",
+                "recipeYield": ["2", "4"],
+                "keywords": "breakfast, quick",
+                "recipeIngredient": ["125 g flour", "0.25 l milk"],
+                "tool": [{"@type": "HowToTool", "name": "bowl"}],
+                "recipeInstructions": [
+                    {"@type": "HowToStep", "text": "Mix flour and milk in a bowl."},
+                    {
+                        "@type": "HowToSection",
+                        "name": "Frying",
+                        "itemListElement": [{"@type": "HowToStep", "text": "Fry for 2 minutes."}],
+                    },
+                ],
+            })
+        );
+        let quantities = QuantityFormatter {
+            fractions: true,
+            ..QuantityFormatter::default()
+        };
+        assert_eq!(
+            recipe.to_json_ld_with(&quantities)["recipeIngredient"],
+            json!(["125 g flour", "¼ l milk"])
+        );
+    }
+
+    #[test]
+    fn from_json_ld() {
+        let json = json!({
+            "@context": "https://schema.org",
+            "@graph": [
+                {"@type": "WebPage", "name": "Pancakes | Example"},
+                {
+                    "@type": ["Recipe"],
+                    "name": "Fluffy Pancakes",
+                    "author": {"@type": "Person", "name": "Jane Doe"},
+                    "recipeYield": ["4", "4 pancakes"],
+                    "prepTime": "PT10M",
+                    "cookTime": "PT1H",
+                    "keywords": "breakfast, sweet",
+                    "recipeIngredient": [
+                        "1 1/2 cups all-purpose flour, sifted",
+                        "½ tsp. salt",
+                        "2 large eggs (beaten)",
+                        "butter",
+                    ],
+                    "recipeInstructions": [
+                        {"@type": "HowToStep", "text": "Mix the all-purpose flour with the eggs."},
+                        {
+                            "@type": "HowToSection",
+                            "name": "Frying",
+                            "itemListElement": [
+                                {"@type": "HowToStep", "text": "Melt the Butter @ medium heat."},
+                            ],
+                        },
+                    ],
+                },
+            ],
+        });
+        let recipe = Recipe::from_json_ld(&json.to_string()).unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.title(), Some("Fluffy Pancakes"));
+        assert_eq!(metadata.get("author"), Some("Jane Doe"));
+        assert_eq!(metadata.servings, Some(vec![4]));
+        assert_eq!(metadata.servings_labels, vec!["4"]);
+        assert_eq!(metadata.time().unwrap().as_secs(), 70 * 60);
+        assert_eq!(metadata.tags(), vec!["breakfast", "sweet"]);
+        let names: Vec<&String> = metadata.ingredients.keys().collect();
+        assert_eq!(
+            names,
+            vec!["all-purpose flour", "salt", "large eggs", "butter"]
+        );
+        let flour = &metadata.ingredients["all-purpose flour"];
+        assert_eq!(flour.unit.as_deref(), Some("cups"));
+        assert_eq!(flour.notes, vec!["sifted"]);
+        assert_eq!(metadata.ingredients["large eggs"].notes, vec!["beaten"]);
+        assert!(metadata.ingredients_specifiers[1].hidden);
+        assert_eq!(recipe.sections[1].name.as_deref(), Some("Frying"));
+        let text = match &recipe.steps[1].items[2] {
+            Item::Text(text) => text.clone(),
+            item => panic!("unexpected item {:?}", item),
+        };
+        assert_eq!(text, " @ medium heat.");
+
+        let json = json!({"@type": "Recipe", "totalTime": "PT99999999999999999999999H"});
+        let recipe = Recipe::from_json_ld(&json.to_string()).unwrap();
+        assert_eq!(recipe.metadata.time(), None);
+        assert!(Recipe::from_json_ld("{\"@type\": \"Person\"}").is_err());
+        assert!(Recipe::from_json_ld("not json").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Layout of the source of a parsed [Recipe], so [Recipe::to_cooklang] can write it back as it
+//! was written.
+//!
+//! The [Layout] keeps the source around the mentions of ingredients, cookware and timers as it is,
+//! including blank lines, comments, the front matter and the metadata lines, and every mention as
+//! written, like `@flour{1.50%kg}`. A mention is written as before as long as it means the same.
+//! Once the [instruction](Recipe::instruction) or the metadata has changed, the layout doesn't fit
+//! anymore and the recipe is written anew.
+
+use crate::diagnostics::Span;
+use crate::diff::metadata;
+use crate::hash::Fnv;
+use crate::writer::{cookware_to_cooklang, ingredient_to_cooklang, timer_to_cooklang};
+use crate::Recipe;
+
+/// Kind of a mention in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum MentionKind {
+    Ingredient,
+    Cookware,
+    Timer,
+}
+
+/// Layout of the source of a [Recipe]. See the [module](crate::layout).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The source before the first mention, between the mentions and after the last one.
+    text: Vec<String>,
+    /// The mentions in order.
+    mentions: Vec<WrittenMention>,
+    /// Hash of the instruction and the metadata when parsed.
+    fingerprint: u64,
+}
+
+/// A mention as written in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WrittenMention {
+    kind: MentionKind,
+    /// The mention as written.
+    source: String,
+    /// The mention as [Recipe::to_cooklang] writes it, to tell if it has been changed.
+    written: String,
+}
+
+impl Layout {
+    /// The layout of the parsed recipe with the spans of its mentions in order. [None] if the
+    /// mentions don't match the recipe.
+    pub(crate) fn new(recipe: &Recipe, mentions: &[(MentionKind, Span)]) -> Option<Layout> {
+        let source = &recipe.source;
+        let mut text = vec![];
+        let mut position = 0;
+        let mut written = vec![];
+        let mut mentioned = Mentioned::default();
+        for (kind, span) in mentions {
+            text.push(source.get(position..span.start)?.to_string());
+            position = span.end;
+            written.push(WrittenMention {
+                kind: *kind,
+                source: source.get(span.start..span.end)?.to_string(),
+                written: mentioned.next(recipe, *kind)?,
+            });
+        }
+        text.push(source.get(position..)?.to_string());
+        if !mentioned.all(recipe) {
+            return None;
+        }
+        Some(Layout {
+            text,
+            mentions: written,
+            fingerprint: fingerprint(recipe),
+        })
+    }
+
+    /// Whether the layout still fits the recipe.
+    pub(crate) fn fits(&self, recipe: &Recipe) -> bool {
+        let count = |kind| self.mentions.iter().filter(|m| m.kind == kind).count();
+        let metadata = &recipe.metadata;
+        self.fingerprint == fingerprint(recipe)
+            && count(MentionKind::Ingredient) == metadata.ingredients_specifiers.len()
+            && count(MentionKind::Cookware) == metadata.cookware.len()
+            && count(MentionKind::Timer) == metadata.timer.len()
+    }
+
+    /// The source with the changed mentions written anew. The layout has to [fit](Layout::fits).
+    pub(crate) fn write(&self, recipe: &Recipe) -> String {
+        let mut mentioned = Mentioned::default();
+        let mut result = String::with_capacity(recipe.source.len());
+        self.text.iter().enumerate().for_each(|(index, text)| {
+            result.push_str(text);
+            if let Some(mention) = self.mentions.get(index) {
+                let written = mentioned.next(recipe, mention.kind).unwrap_or_default();
+                match written == mention.written {
+                    true => result.push_str(&mention.source),
+                    false => result.push_str(&written),
+                }
+            }
+        });
+        result
+    }
+}
+
+/// Number of the ingredients, cookware and timers written so far.
+#[derive(Default)]
+struct Mentioned {
+    ingredients: usize,
+    cookware: usize,
+    timers: usize,
+}
+
+impl Mentioned {
+    /// The next mention of the kind, as written by [Recipe::to_cooklang].
+    fn next(&mut self, recipe: &Recipe, kind: MentionKind) -> Option<String> {
+        let metadata = &recipe.metadata;
+        let written = match kind {
+            MentionKind::Ingredient => {
+                let specifier = metadata.ingredients_specifiers.get(self.ingredients)?;
+                self.ingredients += 1;
+                ingredient_to_cooklang(recipe, specifier)
+            }
+            MentionKind::Cookware => {
+                let cookware = metadata.cookware.get(self.cookware)?;
+                self.cookware += 1;
+                cookware_to_cooklang(cookware)
+            }
+            MentionKind::Timer => {
+                let timer = metadata.timer.get(self.timers)?;
+                self.timers += 1;
+                timer_to_cooklang(timer)
+            }
+        };
+        Some(written)
+    }
+
+    /// Whether all mentions of the recipe have been written.
+    fn all(&self, recipe: &Recipe) -> bool {
+        let metadata = &recipe.metadata;
+        self.ingredients == metadata.ingredients_specifiers.len()
+            && self.cookware == metadata.cookware.len()
+            && self.timers == metadata.timer.len()
+    }
+}
+
+/// Hash of the instruction and the metadata.
+fn fingerprint(recipe: &Recipe) -> u64 {
+    let mut hash = Fnv::default();
+    hash.field(&recipe.instruction);
+    metadata(recipe).iter().for_each(|(key, value)| {
+        hash.field(key);
+        hash.field(value);
+    });
+    hash.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Amount};
+
+    #[test]
+    fn layout() {
+        let source = "\
+---
+title: Pancakes
+---
+// Family recipe
+>>servings: 2 // or 3
+
+
+Mix @flour{1.50%kg}  and @eggs{2} [- fresh ones -] in a #mixing bowl{}.
+Rest for ~{1h30m}, then fry in a \\#1 #pan.\r
+
+> Serve warm";
+        let mut recipe = parse(source).unwrap();
+        assert_eq!(recipe.to_cooklang(), source);
+
+        recipe.metadata.ingredients_specifiers[1].amount_in_step = Amount::Single(3.0);
+        assert_eq!(recipe.to_cooklang(), source.replace("@eggs{2}", "@eggs{3}"));
+
+        recipe.instruction.push_str("\nEnjoy.");
+        assert!(recipe
+            .to_cooklang()
+            .starts_with(">> servings: 2\n>> title: Pancakes\n"));
+    }
+}
@@ -1,6 +1,9 @@
 //! This is a parser crate for the [CookLang](https://github.com/cooklang/spec).  The main feature is parsing a String into a
 //! struct that implements serde and can be easily used from there.
 //!
+//! The serde implementations and the ingredient ids can be turned off by disabling the default
+//! `serde` and `uuid` features, e.g. for a small WASM build.
+//!
 //! The implementation is nearly fully complete. Only image tags are missing. They are just ignored by now.
 //!
 
@@ -14,17 +17,102 @@ use pest::iterators::Pair;
 use pest::Parser;
 use std::boxed::Box;
 use std::collections::HashMap;
-use std::ops::Add;
+use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
+#[cfg(feature = "uuid")]
 use uuid::Uuid;
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
+use layout::{Layout, MentionKind};
+use metadata::MetadataMap;
+use source_map::SourceMap;
+use names::NameNormalizer;
+use units::{UnitError, UnitRegistry};
+
+pub use borrowed::parse_borrowed;
+pub use collection::{Collection, CollectionRecipe};
+pub use diagnostics::{Diagnostic, Severity, Span};
+pub use file::{parse_file, parse_path, FileError};
+pub use quantity_format::QuantityFormatter;
+pub use shopping::{Deduction, ShoppingItem, ShoppingList};
+pub use steps::{Item, Section, Step};
+
+pub mod aisle;
+pub mod bakers;
+pub mod borrowed;
+#[cfg(any(all(test, feature = "serde"), feature = "canonical"))]
+pub mod canonical;
+pub mod collection;
+pub mod completion;
+pub mod cookbook;
+pub mod csv;
+pub mod diagnostics;
+pub mod diet;
+pub mod diff;
+pub mod document;
+pub mod duration;
+pub mod equipment;
+pub mod file;
+pub mod format;
+mod front_matter;
+pub mod grocery;
+mod hash;
+mod ical;
+#[cfg(any(test, feature = "interop"))]
+pub mod interop;
+#[cfg(any(test, feature = "json-ld"))]
+mod json_ld;
+pub mod layout;
+pub mod lint;
+pub mod locale;
+pub mod merge;
+pub mod metadata;
+pub mod names;
+pub mod nutrition;
+#[cfg(any(
+    all(test, feature = "serde"),
+    feature = "json",
+    feature = "yaml",
+    feature = "toml"
+))]
+pub mod output;
+pub mod pantry;
+pub mod plan;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quantity_format;
+pub mod query;
+pub mod render;
+#[cfg(feature = "schemars")]
+mod schema;
+pub mod search;
+pub mod session;
+pub mod shopping;
+pub mod source_map;
+pub mod stats;
+pub mod steps;
+pub mod substitutions;
+pub mod suggest;
+mod time;
+pub mod tokens;
+pub mod units;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+mod writer;
 
 #[derive(Parser)]
 #[grammar = "../CookLang.pest"]
 struct CookParser;
 
 /// Includes the raw source, metadata and instructions.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Recipe {
     /// Raw source code of the recipe that this struct has been generated from.
     pub source: String,
@@ -40,17 +128,120 @@ pub struct Recipe {
     ///
     /// For every mentioning of a timer there is an ~ in replacement. The mentioning directly links
     /// to a [Timer].
+    ///
+    /// Section headers like `== Dough ==` and notes like `> Tastes best warm` are kept as a line
+    /// of their own.
+    ///
+    /// An @, # or ~ that is part of the text is escaped with a backslash. Use
+    /// [instruction_parts] to iterate over the instructions.
     pub instruction: String,
+    /// The instructions split into [Step]s. See [Recipe::steps].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub steps: Vec<Step>,
+    /// The [Section]s grouping the [Recipe::steps]. See [Recipe::sections].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sections: Vec<Section>,
+    /// The comments in the source, in order. They are not part of the [Recipe::instruction].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<Comment>,
+    /// Layout of the source, to write the recipe back as it was written with
+    /// [Recipe::to_cooklang]. [None] if the recipe hasn't been parsed, e.g. deserialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub layout: Option<Layout>,
+    /// Where the [instruction](Recipe::instruction) and the [steps](Recipe::steps) come from in
+    /// the source. [None] if the recipe hasn't been parsed, e.g. deserialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source_map: Option<SourceMap>,
+    /// Path of the file the recipe was read from, see [parse_path].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<PathBuf>,
+    /// Image of the whole recipe, found next to its file by [parse_path].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub image: Option<PathBuf>,
+    /// Images of single steps, by step number, found next to the file by [parse_path].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub step_images: Vec<(usize, PathBuf)>,
+}
+
+/// A comment of the [Recipe].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Comment {
+    /// The text without the markers, trimmed.
+    pub text: String,
+    /// The comment in the [source](Recipe::source), including the markers.
+    pub span: Span,
+    /// Kind of the comment.
+    pub kind: CommentKind,
+}
+
+/// Kind of a [Comment].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum CommentKind {
+    /// A comment up to the end of the line, like `// Tastes best warm`.
+    #[default]
+    Line,
+    /// A comment within a line, like `[- TODO -]`.
+    Block,
+}
+
+/// Part of the [Recipe::instruction].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstructionPart {
+    /// Mentioning of the next [IngredientSpecifier].
+    Ingredient,
+    /// Mentioning of the next cookware.
+    Cookware,
+    /// Mentioning of the next [Timer].
+    Timer,
+    /// A character of the text.
+    Text(char),
+}
+
+/// Iterate over the parts of an instruction, resolving escaped characters.
+pub fn instruction_parts(instruction: &str) -> impl Iterator<Item = InstructionPart> + '_ {
+    let mut chars = instruction.chars().peekable();
+    std::iter::from_fn(move || {
+        let part = match chars.next()? {
+            '@' => InstructionPart::Ingredient,
+            '#' => InstructionPart::Cookware,
+            '~' => InstructionPart::Timer,
+            '\\' => match chars.peek() {
+                Some(&escaped) if escaped == '@' || escaped == '#' || escaped == '~' => {
+                    chars.next();
+                    InstructionPart::Text(escaped)
+                }
+                _ => InstructionPart::Text('\\'),
+            },
+            char => InstructionPart::Text(char),
+        };
+        Some(part)
+    })
 }
 
 /// The metadata from the recipe is described in this metadata struct.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Metadata {
     /// Amount of servings. Is optional.
     pub servings: Option<Vec<usize>>,
-    /// Other optional metadata contained in a [HashMap].
-    pub ominous: HashMap<String, String>,
+    /// The servings as written, e.g. `2 people` or `makes 12 cookies`. Use these for display,
+    /// [Metadata::servings] holds the numbers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub servings_labels: Vec<String>,
+    /// Other optional metadata in the order of the source. Well-known keys like `title` can be
+    /// accessed with typed getters like [Metadata::title].
+    #[cfg_attr(feature = "serde", serde(alias = "ominous"))]
+    pub entries: MetadataMap,
     /// Exact description of an [Ingredient] indexed by name.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, Ingredient>")
+    )]
     pub ingredients: IndexMap<String, Ingredient>,
     /// Ingredient Specifier describing the mentioning of a [Ingredient]. The n-th mention of @
     /// in [Recipe::instruction] is the n-th [IngredientSpecifier] in this [Vec].
@@ -59,264 +250,951 @@ pub struct Metadata {
     pub cookware: Vec<String>,
     /// The n-th mention of ~ in [Recipe::instruction] is the n-th [Timer] in this [Vec].
     pub timer: Vec<Timer>,
+    /// Separator of the values of lists like [Metadata::tags], see [Separators::list].
+    #[cfg_attr(feature = "serde", serde(default = "default_list_separator"))]
+    list_separator: char,
+}
+
+#[cfg(feature = "serde")]
+fn default_list_separator() -> char {
+    Separators::default().list
 }
 
 impl Metadata {
     fn add_key_value(&mut self, key: String, value: String) {
-        self.ominous.insert(key, value);
+        self.entries.insert(key, value);
+    }
+
+    /// Other optional metadata contained in a [HashMap]. Of repeated keys the last value is used.
+    #[deprecated(note = "use Metadata::entries, which keeps the order and repeated keys")]
+    pub fn ominous(&self) -> HashMap<String, String> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
     }
 }
 /// A Timer.
 ///
 /// Describing the timer you have to set in this mentioning in the instructions.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Timer {
-    /// The number of [Timer::unit]s in this Timer mentioning.
+    /// The number of [Timer::unit]s in this Timer mentioning. The lower end of a range like
+    /// `~{1-2%hours}`.
+    ///
+    /// The parts of a compound duration like `~{1%hour 30%minutes}` or `~{1h30m}` are added up
+    /// in the smallest of their units, here as 90 minutes.
     pub amount: f64,
     /// The unit of this Timer contained in a [String].
     pub unit: String,
+    /// The upper end of a range like `~{1-2%hours}`, in the same unit.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max: Option<f64>,
+}
+
+impl Timer {
+    /// The duration of the timer. Time units are compared case-insensitively and can be written
+    /// in English, like `s`, `min`, `hours` or `days`, and in German, French or Spanish.
+    pub fn duration(&self) -> Result<Duration, UnitError> {
+        let seconds = time::unit_seconds(&self.unit)
+            .ok_or_else(|| UnitError::UnknownUnit(self.unit.clone()))?;
+        Duration::try_from_secs_f64(self.amount * seconds)
+            .map_err(|_| UnitError::InvalidValue(self.amount))
+    }
+
+    /// The duration of the upper end of a range like `~{1-2%hours}`, otherwise the same as
+    /// [Timer::duration].
+    pub fn max_duration(&self) -> Result<Duration, UnitError> {
+        let timer = Timer {
+            amount: self.max.unwrap_or(self.amount),
+            ..self.clone()
+        };
+        timer.duration()
+    }
 }
 
 /// IngredientSpecifier
 ///
 /// References to a [Ingredient] in [Metadata::ingredients] by [String].
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct IngredientSpecifier {
     /// Name of the ingredient this specifier references to. Have to be extracted from [Metadata::ingredients].
     pub ingredient: String,
     /// [Amount] to be used in this step.
     pub amount_in_step: Amount,
+    /// Preparation note of this mentioning, like `washed` in `@apples{2}(washed)`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub note: Option<String>,
+    /// Reuse of an ingredient mentioned before, like `@&pasta water{}`. The amount is not added
+    /// to the [Ingredient]. Requires [ParseOptions::extensions].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reference: bool,
+    /// This mentioning is marked as optional, like `@?parsley{}`. Requires
+    /// [ParseOptions::extensions].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optional: bool,
+    /// This mentioning is not part of the step text, like `@-salt{}`. The ingredient is still
+    /// listed. Requires [ParseOptions::extensions].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hidden: bool,
+    /// This mentioning references another recipe by its path, like
+    /// `@./sauces/Marinara{200%g}`. [IngredientSpecifier::ingredient] contains the path, which
+    /// is not part of [Metadata::ingredients]. Requires [ParseOptions::extensions].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recipe: bool,
+    /// Unit of [IngredientSpecifier::amount_in_step].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit: Option<String>,
+    /// Key of the ingredient in [Metadata::ingredients] if it isn't the name, like `flour (2)`
+    /// with [DuplicateMentions::Separate]. See [IngredientSpecifier::ingredient_key].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub key: Option<String>,
+}
+
+impl IngredientSpecifier {
+    /// Key of the [Ingredient] of this mentioning in [Metadata::ingredients].
+    pub fn ingredient_key(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.ingredient)
+    }
+
+    /// The amount and unit of this mentioning, like `2 tbsp` in `@butter{2%tbsp}`. [None] if
+    /// neither is written.
+    pub fn quantity(&self) -> Option<Quantity> {
+        if self.unit.is_none() && self.amount_in_step == Amount::Single(0.0) {
+            return None;
+        }
+        Some(Quantity {
+            amount: self.amount_in_step.clone(),
+            unit: self.unit.clone(),
+        })
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Ingredient {
     /// Name of the ingredient.
     pub name: String,
-    /// Uuid is currently not used.
+    /// Stable id of the ingredient, see [Ingredient::id_for].
+    #[cfg(feature = "uuid")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub id: Uuid,
     /// Optional [Amount] specifier.
+    ///
+    /// Sum of all mentionings using the same unit and kind of amount as the first mentioning with
+    /// an amount, the first quantity of [Ingredient::total]. Use [Ingredient::total] to get the
+    /// other mentionings summed up as well.
     pub amount: Option<Amount>,
     /// Unit this ingredient is measured in.
     pub unit: Option<String>,
+    /// The [Quantity] of every mentioning with an amount.
+    pub quantities: GroupedQuantity,
+    /// Distinct preparation notes of all mentionings. See [IngredientSpecifier::note].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub notes: Vec<String>,
+    /// Every mentioning is marked as optional. See [IngredientSpecifier::optional].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optional: bool,
+}
+
+impl Ingredient {
+    /// The [Ingredient::quantities] summed up per unit and kind of [Amount], in order of their
+    /// first mentioning. E.g. `300 g` and `2 tbsp` stay two quantities.
+    pub fn total(&self) -> GroupedQuantity {
+        let mut total = GroupedQuantity::default();
+        self.quantities
+            .quantities
+            .iter()
+            .for_each(|quantity| total.add(quantity));
+        total
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl Ingredient {
+    /// The id of an ingredient with the name, a UUIDv5 of the name. Parsing the same recipe twice
+    /// gives the same ids, and an ingredient has the same id in every recipe.
+    pub fn id_for(name: &str) -> Uuid {
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("cooklang:ingredient:{}", name).as_bytes())
+    }
+}
+
+/// An [Amount] together with the unit it is measured in.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Quantity {
+    /// The [Amount].
+    pub amount: Amount,
+    /// Unit of the amount.
+    pub unit: Option<String>,
+}
+
+/// Multiple [Quantity]s of the same ingredient, possibly measured in different units.
+///
+/// It is up to the user to decide how quantities with different units are combined.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GroupedQuantity {
+    /// The quantities in the order they are mentioned.
+    pub quantities: Vec<Quantity>,
+}
+
+impl GroupedQuantity {
+    /// Add the quantity to the quantity with the same unit and kind of [Amount], or append it if
+    /// there is none.
+    pub fn add(&mut self, quantity: &Quantity) {
+        let sum = self.quantities.iter_mut().find_map(|existing| {
+            if existing.unit != quantity.unit {
+                return None;
+            }
+            let amount = existing.amount.checked_add(&quantity.amount)?;
+            Some((existing, amount))
+        });
+        match sum {
+            Some((existing, amount)) => existing.amount = amount,
+            None => self.quantities.push(quantity.clone()),
+        }
+    }
+
+    /// Distinct units of the quantities in order of their first appearance.
+    pub fn units(&self) -> Vec<Option<&str>> {
+        let mut units = vec![];
+        self.quantities.iter().for_each(|quantity| {
+            let unit = quantity.unit.as_deref();
+            if !units.contains(&unit) {
+                units.push(unit);
+            }
+        });
+        units
+    }
 }
 
+/// A number of an [Amount], like one of the amounts of [Amount::PerServing].
+pub type Value = f64;
+
 /// Specifies the amount of a [Ingredient].
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Amount {
     /// Scalable amount.
     ///
     /// To get the needed amount in the step or total needed amount [Amount::Multi::0] has to be
     /// multiplied by the servings.
     Multi(f64),
-    /// Static Servings amount.
-    Servings(Vec<f64>),
+    /// Amount per servings, one for each of the recipe's [Metadata::servings] in order, e.g.
+    /// `@eggs{1|2|4}` for `>> servings: 2|4|8`. Formerly named `Servings`, which is still read.
+    #[cfg_attr(feature = "serde", serde(alias = "Servings"))]
+    PerServing(Vec<Value>),
     /// Static amount.
     Single(f64),
 }
 
-impl Add for Amount {
-    type Output = Amount;
+impl Amount {
+    /// Add two amounts of the same kind.
+    ///
+    /// Fails if the amounts are of different kinds or have a different number of servings.
+    pub fn try_add(&self, other: &Amount) -> Result<Amount, QuantityError> {
+        match (self, other) {
+            (Amount::Multi(a), Amount::Multi(b)) => Ok(Amount::Multi(a + b)),
+            (Amount::PerServing(a), Amount::PerServing(b)) => {
+                if a.len() != b.len() {
+                    return Err(QuantityError::ServingsMismatch(a.len(), b.len()));
+                }
+                Ok(Amount::PerServing(
+                    a.iter().zip(b.iter()).map(|e| *e.0 + *e.1).collect(),
+                ))
+            }
+            (Amount::Single(a), Amount::Single(b)) => Ok(Amount::Single(a + b)),
+            _ => Err(QuantityError::IncompatibleAmounts),
+        }
+    }
+
+    /// Add two amounts of the same kind. [None] if [Amount::try_add] fails.
+    pub fn checked_add(&self, other: &Amount) -> Option<Amount> {
+        self.try_add(other).ok()
+    }
+
+    /// The needed amount for the servings at `servings_index` of the recipe's servings, e.g. `1`
+    /// for the second entry of `>> servings: 2|4|8`, and `servings_count` servings, e.g. `4`.
+    ///
+    /// * [Amount::Single] is fixed and the same for all servings,
+    /// * [Amount::Multi] is per serving, so it's multiplied by `servings_count`,
+    /// * [Amount::PerServing] selects the amount at `servings_index`, e.g. `@eggs{1|2|4}`.
+    ///
+    /// Fails with [QuantityError::ServingOutOfRange] if there is no amount at `servings_index`.
+    pub fn resolve(
+        &self,
+        servings_index: usize,
+        servings_count: usize,
+    ) -> Result<f64, QuantityError> {
+        match self {
+            Amount::Single(amount) => Ok(*amount),
+            Amount::Multi(amount) => Ok(amount * servings_count as f64),
+            Amount::PerServing(amounts) => amounts
+                .get(servings_index)
+                .copied()
+                .ok_or(QuantityError::ServingOutOfRange(servings_index, amounts.len())),
+        }
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
+    /// Check that there is an amount for each of the recipe's `servings`.
+    ///
+    /// Fails with [QuantityError::ServingsMismatch] if the amount is an [Amount::PerServing] with
+    /// a different number of amounts.
+    pub fn check_servings(&self, servings: usize) -> Result<(), QuantityError> {
         match self {
-            Amount::Multi(a) => match rhs {
-                Amount::Multi(b) => Amount::Multi(a + b),
-                _ => {
-                    panic!("Unallowed Addition");
-                }
-            },
-            Amount::Servings(a) => match rhs {
-                Amount::Servings(b) => {
-                    Amount::Servings(a.iter().zip(b.iter()).map(|e| *e.0 + *e.1).collect())
-                }
-                _ => {
-                    panic!("Unallowed Addition");
-                }
-            },
-            Amount::Single(a) => match rhs {
-                Amount::Single(b) => Amount::Single(a + b),
-                _ => {
-                    panic!("Unallowed Addition");
-                }
-            },
+            Amount::PerServing(amounts) if amounts.len() != servings => {
+                Err(QuantityError::ServingsMismatch(amounts.len(), servings))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the amounts are of the same kind and their numbers differ by rounding errors at
+    /// most, like after a unit conversion. Unlike `==`, `0.1 + 0.2` equals `0.3`.
+    pub fn approx_eq(&self, other: &Amount) -> bool {
+        let close = |a: &f64, b: &f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+        match (self, other) {
+            (Amount::Single(a), Amount::Single(b)) | (Amount::Multi(a), Amount::Multi(b)) => {
+                close(a, b)
+            }
+            (Amount::PerServing(a), Amount::PerServing(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| close(a, b))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Quantity {
+    /// Whether the quantities have the same unit and [approximately](Amount::approx_eq) the same
+    /// amount.
+    pub fn approx_eq(&self, other: &Quantity) -> bool {
+        self.unit == other.unit && self.amount.approx_eq(&other.amount)
+    }
+}
+
+/// Error of calculations with [Amount]s.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QuantityError {
+    /// The amounts are of different kinds, e.g. a scalable and a static amount.
+    IncompatibleAmounts,
+    /// The amounts per servings have a different number of servings.
+    ServingsMismatch(usize, usize),
+    /// The index of the servings is out of range of the amounts per servings.
+    ServingOutOfRange(usize, usize),
+}
+
+impl fmt::Display for QuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantityError::IncompatibleAmounts => write!(f, "Amounts of different kinds"),
+            QuantityError::ServingsMismatch(a, b) => {
+                write!(f, "Amounts for {} and {} servings", a, b)
+            }
+            QuantityError::ServingOutOfRange(index, count) => {
+                write!(f, "No amount for servings {} of {}", index + 1, count)
+            }
         }
     }
 }
 
+impl std::error::Error for QuantityError {}
 
 /// Parse the input into a [Recipe].
+///
+/// Warnings are dropped. Use [parse_with] to get them.
 pub fn parse(inp: &str) -> Result<Recipe, Box<dyn std::error::Error>> {
-    let successful_parse: Pair<_> = match CookParser::parse(Rule::cook_lang, inp) {
-        Ok(d) => d,
-        Err(e) => {
-            panic!("{:?}", e);
+    Ok(parse_with(inp, &ParseOptions::default())?.recipe)
+}
+
+/// Read the whole reader and parse it into a [Recipe], like [parse].
+///
+/// Recipes can be parsed straight from files, network responses or archives. Fails if the reader
+/// fails or doesn't contain UTF-8.
+pub fn parse_reader<R: std::io::Read>(mut reader: R) -> Result<Recipe, ReadError> {
+    let mut inp = String::new();
+    reader.read_to_string(&mut inp)?;
+    Ok(parse_with(&inp, &ParseOptions::default())?.recipe)
+}
+
+/// Read the whole async reader and parse it into a [Recipe], like [parse_reader].
+#[cfg(feature = "tokio")]
+pub async fn parse_async<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+) -> Result<Recipe, ReadError> {
+    use tokio::io::AsyncReadExt;
+    let mut inp = String::new();
+    reader.read_to_string(&mut inp).await?;
+    Ok(parse_with(&inp, &ParseOptions::default())?.recipe)
+}
+
+/// Error of [parse_reader], like [FileError] without a path.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading failed, or the input isn't UTF-8.
+    Io(std::io::Error),
+    /// Parsing the recipe failed.
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(error) => error.fmt(f),
+            ReadError::Parse(error) => error.fmt(f),
         }
     }
-    .next()
-    .unwrap();
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<std::io::Error> for ReadError {
+    fn from(error: std::io::Error) -> Self {
+        ReadError::Io(error)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(error: ParseError) -> Self {
+        ReadError::Parse(error)
+    }
+}
+
+impl FromStr for Recipe {
+    type Err = ParseError;
+
+    /// Parse the recipe with the default [ParseOptions], like [parse].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse_with(s, &ParseOptions::default())?.recipe)
+    }
+}
+
+/// Parse the input into a [Recipe] and normalize all ingredient units with the [UnitRegistry].
+///
+/// Equivalent units like `tbsp` and `tablespoons` are unified into one, so mentionings of the
+/// same ingredient in both units can be summed up.
+pub fn parse_with_units(
+    inp: &str,
+    units: &UnitRegistry,
+) -> Result<Recipe, Box<dyn std::error::Error>> {
+    let options = ParseOptions {
+        units: Some(units.clone()),
+        ..ParseOptions::default()
+    };
+    Ok(parse_with(inp, &options)?.recipe)
+}
+
+/// Options controlling the behavior of [parse_with].
+///
+/// The default options are lenient and match the behavior of [parse].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Fail on syntax that is not understood, like a `@` that isn't followed by an ingredient
+    /// name or an invalid metadata line. Otherwise it is treated as text.
+    pub strict: bool,
+    /// Allow ingredient amounts and timers without a unit.
+    pub allow_missing_units: bool,
+    /// Enable syntax extensions that are not part of the CookLang spec:
+    ///
+    /// - `@&name` references an ingredient mentioned before, see [IngredientSpecifier::reference].
+    /// - `@?name` marks an ingredient as optional, see [Ingredient::optional].
+    /// - `@-name` hides an ingredient from the step text, see [IngredientSpecifier::hidden].
+    /// - `@./path/name` references another recipe, see [IngredientSpecifier::recipe].
+    /// - Temperatures like `180°C` in the text of a step become [Item::Temperature]s.
+    pub extensions: bool,
+    /// Normalize all ingredient units with this registry. See [parse_with_units].
+    pub units: Option<UnitRegistry>,
+    /// Aggregate mentions of ingredients whose names are the same after normalizing, like
+    /// `onions` and `Onion`. The ingredient is named like its first mention.
+    pub names: Option<NameNormalizer>,
+    /// Don't fail on errors. The offending syntax is treated as in lenient mode and the error is
+    /// added to [ParseOutput::warnings] with [Severity::Error], so the rest of the recipe is still
+    /// returned. Useful for live previews in editors.
+    ///
+    /// Only errors found after the grammar matched the recipe are recovered from. The grammar
+    /// itself treats everything it doesn't understand as text or an invalid metadata line, like
+    /// a `@` without a name or an unclosed `{`. Should it fail nonetheless, the error is returned
+    /// like without recovery.
+    pub recover: bool,
+    /// How repeated mentions of an ingredient are aggregated in [Metadata::ingredients].
+    pub duplicates: DuplicateMentions,
+    /// How metadata keys given more than once, like two `>> tags:` lines, are handled.
+    pub duplicate_keys: DuplicateKeys,
+    /// Separators of decimals and lists, like `1,5` and `vegan; quick` in many European
+    /// languages.
+    pub separators: Separators,
+}
+
+/// Separators of decimals and lists, which differ by locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Separators {
+    /// Separator of the decimals of amounts and timers, either `.` or `,`. Numbers written with
+    /// the other one are invalid, so `1,500` isn't read as `1.5` by mistake.
+    pub decimal: char,
+    /// Separator of the values of metadata lists, like `,` in `tags: vegan, quick`. Lists in the
+    /// front matter are joined with it.
+    pub list: char,
+}
+
+impl Default for Separators {
+    /// `.` for decimals and `,` for lists, like in English.
+    fn default() -> Self {
+        Separators {
+            decimal: '.',
+            list: ',',
+        }
+    }
+}
+
+impl Separators {
+    /// The separators for a locale like `de` or `pt-BR`. Languages writing decimals with a
+    /// comma, like in [QuantityFormatter::for_locale], separate lists with `;`.
+    pub fn for_locale(locale: &str) -> Separators {
+        match QuantityFormatter::for_locale(locale).decimal_separator {
+            ',' => Separators {
+                decimal: ',',
+                list: ';',
+            },
+            _ => Separators::default(),
+        }
+    }
+}
+
+/// How repeated mentions of an ingredient are aggregated in [Metadata::ingredients]. References
+/// like `@&flour` never add a quantity, see [IngredientSpecifier::reference].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DuplicateMentions {
+    /// One ingredient with the quantities of all mentions, summed up per unit. See
+    /// [Ingredient::total].
+    #[default]
+    Sum,
+    /// Every mention is an ingredient of its own. Repeated mentions are added with a numbered
+    /// key like `flour (2)`, [Ingredient::name] stays the same.
+    Separate,
+    /// One ingredient with the quantity of the first mention with an amount. Later amounts are
+    /// only kept in [Metadata::ingredients_specifiers].
+    FirstWins,
+}
+
+/// How metadata keys given more than once are handled. Keys in the front matter are overridden
+/// by metadata lines without notice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DuplicateKeys {
+    /// The last value is used, with a warning. In strict mode it's an error. All values stay in
+    /// [Metadata::entries].
+    #[default]
+    Last,
+    /// The values are joined with the [list separator](Separators::list) into one entry, like
+    /// `vegan, quick`. Repeated servings use the last value, with a warning.
+    Concatenate,
+    /// A repeated key is an error, also in lenient mode.
+    Error,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::lenient()
+    }
+}
+
+impl ParseOptions {
+    /// Treat everything that isn't understood as text.
+    pub fn lenient() -> ParseOptions {
+        ParseOptions {
+            strict: false,
+            allow_missing_units: true,
+            extensions: false,
+            units: None,
+            names: None,
+            recover: false,
+            duplicates: DuplicateMentions::Sum,
+            duplicate_keys: DuplicateKeys::Last,
+            separators: Separators::default(),
+        }
+    }
+
+    /// Fail on everything that isn't understood and on missing units.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            strict: true,
+            allow_missing_units: false,
+            ..ParseOptions::lenient()
+        }
+    }
+}
+
+/// Result of [parse_with].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ParseOutput {
+    /// The parsed [Recipe].
+    pub recipe: Recipe,
+    /// Recoverable issues found while parsing, in the order of the source. In recovery mode
+    /// this includes the errors. See [ParseOptions::recover].
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl ParseOutput {
+    /// Whether errors have been recovered from.
+    pub fn has_errors(&self) -> bool {
+        self.warnings
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Error returned if a recipe can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The [Diagnostic] describing the error.
+    pub diagnostic: Diagnostic,
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.diagnostic.message, self.line, self.column
+        )
+    }
+}
+
+impl ParseError {
+    /// Create an error from a [Diagnostic] of the source.
+    pub fn new(source: &str, diagnostic: Diagnostic) -> ParseError {
+        let (line, column) = diagnostic.line_col(source);
+        ParseError {
+            diagnostic,
+            line,
+            column,
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<pest::error::Error<Rule>> for ParseError {
+    fn from(error: pest::error::Error<Rule>) -> Self {
+        let span = match error.location {
+            pest::error::InputLocation::Pos(pos) => Span::new(pos, pos),
+            pest::error::InputLocation::Span((start, end)) => Span::new(start, end),
+        };
+        let (line, column) = match error.line_col {
+            pest::error::LineColLocation::Pos(pos) => pos,
+            pest::error::LineColLocation::Span(start, _) => start,
+        };
+        ParseError {
+            diagnostic: Diagnostic::error(span, error.variant.message()),
+            line,
+            column,
+        }
+    }
+}
+
+/// Parse the input into a [Recipe] using the given [ParseOptions].
+///
+/// Recoverable issues, like an ingredient amount that can't be understood and is kept as text,
+/// are returned as warnings in the [ParseOutput]. In strict mode they fail the parsing instead.
+pub fn parse_with(inp: &str, options: &ParseOptions) -> Result<ParseOutput, ParseError> {
+    let units = options.units.as_ref();
+    let mut warnings = vec![];
+    // Spans of the amounts per serving, checked once the servings are known.
+    let mut per_serving = vec![];
+    let mut comments = vec![];
+    // Spans of the ingredients, cookware and timers in order, for the layout.
+    let mut mentions = vec![];
+    let mut source_map = SourceMap::default();
+    let successful_parse: Pair<_> = CookParser::parse(Rule::cook_lang, inp)?
+        .next()
+        .unwrap();
     let mut metadata = Metadata {
         servings: None,
-        ominous: Default::default(),
+        servings_labels: vec![],
+        entries: MetadataMap::new(),
         ingredients: IndexMap::new(),
         ingredients_specifiers: vec![],
         cookware: vec![],
         timer: vec![],
+        list_separator: options.separators.list,
     };
     let source = successful_parse.as_str().to_string();
-    let mut source_edited = source.clone();
+    // The reduced instruction is built in one pass, copying the text between the parts of the
+    // source as it is.
+    let mut instruction = String::with_capacity(inp.len());
+    let mut position = 0;
+    let mut front_matter = front_matter::FrontMatter::default();
     let metadata_line_iterator = successful_parse.clone().into_inner();
-    metadata_line_iterator.for_each(|e| {
-        if e.as_rule() == Rule::metadata {
-            e.into_inner().for_each(|property| {
+    for e in metadata_line_iterator {
+        source_map.copy(&mut instruction, 0, inp, position..e.as_span().start());
+        position = e.as_span().end();
+        if e.as_rule() == Rule::front_matter {
+            let body = e.into_inner().next().unwrap();
+            match front_matter::parse(body.as_str(), options.separators.list) {
+                Ok(parsed) => front_matter = parsed,
+                Err(message) if options.strict => {
+                    let error = syntax_error(&body, &message, FRONT_MATTER_HINT);
+                    report_error(options, &mut warnings, error)?;
+                }
+                Err(message) => warnings.push(
+                    Diagnostic::warning(body.as_span().into(), message + ", front matter is ignored")
+                        .with_hint(FRONT_MATTER_HINT),
+                ),
+            }
+        } else if e.as_rule() == Rule::metadata {
+            source_map.copy(&mut instruction, 0, inp, span_range(&e));
+            for property in e.into_inner() {
+                let property_pair = property.clone();
+                let is_servings = property.as_rule() == Rule::servings;
                 let mut key_value_iterator = property.into_inner();
-                let name = key_value_iterator.next().unwrap().as_str();
+                let name = if is_servings {
+                    "servings"
+                } else {
+                    key_value_iterator.next().unwrap().as_str()
+                };
+                let duplicate = if !is_servings {
+                    metadata.entries.contains_key(name)
+                } else {
+                    metadata.servings.is_some()
+                };
+                let concatenate =
+                    !is_servings && options.duplicate_keys == DuplicateKeys::Concatenate;
+                if duplicate && !concatenate {
+                    let message = format!("Duplicate metadata key {}", name);
+                    if options.strict || options.duplicate_keys == DuplicateKeys::Error {
+                        let error = syntax_error(&property_pair, &message, DUPLICATE_HINT);
+                        report_error(options, &mut warnings, error)?;
+                    } else {
+                        warnings.push(
+                            Diagnostic::warning(
+                                property_pair.as_span().into(),
+                                message + ", the last value is used",
+                            )
+                            .with_hint(DUPLICATE_HINT),
+                        );
+                    }
+                }
 
-                if name != "servings" {
-                    let value = key_value_iterator.next().unwrap().as_str();
-                    metadata.add_key_value(name.to_string(), value.to_string());
+                if !is_servings {
+                    let value = key_value_iterator.next().unwrap().as_str().trim_end();
+                    if duplicate && concatenate {
+                        metadata.entries.concat(name, value, options.separators.list);
+                    } else {
+                        metadata.add_key_value(name.to_string(), value.to_string());
+                    }
                 } else {
+                    let value = key_value_iterator.next().unwrap();
                     let mut servings = Vec::with_capacity(3);
-                    key_value_iterator
-                        .next()
-                        .unwrap()
-                        .into_inner()
-                        .for_each(|serving| {
-                            // println!("Serving => {:?}", serving);
-                            if serving.as_str() != "|" {
-                                let serving_number = usize::from_str(serving.as_str())
-                                    .expect("Parsing of serving number failed");
-                                servings.push(serving_number);
+                    let mut labels = Vec::with_capacity(3);
+                    let mut offset = value.as_span().start();
+                    for serving in value.as_str().split('|') {
+                        let label = serving.trim();
+                        let start = offset + serving.len() - serving.trim_start().len();
+                        let span = Span::new(start, start + label.len());
+                        offset += serving.len() + 1;
+                        match servings_number(label) {
+                            Some(serving_number) => servings.push(serving_number),
+                            None if options.strict => {
+                                let error = ParseError::new(
+                                    inp,
+                                    Diagnostic::error(span, "Invalid servings")
+                                        .with_hint(SERVINGS_HINT),
+                                );
+                                report_error(options, &mut warnings, error)?;
                             }
-                        });
+                            None => warnings.push(
+                                Diagnostic::warning(span, "Invalid servings are ignored")
+                                    .with_hint(SERVINGS_HINT),
+                            ),
+                        }
+                        labels.push(label.to_string());
+                    }
                     metadata.servings = Some(servings);
+                    metadata.servings_labels = labels;
                 }
-            });
+            }
         } else if e.as_rule() == Rule::comment {
-            println!("Replacing comment = {}", e.as_str());
-            source_edited = source_edited.replace(e.as_str(), "");
-
+            // Comments are not part of the instruction.
+            comments.push(comment(e));
+        } else if e.as_rule() == Rule::section || e.as_rule() == Rule::note {
+            source_map.push(&mut instruction, 0, &escape(e.as_str()), span_range(&e));
+        } else if e.as_rule() == Rule::invalid_metadata {
+            source_map.copy(&mut instruction, 0, inp, span_range(&e));
+            if options.strict {
+                let error = syntax_error(&e, "Invalid metadata", METADATA_HINT);
+                report_error(options, &mut warnings, error)?;
+            }
         } else {
-            // println!("Line => {:?}", e);
-            let _line = e.as_str().to_string().clone();
-            e.into_inner().for_each(|ingredients_cookware| {
-                // println!("Ingredient / Cookware => {:?}", ingredients_cookware);
+            let mut line_edited = String::with_capacity(e.as_str().len());
+            // The line is added to the instruction as a whole, after the previous lines.
+            let line_offset = instruction.len();
+            let mut line_position = e.as_span().start();
+            let line_end = e.as_span().end();
+            for ingredients_cookware in e.into_inner() {
+                let mention = span_range(&ingredients_cookware);
+                let text = line_position..mention.start;
+                source_map.copy(&mut line_edited, line_offset, inp, text);
+                line_position = mention.end;
+                let kind = match ingredients_cookware.as_rule() {
+                    Rule::ingredient => Some(MentionKind::Ingredient),
+                    Rule::cookware => Some(MentionKind::Cookware),
+                    Rule::timer => Some(MentionKind::Timer),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    mentions.push((kind, ingredients_cookware.as_span().into()));
+                }
                 if ingredients_cookware.as_rule() == Rule::ingredient {
-                    source_edited = source_edited.replace(ingredients_cookware.as_str(), "@");
-                    // println!("Ingredient => {:?}", ingredients_cookware);
-                    let mut name = String::new();
-                    let mut ingredient_amount = None;
-                    let mut ingredient_modified = None;
-                    let mut ingredient_unit = None;
-                    ingredients_cookware
-                        .into_inner()
-                        .for_each(|ingredient_property| {
-                            // println!("Ingredient Property => {:?}", ingredient_property);
-                            match ingredient_property.as_rule() {
-                                Rule::name => {
-                                    name.push_str(ingredient_property.as_str());
-                                    name.push(' ');
-                                }
-                                Rule::text => {
-                                    name.push_str(ingredient_property.as_str());
-                                    name.push(' ');
-                                }
-                                Rule::number => {
-                                    ingredient_property.into_inner().for_each(
-                                        |ingredient_amount_inner| match ingredient_amount.clone() {
-                                            None => {
-                                                ingredient_amount = Some(Amount::Single(
-                                                    usize::from_str(
-                                                        ingredient_amount_inner.as_str(),
-                                                    )
-                                                    .expect("Failed to parse ingredient amount")
-                                                        as f64,
-                                                ))
-                                            }
-                                            Some(d) => {
-                                                let data_point = usize::from_str(
-                                                    ingredient_amount_inner.as_str(),
-                                                )
-                                                .expect("Failed to parse ingredient amount")
-                                                    as f64;
-                                                let ingredient_amount_raw = match d {
-                                                    Amount::Multi(_) => {
-                                                        panic!("This isn't allowed with multiply.")
-                                                    }
-                                                    Amount::Servings(dd) => {
-                                                        let mut res = dd.clone();
-                                                        // println!("Res => {:?}", res);
-                                                        let last = res.len() - 1;
-                                                        if res.get(last).unwrap().clone() == 0.0 {
-                                                            let reference =
-                                                                res.get_mut(last).unwrap();
-                                                            *reference = data_point;
-                                                        } else {
-                                                            let dat = res.pop().unwrap();
-                                                            res.push(dat / data_point);
-                                                        }
-                                                        // println!("Res => {:?}", res);
-                                                        Amount::Servings(res)
-                                                    }
-                                                    Amount::Single(d) => {
-                                                        Amount::Single(d / data_point)
-                                                    }
-                                                };
-                                                ingredient_amount = Some(ingredient_amount_raw);
-                                            }
-                                        },
-                                    );
-                                }
-                                Rule::ingredient_separator => match ingredient_amount.clone() {
-                                    None => {
-                                        panic!("This shouldn't have happened.");
-                                    }
-                                    Some(d) => match d {
-                                        Amount::Multi(_) => {
-                                            panic!("This shouldn't have happened.")
-                                        }
-                                        Amount::Servings(dd) => {
-                                            let mut res = dd.clone();
-                                            res.push(0.0);
-                                            ingredient_amount = Some(Amount::Servings(res));
-                                        }
-                                        Amount::Single(dd) => {
-                                            ingredient_amount =
-                                                Some(Amount::Servings(vec![dd, 0.0]));
-                                        }
-                                    },
-                                },
-                                Rule::modified => {
-                                    let modified = ingredient_property
-                                        .into_inner()
-                                        .next()
-                                        .unwrap()
-                                        .as_str()
-                                        .to_string();
-                                    ingredient_modified = Some(modified);
-                                }
-                                Rule::unit => {
-                                    ingredient_unit = Some(ingredient_property.as_str().to_string())
-                                }
-                                Rule::scaling => {
-                                    ingredient_amount = match ingredient_amount.clone() {
-                                        Some(d) => match d {
-                                            Amount::Single(d) => Some(Amount::Multi(d)),
-                                            _ => {
-                                                panic!("This shouldn't have happened.")
-                                            }
-                                        },
-                                        None => {
-                                            panic!("This shouldn't have happened.")
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    panic!("That should have happened")
-                                }
-                            }
-                        });
-                    if name.len() > 0 {
-                        name.pop();
+                    source_map.push(&mut line_edited, line_offset, "@", mention.clone());
+                    let ingredient_pair = ingredients_cookware.clone();
+                    let parts = ingredient_parts(ingredients_cookware, &options.separators);
+                    let words = parts.words.iter().map(pest::Span::as_str);
+                    let mut name = words.collect::<Vec<_>>().join(" ");
+                    let ingredient_amount = parts.amount;
+                    let ingredient_modified = parts.note.map(str::to_string);
+                    let ingredient_unit = parts.unit.map(|unit| match units {
+                        Some(units) => units.normalize(unit),
+                        None => unit.to_string(),
+                    });
+                    let invalid_amount = parts.invalid_amount;
+                    let mut modifiers = parts.modifiers.to_string();
+                    let mut recipe = parts.recipe;
+                    if invalid_amount {
+                        let ingredient_str = ingredient_pair.as_str();
+                        let start = ingredient_str.find('{').unwrap();
+                        let end = ingredient_str.rfind('}').unwrap() + 1;
+                        let offset = ingredient_pair.as_span().start();
+                        let span = Span::new(offset + start, offset + end);
+                        if options.strict {
+                            let error = syntax_error(&ingredient_pair, "Invalid amount", AMOUNT_HINT);
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(span, "Unparseable amount is treated as text")
+                                    .with_hint(AMOUNT_HINT),
+                            );
+                        }
+                        let amount = escape(&ingredient_str[start..end]);
+                        let range = offset + start..offset + end;
+                        source_map.push(&mut line_edited, line_offset, &amount, range);
+                    }
+                    if let Some(amount @ Amount::PerServing(_)) = &ingredient_amount {
+                        let span = ingredient_pair.as_span();
+                        let start = span.start() + span.as_str().find('{').unwrap();
+                        let end = span.start() + span.as_str().rfind('}').unwrap() + 1;
+                        per_serving.push((Span::new(start, end), amount.clone()));
+                    }
+                    if ingredient_amount.is_some()
+                        && ingredient_unit.is_none()
+                        && !options.allow_missing_units
+                    {
+                        let error = syntax_error(&ingredient_pair, "Missing unit", UNIT_HINT);
+                        report_error(options, &mut warnings, error)?;
+                    }
+                    let modifiers_start = ingredient_pair.as_span().start() + 1;
+                    let modifiers_span =
+                        Span::new(modifiers_start, modifiers_start + modifiers.len());
+                    if !modifiers.is_empty() && !options.extensions {
+                        if options.strict {
+                            let error = ParseError::new(
+                                inp,
+                                Diagnostic::error(
+                                    modifiers_span,
+                                    "Ingredient modifiers are an extension",
+                                )
+                                .with_hint(EXTENSIONS_HINT),
+                            );
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(
+                                    modifiers_span,
+                                    "Ingredient modifiers are ignored without extensions",
+                                )
+                                .with_hint(EXTENSIONS_HINT),
+                            );
+                        }
+                        modifiers.clear();
+                    }
+                    if recipe && !options.extensions {
+                        let span = ingredient_pair.as_span().into();
+                        if options.strict {
+                            let error = ParseError::new(
+                                inp,
+                                Diagnostic::error(span, "Recipe references are an extension")
+                                    .with_hint(EXTENSIONS_HINT),
+                            );
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(
+                                    span,
+                                    "Recipe reference is treated as an ingredient without extensions",
+                                )
+                                .with_hint(EXTENSIONS_HINT),
+                            );
+                        }
+                        recipe = false;
+                    }
+                    if let Some(names) = options.names.as_ref().filter(|_| !recipe) {
+                        let mut keys = metadata.ingredients.keys();
+                        if let Some(key) = keys.find(|key| names.same(key, &name)) {
+                            name = key.clone();
+                        }
+                    }
+                    let mut reference = modifiers.contains('&');
+                    if reference && !metadata.ingredients.contains_key(&name) {
+                        let span = ingredient_pair.as_span().into();
+                        if options.strict {
+                            let error = ParseError::new(
+                                inp,
+                                Diagnostic::error(
+                                    span,
+                                    "Reference to an ingredient that hasn't been mentioned before",
+                                )
+                                .with_hint(REFERENCE_HINT),
+                            );
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(
+                                    span,
+                                    "Reference to an ingredient that hasn't been mentioned before",
+                                )
+                                .with_hint(REFERENCE_HINT),
+                            );
+                        }
+                        reference = false;
+                    }
+                    let optional = modifiers.contains('?');
+                    let hidden = modifiers.contains('-');
+                    let mut key = name.clone();
+                    if options.duplicates == DuplicateMentions::Separate && !reference && !recipe {
+                        let count = metadata.ingredients.values().filter(|i| i.name == name);
+                        key = match count.count() {
+                            0 => key,
+                            count => format!("{} ({})", name, count + 1),
+                        };
                     }
                     let ingredient_specifier = IngredientSpecifier {
                         ingredient: name.clone(),
@@ -324,92 +1202,510 @@ pub fn parse(inp: &str) -> Result<Recipe, Box<dyn std::error::Error>> {
                             None => Amount::Single(0.0),
                             Some(d) => d,
                         },
+                        note: ingredient_modified.clone(),
+                        reference,
+                        optional,
+                        hidden,
+                        recipe,
+                        unit: ingredient_unit.clone(),
+                        key: Some(key.clone()).filter(|key| *key != name),
                     };
                     metadata
                         .ingredients_specifiers
                         .push(ingredient_specifier.clone());
-                    if metadata.ingredients.contains_key(&name) {
-                        let mut ingredient = metadata.ingredients.get_mut(&name).unwrap();
-                        match ingredient_amount.clone() {
-                            None => {}
-                            Some(amount) => {
-                                ingredient.amount =
-                                    Some(ingredient.amount.as_ref().unwrap().clone() + amount);
+                    if recipe {
+                        continue;
+                    }
+                    let quantity = ingredient_amount.clone().map(|amount| Quantity {
+                        amount,
+                        unit: ingredient_unit.clone(),
+                    });
+                    if metadata.ingredients.contains_key(&key) {
+                        let ingredient = metadata.ingredients.get_mut(&key).unwrap();
+                        let add = options.duplicates != DuplicateMentions::FirstWins
+                            || ingredient.quantities.quantities.is_empty();
+                        if !reference && add {
+                            if let Some(error) = quantity.as_ref().and_then(|quantity| {
+                                sum_error(&ingredient.total(), quantity)
+                            }) {
+                                let span = ingredient_pair.as_span().into();
+                                let message =
+                                    format!("Amounts of {} can't be added: {}", name, error);
+                                if options.strict {
+                                    let error = ParseError::new(
+                                        inp,
+                                        Diagnostic::error(span, message).with_hint(SUM_HINT),
+                                    );
+                                    report_error(options, &mut warnings, error)?;
+                                } else {
+                                    let warning = Diagnostic::warning(span, message);
+                                    warnings.push(warning.with_hint(SUM_HINT));
+                                }
+                            }
+                            ingredient.quantities.quantities.extend(quantity);
+                            ingredient.optional &= optional;
+                            if let Some(total) = ingredient.total().quantities.into_iter().next() {
+                                ingredient.amount = Some(total.amount);
+                                ingredient.unit = total.unit;
                             }
                         }
-                        if ingredient.unit != ingredient_unit {
-                            panic!("Amount of ingredient is inconsistent.")
+                        if let Some(note) = ingredient_modified {
+                            if !ingredient.notes.contains(&note) {
+                                ingredient.notes.push(note);
+                            }
                         }
-                        ingredient.unit = ingredient_unit;
                     } else {
                         let ingredient = Ingredient {
                             name: name.clone(),
-                            id: Uuid::new_v4(),
+                            #[cfg(feature = "uuid")]
+                            id: Ingredient::id_for(&name),
                             amount: ingredient_amount,
                             unit: ingredient_unit,
+                            quantities: GroupedQuantity {
+                                quantities: quantity.into_iter().collect(),
+                            },
+                            notes: ingredient_modified.into_iter().collect(),
+                            optional,
                         };
-                        metadata.ingredients.insert(name.clone(), ingredient);
+                        metadata.ingredients.insert(key, ingredient);
                     }
-                    // println!("Name => {}", name);
                 } else if ingredients_cookware.as_rule() == Rule::cookware {
-                    source_edited = source_edited.replace(ingredients_cookware.as_str(), "#");
-                    // println!("Cookware => {:?}", ingredients_cookware);
+                    source_map.push(&mut line_edited, line_offset, "#", mention);
                     let mut name = String::new();
                     ingredients_cookware
                         .into_inner()
                         .for_each(|cookware_property| {
-                            // println!("Cookware Property => {:?}", cookware_property);
                             name.push_str(cookware_property.as_str());
                             name.push(' ');
                         });
                     name.pop().unwrap();
-                    // println!("Name => {}", name);
                     metadata.cookware.push(name);
                 } else if ingredients_cookware.as_rule() == Rule::timer {
-                    source_edited = source_edited.replace(ingredients_cookware.as_str(), "~");
-                    // println!("Timer => {:?}", ingredients_cookware);
-                    let mut timer = Timer {
-                        amount: 0.0,
-                        unit: "".to_string(),
-                    };
-                    ingredients_cookware
-                        .into_inner()
-                        .for_each(|timer_property| {
-                            // println!("Timer Property => {:?}", timer_property);
-                            if timer_property.as_rule() == Rule::number {
-                                let amount = usize::from_str(timer_property.as_str())
-                                    .expect("Unaple to parse timer duration")
-                                    as f64;
-                                timer.amount = amount;
+                    source_map.push(&mut line_edited, line_offset, "~", mention);
+                    let timer_pair = ingredients_cookware.clone();
+                    let (mut amount, mut max, unit) = match timer_value(ingredients_cookware) {
+                        Ok(value) => value,
+                        Err(first) => {
+                            if options.strict {
+                                let message = "Unknown time unit in a compound duration";
+                                let error = syntax_error(&timer_pair, message, TIME_UNIT_HINT);
+                                report_error(options, &mut warnings, error)?;
                             } else {
-                                let unit = timer_property.as_str().to_string();
-                                timer.unit = unit;
+                                let message = "Only the first part of a duration with an \
+                                               unknown time unit is used";
+                                warnings.push(
+                                    Diagnostic::warning(timer_pair.as_span().into(), message)
+                                        .with_hint(TIME_UNIT_HINT),
+                                );
                             }
-                        });
+                            first
+                        }
+                    };
+                    if foreign_decimal(&timer_pair, &options.separators) {
+                        let message = format!(
+                            "Decimal separator isn't {}",
+                            options.separators.decimal
+                        );
+                        if options.strict {
+                            let error = syntax_error(&timer_pair, &message, DECIMAL_HINT);
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(
+                                    timer_pair.as_span().into(),
+                                    message + ", the amount is ignored",
+                                )
+                                .with_hint(DECIMAL_HINT),
+                            );
+                        }
+                        // Like amounts of ingredients, `1,500` isn't read as 1.5.
+                        amount = 0.0;
+                        max = None;
+                    }
+                    if max.is_some_and(|max| max < amount) {
+                        let message = "Upper end of the range is below the amount";
+                        if options.strict {
+                            let error = syntax_error(&timer_pair, message, RANGE_HINT);
+                            report_error(options, &mut warnings, error)?;
+                        } else {
+                            warnings.push(
+                                Diagnostic::warning(
+                                    timer_pair.as_span().into(),
+                                    format!("{}, the range is ignored", message),
+                                )
+                                .with_hint(RANGE_HINT),
+                            );
+                        }
+                        max = None;
+                    }
+                    let timer = Timer {
+                        amount,
+                        unit: unit.to_string(),
+                        max,
+                    };
+                    if timer.unit.is_empty() && !options.allow_missing_units {
+                        let error = syntax_error(&timer_pair, "Missing unit", UNIT_HINT);
+                        report_error(options, &mut warnings, error)?;
+                    }
                     metadata.timer.push(timer);
-                } else if ingredients_cookware.as_rule() == Rule::comment {
-                    println!("Replacing comment {}", ingredients_cookware.as_str());
-                    source_edited = source_edited.replace(ingredients_cookware.as_str(), "");
+                } else if ingredients_cookware.as_rule() == Rule::comment
+                    || ingredients_cookware.as_rule() == Rule::block_comment
+                {
+                    // Comments are not part of the instruction.
+                    comments.push(comment(ingredients_cookware));
+                } else if ingredients_cookware.as_rule() == Rule::unknown {
+                    let sigil = ingredients_cookware.as_str();
+                    let empty_name = sigil != "~"
+                        && inp[ingredients_cookware.as_span().end()..].starts_with('{');
+                    if options.strict {
+                        let error =
+                            syntax_error(&ingredients_cookware, "Unknown syntax", NAME_HINT);
+                        report_error(options, &mut warnings, error)?;
+                    } else if empty_name {
+                        let message = if sigil == "@" {
+                            "Empty ingredient name, treated as text"
+                        } else {
+                            "Empty cookware name, treated as text"
+                        };
+                        warnings.push(
+                            Diagnostic::warning(ingredients_cookware.as_span().into(), message)
+                                .with_hint(NAME_HINT),
+                        );
+                    }
+                    let escaped = format!("\\{}", sigil);
+                    source_map.push(&mut line_edited, line_offset, &escaped, mention);
                 }
-            })
+            }
+            source_map.copy(&mut line_edited, line_offset, inp, line_position..line_end);
+            instruction.push_str(&line_edited);
         }
-    });
-    // println!("{:#?}", successful_parse);
-    // println!("Source edited: {}", source_edited);
-    // println!("{:#?}", metadata);
-    let recipe = Recipe {
+    }
+    source_map.copy(&mut instruction, 0, inp, position..inp.len());
+    source_map.set_mentions(&mentions);
+    // Metadata lines take precedence over the front matter.
+    if metadata.servings.is_none() {
+        metadata.servings = front_matter.servings;
+        metadata.servings_labels = front_matter.servings_labels;
+    }
+    if let Some(servings) = &metadata.servings {
+        for (span, amount) in per_serving {
+            let Err(QuantityError::ServingsMismatch(amounts, servings)) =
+                amount.check_servings(servings.len())
+            else {
+                continue;
+            };
+            let message = format!("{} amounts for {} servings", amounts, servings);
+            if options.strict {
+                let error = ParseError::new(
+                    inp,
+                    Diagnostic::error(span, message).with_hint(PER_SERVING_HINT),
+                );
+                report_error(options, &mut warnings, error)?;
+            } else {
+                warnings.push(Diagnostic::warning(span, message).with_hint(PER_SERVING_HINT));
+            }
+        }
+    }
+    let mut entries: MetadataMap = front_matter
+        .entries
+        .into_iter()
+        .filter(|(key, _)| !metadata.entries.contains_key(key))
+        .collect();
+    entries.extend(metadata.entries.iter());
+    metadata.entries = entries;
+    let (steps, sections) = steps::split_steps(&instruction, &metadata, options.extensions);
+    let mut recipe = Recipe {
         source,
         metadata,
-        instruction: source_edited
+        instruction,
+        steps,
+        sections,
+        comments,
+        layout: None,
+        source_map: Some(source_map),
+        path: None,
+        image: None,
+        step_images: vec![],
+    };
+    recipe.layout = Layout::new(&recipe, &mentions);
+    Ok(ParseOutput { recipe, warnings })
+
+}
+
+/// The comment of a `comment` or `block_comment` pair.
+fn comment(pair: Pair<Rule>) -> Comment {
+    let kind = match pair.as_rule() {
+        Rule::block_comment => CommentKind::Block,
+        _ => CommentKind::Line,
+    };
+    let span = pair.as_span().into();
+    let text = pair.into_inner().next().map_or("", |text| text.as_str());
+    Comment {
+        text: text.trim().to_string(),
+        span,
+        kind,
+    }
+}
+
+/// First number in a servings option like `makes 12 cookies`.
+pub(crate) fn servings_number(serving: &str) -> Option<usize> {
+    let start = serving.find(|c: char| c.is_ascii_digit())?;
+    let digits = &serving[start..];
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    usize::from_str(&digits[..end]).ok()
+}
+
+/// Text for the reduced instruction, with @, # and ~ escaped unless they are escaped already.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+    text.chars().for_each(|char| {
+        if (char == '@' || char == '#' || char == '~') && previous != Some('\\') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+        previous = Some(char);
+    });
+    escaped
+}
+
+/// Byte range of the pair in the source.
+fn span_range(pair: &Pair<Rule>) -> std::ops::Range<usize> {
+    pair.as_span().start()..pair.as_span().end()
+}
+
+/// Value of a number like `3`, `1/2` or `1.5`. Both `.` and `,` are read as decimal separator,
+/// see [foreign_decimal].
+fn number_value(number: Pair<Rule>) -> f64 {
+    let mut values = number.into_inner().map(|num_value| {
+        let num_value = num_value.as_str().replace(',', ".");
+        f64::from_str(&num_value).expect("Failed to parse number")
+    });
+    let first = values.next().unwrap_or_default();
+    values.fold(first, |value, divisor| value / divisor)
+}
+
+/// The parts of an ingredient mention like `@?fresh basil{1/2%bunch}(chopped)`, read the same
+/// way by [parse_with] and [parse_borrowed].
+pub(crate) struct IngredientParts<'a> {
+    /// The modifiers before the name, like `?`.
+    pub(crate) modifiers: &'a str,
+    /// The words of the name, or the path of a referenced recipe.
+    pub(crate) words: Vec<pest::Span<'a>>,
+    /// Whether the ingredient is a reference to another recipe.
+    pub(crate) recipe: bool,
+    /// The amount, [None] if there is none or it is invalid.
+    pub(crate) amount: Option<Amount>,
+    /// The unit as written, [None] if there is none or the amount is invalid.
+    pub(crate) unit: Option<&'a str>,
+    /// The preparation note, [None] if there is none or it is blank.
+    pub(crate) note: Option<&'a str>,
+    /// Whether the amount is written but can't be read, see [foreign_decimal].
+    pub(crate) invalid_amount: bool,
+}
+
+/// Read the parts of an ingredient mention.
+pub(crate) fn ingredient_parts<'a>(
+    ingredient: Pair<'a, Rule>,
+    separators: &Separators,
+) -> IngredientParts<'a> {
+    // Scaling of amounts per servings, divisions by zero and numbers like `1,5` if the decimal
+    // separator is `.`.
+    let mut invalid_amount = foreign_decimal(&ingredient, separators);
+    let mut parts = IngredientParts {
+        modifiers: "",
+        words: vec![],
+        recipe: false,
+        amount: None,
+        unit: None,
+        note: None,
+        invalid_amount: false,
+    };
+    let mut numbers = vec![];
+    let mut scaling = false;
+    for property in ingredient.into_inner() {
+        match property.as_rule() {
+            Rule::ingredient_modifier => {
+                // The modifiers are single characters right after each other.
+                let end = property.as_span().end();
+                parts.modifiers = &property.get_input()[end - parts.modifiers.len() - 1..end];
+            }
+            Rule::name | Rule::text => parts.words.push(property.as_span()),
+            Rule::recipe_path => {
+                parts.words.push(property.as_span());
+                parts.recipe = true;
+            }
+            Rule::number => numbers.push(number_value(property)),
+            Rule::ingredient_separator => {}
+            Rule::modified => {
+                let note = property.into_inner().next().unwrap().as_str().trim();
+                parts.note = Some(note).filter(|note| !note.is_empty());
+            }
+            Rule::unit => parts.unit = Some(property.as_str()),
+            Rule::scaling => scaling = true,
+            Rule::invalid_amount => invalid_amount = true,
+            _ => {
+                panic!("That should have happened")
+            }
+        }
+    }
+    parts.amount = match (numbers.len(), scaling) {
+        (0, _) => None,
+        (1, false) => Some(Amount::Single(numbers[0])),
+        (1, true) => Some(Amount::Multi(numbers[0])),
+        (_, false) => Some(Amount::PerServing(numbers)),
+        (_, true) => None,
+    };
+    invalid_amount |= (scaling && parts.amount.is_none())
+        || parts
+            .amount
+            .as_ref()
+            .is_some_and(|amount| !amount.max_value().is_finite());
+    if invalid_amount {
+        parts.amount = None;
+        parts.unit = None;
+    }
+    parts.invalid_amount = invalid_amount;
+    parts
+}
+
+/// Whether a number in the pair is written with another decimal separator than the configured
+/// one.
+fn foreign_decimal(pair: &Pair<Rule>, separators: &Separators) -> bool {
+    pair.clone()
+        .into_inner()
+        .flatten()
+        .filter(|inner| inner.as_rule() == Rule::num_value)
+        .any(|num_value| {
+            num_value
+                .as_str()
+                .contains(|c| matches!(c, '.' | ',') && c != separators.decimal)
+        })
+}
+
+/// The amount, the upper end of a range and the unit of a timer.
+pub(crate) type TimerValue<'a> = (f64, Option<f64>, &'a str);
+
+/// Amount, upper end and unit of the timer. The parts of a compound duration like
+/// `1%hour 30%minutes` are added up in the smallest of their units. Fails with the first part if
+/// one of the units isn't a known time unit.
+pub(crate) fn timer_value(timer: Pair<Rule>) -> Result<TimerValue, TimerValue> {
+    let mut parts: Vec<TimerValue> = vec![];
+    for property in timer.into_inner() {
+        match property.as_rule() {
+            Rule::number => parts = vec![(number_value(property), None, "")],
+            Rule::unit => parts.iter_mut().for_each(|part| part.2 = property.as_str()),
+            Rule::timer_part => {
+                let mut part = (0.0, None, "");
+                for inner in property.into_inner() {
+                    match inner.as_rule() {
+                        Rule::timer_range => {
+                            let mut numbers = inner.into_inner().map(number_value);
+                            part.0 = numbers.next().unwrap_or_default();
+                            part.1 = numbers.next();
+                        }
+                        Rule::number => part.0 = number_value(inner),
+                        _ => part.2 = inner.as_str(),
+                    }
+                }
+                parts.push(part);
+            }
+            _ => {}
+        }
+    }
+    if parts.len() <= 1 {
+        return Ok(parts.pop().unwrap_or((0.0, None, "")));
+    }
+    let seconds: Option<Vec<f64>> = parts
+        .iter()
+        .map(|(_, _, unit)| time::unit_seconds(unit))
+        .collect();
+    let seconds = seconds.ok_or(parts[0])?;
+    let smallest = (0..parts.len())
+        .min_by(|a, b| seconds[*a].total_cmp(&seconds[*b]))
+        .unwrap();
+    let sum = |value: fn(&TimerValue) -> f64| -> f64 {
+        let total: f64 = parts.iter().zip(&seconds).map(|(p, s)| value(p) * s).sum();
+        total / seconds[smallest]
+    };
+    let max = match parts.iter().any(|part| part.1.is_some()) {
+        true => Some(sum(|part| part.1.unwrap_or(part.0))),
+        false => None,
     };
-    Ok(recipe)
+    Ok((sum(|part| part.0), max, parts[smallest].2))
+}
+
+/// Fail with the error, or record it and go on in recovery mode.
+fn report_error(
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+    error: ParseError,
+) -> Result<(), ParseError> {
+    if options.recover {
+        diagnostics.push(error.diagnostic);
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
 
+/// The error of adding the quantity to the total, if it has the unit of the total but none of
+/// its quantities of that unit can be added to.
+fn sum_error(total: &GroupedQuantity, quantity: &Quantity) -> Option<QuantityError> {
+    let mut sums = total
+        .quantities
+        .iter()
+        .filter(|existing| existing.unit == quantity.unit)
+        .map(|existing| existing.amount.try_add(&quantity.amount));
+    let first = sums.next()?.err()?;
+    match sums.all(|sum| sum.is_err()) {
+        true => Some(first),
+        false => None,
+    }
+}
+
+const DUPLICATE_HINT: &str = "Remove one of the values";
+const SERVINGS_HINT: &str = "Servings are whole numbers separated by |, like 2|4";
+const METADATA_HINT: &str = "Metadata is written as >> key: value";
+const FRONT_MATTER_HINT: &str = "Front matter is a YAML mapping like title: Pancakes";
+const EXTENSIONS_HINT: &str = "Enable ParseOptions::extensions";
+const REFERENCE_HINT: &str = "Mention the ingredient without & the first time";
+const SUM_HINT: &str = "Give every mention the same kind of amount, like {1|2} each time";
+const PER_SERVING_HINT: &str = "Give an amount for each of the servings, like {1|2} for 2|4";
+const DECIMAL_HINT: &str = "Set ParseOptions::separators to the locale of the recipe";
+const AMOUNT_HINT: &str = "Amounts are numbers like 2, 1/2 or 1|2, followed by an optional %unit";
+const UNIT_HINT: &str = "Add a unit after a %, like {2%g}";
+const TIME_UNIT_HINT: &str = "Time units are like s, min, h or days";
+const RANGE_HINT: &str = "Write the lower end of a range first, like ~{1-2%hours}";
+const NAME_HINT: &str = "@, # and ~ have to be followed by a name, like @salt";
+
+fn syntax_error(pair: &Pair<Rule>, message: &str, hint: &str) -> ParseError {
+    let (line, column) = pair.as_span().start_pos().line_col();
+    ParseError {
+        diagnostic: Diagnostic::error(
+            pair.as_span().into(),
+            format!("{}: {}", message, pair.as_str()),
+        )
+        .with_hint(hint),
+        line,
+        column,
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parse;
+    use crate::units::UnitError;
+    use crate::{
+        instruction_parts, parse, parse_reader, parse_with, Amount, Comment, CommentKind,
+        DuplicateKeys, DuplicateMentions, Ingredient, IngredientSpecifier, InstructionPart, Item,
+        ParseError, ParseOptions, Quantity, QuantityError, ReadError, Recipe, Separators,
+        Severity, Span, Timer,
+    };
+    use indexmap::IndexMap;
     use std::fs::read_to_string;
+    use std::time::Duration;
 
     #[test]
     fn it_works() {
@@ -424,7 +1720,591 @@ Start the timer ~{10%minutes}\n\
 ",
         );
 
-        let _recipe = parse(&test_rec).unwrap();
+        let recipe = parse(&test_rec).unwrap();
+        let specifier = &recipe.metadata.ingredients_specifiers[0];
+        assert_eq!(specifier.note.as_deref(), Some("washed"));
+        assert_eq!(recipe.metadata.ingredients["fruit salat ananas"].notes, vec!["washed"]);
+    }
+
+    #[test]
+    fn parse_options() {
+        let recipe = parse("Mail me @ home // not @salt\n>> late: metadata\n").unwrap();
+        assert_eq!(recipe.instruction, "Mail me \\@ home \n>> late: metadata\n");
+        assert_eq!(recipe.metadata.entries["late"], "metadata");
+        assert!(recipe.metadata.ingredients.is_empty());
+        let parts: Vec<InstructionPart> = instruction_parts("a\\@@").collect();
+        assert_eq!(
+            parts,
+            vec![
+                InstructionPart::Text('a'),
+                InstructionPart::Text('@'),
+                InstructionPart::Ingredient
+            ]
+        );
+
+        let strict = ParseOptions::strict();
+        assert!(parse_with("Mail me @ home\n", &strict).is_err());
+        assert!(parse_with(">> no metadata\n", &strict).is_err());
+        assert!(parse_with("Add @salt{1}\n", &strict).is_err());
+        assert!(parse_with("Wait ~{10}\n", &strict).is_err());
+        assert!(parse_with("Add @salt{1%g} and wait ~{10%minutes}\n", &strict).is_ok());
+        assert!(parse(">> no metadata\n").is_ok());
+    }
+
+    #[test]
+    fn warnings() {
+        let source = ">> a: b\n>> a: c\nAdd @salt{a pinch}, @{1} and @rice{1|2*}\n";
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        let messages: Vec<&str> = output
+            .warnings
+            .iter()
+            .map(|warning| warning.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Duplicate metadata key a, the last value is used",
+                "Unparseable amount is treated as text",
+                "Empty ingredient name, treated as text",
+                "Unparseable amount is treated as text",
+            ]
+        );
+        assert!(output
+            .warnings
+            .iter()
+            .all(|warning| warning.severity == Severity::Warning));
+        let span = output.warnings[1].span;
+        assert_eq!(&source[span.start..span.end], "{a pinch}");
+        assert_eq!(output.recipe.metadata.entries["a"], "c");
+        assert!(output.recipe.metadata.ingredients["salt"].amount.is_none());
+        assert!(output.recipe.metadata.ingredients["rice"].amount.is_none());
+        assert!(output
+            .recipe
+            .instruction
+            .ends_with("Add @{a pinch}, \\@{1} and @{1|2*}\n"));
+
+        let error = parse_with("Add @salt{a pinch}\n", &ParseOptions::strict()).unwrap_err();
+        assert_eq!(error.diagnostic.severity, Severity::Error);
+        assert_eq!((error.line, error.column), (1, 5));
+
+        let recover = ParseOptions {
+            recover: true,
+            ..ParseOptions::strict()
+        };
+        let output = parse_with("Add @salt{1}, @pepper and @ rice\nStir\n", &recover).unwrap();
+        assert!(output.has_errors());
+        assert_eq!(output.warnings.len(), 2);
+        assert_eq!(output.recipe.metadata.ingredients.len(), 2);
+        assert!(output.recipe.instruction.ends_with("\\@ rice\nStir\n"));
+        assert!(!parse_with("Stir\n", &recover).unwrap().has_errors());
+        ["@flour{1%g", "~{", ">>", "#", "@{}", "@x{%}", "---\nx: [\n---\n", "\\"]
+            .iter()
+            .for_each(|source| assert!(parse_with(source, &recover).is_ok(), "{}", source));
+
+        let recipe = parse("Wait ~{1/2%hour}\n").unwrap();
+        assert_eq!(recipe.metadata.timer[0].amount, 0.5);
+    }
+
+    #[test]
+    fn servings_labels() {
+        let mut recipe =
+            parse(">> servings: 2 people|4 people // for the family\nStir.\n").unwrap();
+        assert_eq!(recipe.metadata.servings, Some(vec![2, 4]));
+        assert_eq!(recipe.metadata.servings_labels, vec!["2 people", "4 people"]);
+        recipe.layout = None;
+        assert!(recipe
+            .to_cooklang()
+            .starts_with(">> servings: 2 people|4 people\n"));
+
+        let recipe = parse(">> servings: makes 12 cookies\n").unwrap();
+        assert_eq!(recipe.metadata.servings, Some(vec![12]));
+
+        let output = parse_with(">> servings: 2|a few\n", &ParseOptions::default()).unwrap();
+        assert_eq!(output.recipe.metadata.servings, Some(vec![2]));
+        let span = output.warnings[0].span;
+        assert_eq!((span.start, span.end), (15, 20));
+        assert!(parse_with(">> servings: a few\n", &ParseOptions::strict()).is_err());
+    }
+
+    #[test]
+    fn ingredient_references() {
+        let source =
+            "Boil @pasta water{2%l}, then add the reserved @&pasta water{200%ml} and @&salt\n";
+        let extensions = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let output = parse_with(source, &extensions).unwrap();
+        let metadata = &output.recipe.metadata;
+        let water = &metadata.ingredients["pasta water"];
+        assert!(matches!(water.amount, Some(Amount::Single(d)) if d == 2.0));
+        assert_eq!(water.quantities.quantities.len(), 1);
+        assert!(metadata.ingredients_specifiers[1].reference);
+        assert!(!metadata.ingredients_specifiers[2].reference);
+        assert_eq!(output.warnings.len(), 1);
+        assert!(output.recipe.to_cooklang().contains("@&pasta water{200"));
+        let strict = ParseOptions {
+            extensions: true,
+            ..ParseOptions::strict()
+        };
+        let error = parse_with("Add @&salt{1%g}\n", &strict).unwrap_err();
+        assert!(error.diagnostic.message.starts_with("Reference to an ingredient"));
+
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        let water = &output.recipe.metadata.ingredients["pasta water"];
+        assert_eq!(water.quantities.quantities.len(), 2);
+        assert_eq!(output.warnings.len(), 2);
+        assert!(parse_with(source, &ParseOptions::strict()).is_err());
+    }
+
+    #[test]
+    fn ingredient_names() {
+        let source = "Add @salt, @pepper. Top with @sun-dried tomatoes and @jalapeño; \
+                      stir in @crème fraîche{} and @half-and-half-.\nServe with @pita_bread!\n";
+        let recipe = parse(source).unwrap();
+        let names: Vec<&str> = recipe
+            .metadata
+            .ingredients_specifiers
+            .iter()
+            .map(|specifier| specifier.ingredient.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "salt",
+                "pepper",
+                "sun-dried",
+                "jalapeño",
+                "crème fraîche",
+                "half-and-half",
+                "pita_bread"
+            ]
+        );
+        let texts: String = recipe.steps()[0]
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            "Add , . Top with  tomatoes and ; stir in  and -. Serve with !"
+        );
+    }
+
+    #[test]
+    fn escaped_sigils() {
+        let source =
+            "Mail me\\@example.com about \\#1 and \\~5 min.\n> Ask \\@chef.\nAdd @salt.\n";
+        let recipe = parse_with(source, &ParseOptions::strict()).unwrap().recipe;
+        assert_eq!(recipe.metadata.ingredients.len(), 1);
+        assert!(recipe.metadata.cookware.is_empty());
+        assert!(recipe.metadata.timer.is_empty());
+        assert_eq!(
+            recipe.steps()[0].items[0],
+            Item::Text("Mail me@example.com about #1 and ~5 min.".to_string())
+        );
+        assert_eq!(recipe.steps()[0].notes().next(), Some("Ask @chef."));
+        assert_eq!(recipe.to_cooklang(), source);
+    }
+
+    #[test]
+    fn repeated_text() {
+        let source = "== @eggs ==\n@eggs{2} // @eggs\n\n// @eggs\nBeat the @eggs.\n";
+        let recipe = parse(source).unwrap();
+        assert_eq!(recipe.instruction, "== \\@eggs ==\n@ \n\n\nBeat the @.\n");
+        assert_eq!(recipe.steps.len(), 2);
+        assert_eq!(recipe.sections[0].name.as_deref(), Some("@eggs"));
+    }
+
+    #[test]
+    fn parse_from_reader() {
+        let source = "Boil @water{1%l} in a #pot.\n";
+        let recipe = parse_reader(source.as_bytes()).unwrap();
+        assert_eq!(recipe, parse(source).unwrap());
+        let invalid = parse_reader(&[0xff, 0xfe][..]);
+        assert!(matches!(invalid, Err(ReadError::Io(_))));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn parse_from_async_reader() {
+        let source = "Boil @water{1%l} in a #pot.\n";
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let recipe = runtime
+            .block_on(crate::parse_async(source.as_bytes()))
+            .unwrap();
+        assert_eq!(recipe, parse(source).unwrap());
+    }
+
+    #[test]
+    fn mixed_units() {
+        let recipe = parse("Add @milk{1%cup}, then @milk{2%tbsp} and @milk\n").unwrap();
+        let milk = &recipe.metadata.ingredients["milk"];
+        assert_eq!(milk.unit.as_deref(), Some("cup"));
+        assert!(matches!(milk.amount, Some(Amount::Single(d)) if d == 1.0));
+        assert_eq!(milk.quantities.quantities.len(), 2);
+        assert_eq!(milk.quantities.units(), vec![Some("cup"), Some("tbsp")]);
+        let quantities: Vec<Option<String>> = recipe
+            .metadata
+            .ingredients_specifiers
+            .iter()
+            .map(|specifier| specifier.quantity().map(|quantity| quantity.to_string()))
+            .collect();
+        assert_eq!(
+            quantities,
+            vec![Some("1 cup".to_string()), Some("2 tbsp".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn ingredient_total() {
+        let recipe = parse("Add @flour{300%g}, @flour{2%tbsp}, @flour{1*%g} and @flour{50%g}\n");
+        let flour = &recipe.unwrap().metadata.ingredients["flour"];
+        let total: Vec<String> = flour
+            .total()
+            .quantities
+            .iter()
+            .map(Quantity::to_string)
+            .collect();
+        assert_eq!(total, vec!["350 g", "2 tbsp", "1 per serving g"]);
+        assert_eq!(flour.amount, Some(Amount::Single(350.0)));
+        assert_eq!(flour.quantities.quantities.len(), 4);
+
+        let source = ">> servings: 2|4\nBeat @eggs{1|2}, then @eggs{3}.\n";
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        assert_eq!(output.warnings.len(), 1);
+        let message = &output.warnings[0].message;
+        assert!(message.starts_with("Amounts of eggs can't be added"));
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let error = parse_with(source, &strict).unwrap_err();
+        assert_eq!(error.diagnostic.message, *message);
+        let source = "Beat @eggs{1}, then @eggs{3}.\n";
+        assert!(parse_with(source, &ParseOptions::default()).unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn duplicate_mentions() {
+        let source = "Mix @flour, @flour{300%g} and @salt.\n\nDust with @flour{20%g}.\n";
+        let parse = |duplicates| {
+            let options = ParseOptions {
+                duplicates,
+                ..ParseOptions::default()
+            };
+            parse_with(source, &options).unwrap().recipe.metadata.ingredients
+        };
+        let amounts = |ingredients: &IndexMap<String, Ingredient>| -> Vec<(String, String)> {
+            ingredients
+                .iter()
+                .map(|(key, ingredient)| {
+                    let total = ingredient.total().quantities;
+                    let total: Vec<String> = total.iter().map(Quantity::to_string).collect();
+                    (key.clone(), total.join(" + "))
+                })
+                .collect()
+        };
+        let pair = |key: &str, amount: &str| (key.to_string(), amount.to_string());
+        assert_eq!(
+            amounts(&parse(DuplicateMentions::Sum)),
+            vec![pair("flour", "320 g"), pair("salt", "")]
+        );
+        assert_eq!(
+            amounts(&parse(DuplicateMentions::FirstWins)),
+            vec![pair("flour", "300 g"), pair("salt", "")]
+        );
+        let separate = parse(DuplicateMentions::Separate);
+        assert_eq!(
+            amounts(&separate),
+            vec![
+                pair("flour", ""),
+                pair("flour (2)", "300 g"),
+                pair("salt", ""),
+                pair("flour (3)", "20 g")
+            ]
+        );
+        assert_eq!(separate["flour (3)"].name, "flour");
+        let options = ParseOptions {
+            duplicates: DuplicateMentions::Separate,
+            ..ParseOptions::default()
+        };
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        let specifiers = recipe.metadata.ingredients_specifiers.iter();
+        let keys: Vec<&str> = specifiers.map(IngredientSpecifier::ingredient_key).collect();
+        assert_eq!(keys, vec!["flour", "flour (2)", "salt", "flour (3)"]);
+    }
+
+    #[test]
+    fn amount_arithmetic() {
+        let sum = Amount::PerServing(vec![1.0, 2.0]).try_add(&Amount::PerServing(vec![1.0, 1.0]));
+        assert!(matches!(sum, Ok(Amount::PerServing(dd)) if dd == vec![2.0, 3.0]));
+        assert_eq!(
+            Amount::Single(1.0).try_add(&Amount::Multi(1.0)).unwrap_err(),
+            QuantityError::IncompatibleAmounts
+        );
+        assert!(Amount::PerServing(vec![1.0])
+            .checked_add(&Amount::PerServing(vec![1.0, 2.0]))
+            .is_none());
+
+        let recipe = parse("Add @sugar{1*%g} and @sugar{2%g}\n").unwrap();
+        let sugar = &recipe.metadata.ingredients["sugar"];
+        assert!(matches!(sugar.amount, Some(Amount::Multi(d)) if d == 1.0));
+        assert_eq!(sugar.quantities.quantities.len(), 2);
+
+        assert!(Amount::Single(0.1 + 0.2).approx_eq(&Amount::Single(0.3)));
+        assert!(!Amount::Single(0.3).approx_eq(&Amount::Single(0.31)));
+        assert!(!Amount::Single(1.0).approx_eq(&Amount::Multi(1.0)));
+        #[cfg(feature = "serde")]
+        {
+            let old: Amount = serde_json::from_str(r#"{"Servings": [1, 2]}"#).unwrap();
+            assert_eq!(old, Amount::PerServing(vec![1.0, 2.0]));
+        }
+        let servings = Amount::PerServing(vec![1.0, 1e12]);
+        assert!(servings.approx_eq(&Amount::PerServing(vec![1.0, 1e12 + 1e-3])));
+    }
+
+    #[test]
+    fn resolve_amounts() {
+        let recipe = parse(
+            ">> servings: 2|4|8\nAdd @salt{1%pinch}, @flour{1/2*%kg} and @eggs{1|2|4} and \
+             @milk{1/4|1/2|1%l}.\n",
+        )
+        .unwrap();
+        let amount = |name: &str| recipe.metadata.ingredients[name].amount.clone().unwrap();
+        assert_eq!(amount("salt").resolve(2, 8), Ok(1.0));
+        assert_eq!(amount("flour").resolve(1, 4), Ok(2.0));
+        assert_eq!(amount("eggs").resolve(1, 4), Ok(2.0));
+        assert_eq!(amount("milk").resolve(0, 2), Ok(0.25));
+        assert_eq!(amount("milk").resolve(2, 8), Ok(1.0));
+        assert_eq!(
+            amount("eggs").resolve(3, 16),
+            Err(QuantityError::ServingOutOfRange(3, 3))
+        );
+
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let output = parse_with("Add @eggs{1|2*} and @flour{1/0%kg}.\n", &options).unwrap();
+        let ingredients = &output.recipe.metadata.ingredients;
+        assert!(ingredients.values().all(|ingredient| ingredient.amount.is_none()));
+        assert_eq!(output.warnings.len(), 2);
+    }
+
+    #[test]
+    fn locale_separators() {
+        let recipe = parse("Add @milk{1.5%l} and @flour{1/2%kg}.\n").unwrap();
+        let milk = &recipe.metadata.ingredients["milk"];
+        assert_eq!(milk.amount, Some(Amount::Single(1.5)));
+
+        let source = "---\ntags: [vegan, quick]\n---\nAdd @milk{1,5%l} for ~{1,5%hours}.\n";
+        let german = ParseOptions {
+            separators: Separators::for_locale("de-AT"),
+            ..ParseOptions::lenient()
+        };
+        let output = parse_with(source, &german).unwrap();
+        assert!(output.warnings.is_empty());
+        let metadata = &output.recipe.metadata;
+        assert_eq!(metadata.ingredients["milk"].amount, Some(Amount::Single(1.5)));
+        assert_eq!(metadata.timer[0].amount, 1.5);
+        assert_eq!(metadata.get("tags"), Some("vegan; quick"));
+        assert_eq!(metadata.list_separator(), ';');
+        assert_eq!(metadata.tags(), vec!["vegan", "quick"]);
+
+        let output = parse_with(source, &ParseOptions::lenient()).unwrap();
+        assert_eq!(output.warnings.len(), 2);
+        assert_eq!(output.recipe.metadata.ingredients["milk"].amount, None);
+        assert_eq!(output.recipe.metadata.timer[0].amount, 0.0);
+        assert!(output.warnings[1].message.ends_with("the amount is ignored"));
+        assert_eq!(output.recipe.metadata.tags(), vec!["vegan", "quick"]);
+        assert!(parse_with(source, &ParseOptions::strict()).is_err());
+    }
+
+    #[test]
+    fn per_serving_amounts() {
+        let source = ">> servings: 2|4|8\nAdd @milk{1|2%l}.\n";
+        let error = parse_with(source, &ParseOptions::strict()).unwrap_err();
+        assert_eq!(error.diagnostic.span, crate::Span::new(28, 35));
+        assert_eq!(
+            error.diagnostic.message,
+            "2 amounts for 3 servings"
+        );
+        let output = parse_with(source, &ParseOptions::lenient()).unwrap();
+        assert_eq!(output.warnings.len(), 1);
+        let milk = &output.recipe.metadata.ingredients["milk"];
+        assert_eq!(milk.amount, Some(Amount::PerServing(vec![1.0, 2.0])));
+
+        let recipe = parse("---\nservings: 2|4\n---\nAdd @eggs{1|2}.\n").unwrap();
+        assert_eq!(Amount::Single(1.0).check_servings(2), Ok(()));
+        assert_eq!(
+            recipe.metadata.ingredients["eggs"].amount.as_ref().unwrap().check_servings(3),
+            Err(QuantityError::ServingsMismatch(2, 3))
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let recipe: Recipe = "Boil @water{1%l}.\n".parse().unwrap();
+        assert_eq!(recipe, parse("Boil @water{1%l}.\n").unwrap());
+        let recipe: Result<Recipe, ParseError> = "Mail me @ home\n".parse();
+        assert!(recipe.unwrap().metadata.ingredients.is_empty());
+    }
+
+    #[test]
+    fn clone_and_compare() {
+        let recipe = parse(">> servings: 2\nBoil @water{1%l} for ~{5%min}.\n").unwrap();
+        assert_eq!(recipe.clone(), recipe);
+        assert_ne!(parse("Boil @water{2%l} for ~{5%min}.\n").unwrap(), recipe);
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn ingredient_ids() {
+        use crate::Ingredient;
+        let source = "Mix @flour{100%g} and @sugar.\n";
+        let (first, second) = (parse(source).unwrap(), parse(source).unwrap());
+        let ids = |recipe: &crate::Recipe| -> Vec<_> {
+            recipe.metadata.ingredients.values().map(|i| i.id).collect()
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert_ne!(ids(&first)[0], ids(&first)[1]);
+        assert_eq!(first.metadata.ingredients["flour"].id, Ingredient::id_for("flour"));
+    }
+
+    #[test]
+    fn timer_duration() {
+        let recipe =
+            parse("Wait ~{1/2%hour}, ~{90%Sek}, ~{2%Minuten} and ~{3%weeks}.\n").unwrap();
+        let durations: Vec<_> = recipe.metadata.timer.iter().map(|t| t.duration()).collect();
+        assert_eq!(
+            durations,
+            vec![
+                Ok(Duration::from_secs(1800)),
+                Ok(Duration::from_secs(90)),
+                Ok(Duration::from_secs(120)),
+                Err(UnitError::UnknownUnit("weeks".to_string())),
+            ]
+        );
+        let recipe = parse("Wait ~{99999999999999999999999%days}.\n").unwrap();
+        let duration = recipe.metadata.timer[0].duration();
+        assert!(matches!(duration, Err(UnitError::InvalidValue(_))));
+        assert_eq!(recipe.estimated_duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn timer_ranges() {
+        let source = "Proof for ~{1-2%hours}, bake for ~{1%hour 30%minutes} or ~{1h30m}, rest \
+                      ~{1h 5-10min}.\n";
+        let recipe = parse(source).unwrap();
+        let timers: Vec<String> = recipe.metadata.timer.iter().map(Timer::to_string).collect();
+        assert_eq!(
+            timers,
+            vec!["1-2 hours", "90 minutes", "90 m", "65-70 min"]
+        );
+        let proof = &recipe.metadata.timer[0];
+        assert_eq!(proof.duration(), Ok(Duration::from_secs(3600)));
+        assert_eq!(proof.max_duration(), Ok(Duration::from_secs(7200)));
+        assert_eq!(recipe.to_cooklang(), source);
+        let recipe = Recipe {
+            layout: None,
+            ..recipe
+        };
+        assert!(recipe
+            .to_cooklang()
+            .starts_with("Proof for ~{1-2%hours}, bake for ~{90%minutes}"));
+
+        let source = "Proof for ~{2-1%hours}.\n";
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        assert_eq!(output.recipe.metadata.timer[0].to_string(), "2 hours");
+        assert!(output.warnings[0].message.ends_with("the range is ignored"));
+        assert!(parse_with(source, &ParseOptions::strict()).is_err());
+
+        let source = "Bake ~{1%hour 3%moons}.\n";
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        assert_eq!(output.recipe.metadata.timer[0].to_string(), "1 hour");
+        assert_eq!(output.warnings.len(), 1);
+        assert!(parse_with(source, &ParseOptions::strict()).is_err());
+    }
+
+    #[test]
+    fn metadata_values() {
+        let source = "\
+>> source: https://example.com/recipe?id=1 // from the web
+>> time: 1:30
+>> serve: at 12:00 sharp  //lunch
+Boil the @eggs{2}.
+";
+        let recipe = parse(source).unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.get("source"), Some("https://example.com/recipe?id=1"));
+        assert_eq!(metadata.get("time"), Some("1:30"));
+        assert_eq!(metadata.get("serve"), Some("at 12:00 sharp"));
+        assert_eq!(recipe.comments.len(), 2);
+        assert_eq!(recipe.steps.len(), 1);
+        let written = Recipe {
+            layout: None,
+            ..recipe
+        }
+        .to_cooklang();
+        assert!(written.contains(">> source: https://example.com/recipe?id=1\n"));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let source = ">> tags: vegan\n>> tags: quick\n>> servings: 2\n>> servings: 4\nMix.\n";
+        let parse = |duplicate_keys| {
+            let options = ParseOptions {
+                duplicate_keys,
+                ..ParseOptions::default()
+            };
+            parse_with(source, &options)
+        };
+        let last = parse(DuplicateKeys::Last).unwrap();
+        assert_eq!(last.recipe.metadata.entries.get("tags"), Some("quick"));
+        assert_eq!(last.recipe.metadata.entries.len(), 2);
+        assert_eq!(last.warnings.len(), 2);
+
+        let concatenated = parse(DuplicateKeys::Concatenate).unwrap();
+        let entries = &concatenated.recipe.metadata.entries;
+        assert_eq!(entries.get_all("tags").collect::<Vec<_>>(), vec!["vegan, quick"]);
+        assert_eq!(concatenated.recipe.metadata.servings, Some(vec![4]));
+        assert_eq!(concatenated.warnings.len(), 1);
+
+        let error = parse(DuplicateKeys::Error).unwrap_err();
+        assert!(error.diagnostic.message.starts_with("Duplicate metadata key tags"));
+    }
+
+    #[test]
+    fn comments() {
+        let source = "\
+// Family recipe
+>> servings: 2 // or 3
+Add @milk{1%l} [- TODO: try oat milk -] and stir.
+";
+        let recipe = parse(source).unwrap();
+        let comment = |text: &str, kind| Comment {
+            text: text.to_string(),
+            span: Span::new(source.find(text).unwrap() - 3, 0),
+            kind,
+        };
+        let mut expected = vec![
+            comment("Family recipe", CommentKind::Line),
+            comment("or 3", CommentKind::Line),
+            comment("TODO: try oat milk", CommentKind::Block),
+        ];
+        expected[0].span.end = 16;
+        expected[1].span.end = source.find("or 3").unwrap() + 4;
+        expected[2].span.end = source.find(" and stir").unwrap();
+        assert_eq!(recipe.comments, expected);
+        assert_eq!(recipe.steps.len(), 1);
+        assert!(!recipe.instruction.contains("TODO"));
+        assert!(parse("Mix [-the @flour.\n").unwrap().comments.is_empty());
     }
 
     #[test]
@@ -0,0 +1,399 @@
+//! Linting of parsed recipes.
+//!
+//! A [Linter] runs [LintRule]s over a [Recipe] and collects their [Diagnostic]s, sorted by their
+//! position in the source. The built-in rules are enabled by default and report warnings. Rules
+//! are configured by their name:
+//!
+//! ```
+//! use cook_with_rust_parser::diagnostics::Severity;
+//! use cook_with_rust_parser::lint::Linter;
+//! use cook_with_rust_parser::parse;
+//!
+//! let recipe = parse("Season with @salt and bake for ~{20}.\n").unwrap();
+//! let linter = Linter::default()
+//!     .allow("missing-quantity")
+//!     .set_severity("timer-without-unit", Severity::Error);
+//! let diagnostics = linter.lint(&recipe);
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(diagnostics[0].severity, Severity::Error);
+//! ```
+
+use crate::diagnostics::{Diagnostic, Severity, Span};
+use crate::metadata::normalize_key;
+use crate::{Amount, CookParser, Recipe, Rule};
+use pest::iterators::Pair;
+use pest::Parser;
+use std::collections::HashMap;
+
+/// A check of a recipe.
+pub trait LintRule {
+    /// Name of the rule, like `missing-quantity`, to configure it in the [Linter].
+    fn name(&self) -> &str;
+
+    /// Issues found in the recipe.
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic>;
+}
+
+/// The recipe to check, with the positions of its parts in the source.
+#[derive(Debug, Clone)]
+pub struct LintContext<'a> {
+    /// The recipe.
+    pub recipe: &'a Recipe,
+    /// Spans of the ingredients, in the order of
+    /// [Metadata::ingredients_specifiers](crate::Metadata::ingredients_specifiers).
+    pub ingredients: Vec<Span>,
+    /// Spans of the cookware, in the order of [Metadata::cookware](crate::Metadata::cookware).
+    pub cookware: Vec<Span>,
+    /// Spans of the timers, in the order of [Metadata::timer](crate::Metadata::timer).
+    pub timers: Vec<Span>,
+    /// Metadata keys of the front matter and the `>>` lines, in the order of the source.
+    pub metadata: Vec<(&'a str, Span)>,
+}
+
+impl<'a> LintContext<'a> {
+    /// Find the parts of the recipe in its [source](Recipe::source).
+    pub fn new(recipe: &'a Recipe) -> LintContext<'a> {
+        let mut context = LintContext {
+            recipe,
+            ingredients: vec![],
+            cookware: vec![],
+            timers: vec![],
+            metadata: vec![],
+        };
+        let cook_lang = match CookParser::parse(Rule::cook_lang, &recipe.source) {
+            Ok(mut pairs) => pairs.next().unwrap(),
+            Err(_) => return context,
+        };
+        cook_lang
+            .into_inner()
+            .for_each(|pair| match pair.as_rule() {
+                Rule::front_matter => {
+                    let body = pair.into_inner().next().unwrap();
+                    let mut offset = body.as_span().start();
+                    body.as_str().split_inclusive('\n').for_each(|line| {
+                        let indented = line.starts_with([' ', '\t', '-', '#']);
+                        if let Some((key, _)) = line.split_once(':').filter(|_| !indented) {
+                            let key = key.trim();
+                            context
+                                .metadata
+                                .push((key, Span::new(offset, offset + line.trim_end().len())));
+                        }
+                        offset += line.len();
+                    });
+                }
+                Rule::metadata => {
+                    let span = pair.as_span().into();
+                    let property = pair.into_inner().next().unwrap();
+                    let key = match property.as_rule() {
+                        Rule::servings => "servings",
+                        _ => property.into_inner().next().unwrap().as_str(),
+                    };
+                    context.metadata.push((key, span));
+                }
+                Rule::line => pair.into_inner().for_each(|part| context.add_part(part)),
+                _ => {}
+            });
+        context
+    }
+
+    fn add_part(&mut self, part: Pair<'_, Rule>) {
+        let span = part.as_span().into();
+        match part.as_rule() {
+            Rule::ingredient => self.ingredients.push(span),
+            Rule::cookware => self.cookware.push(span),
+            Rule::timer => self.timers.push(span),
+            _ => {}
+        }
+    }
+
+    /// Span of the last metadata key, compared like in [Metadata::get](crate::Metadata::get).
+    pub fn metadata_span(&self, key: &str) -> Option<Span> {
+        let key = normalize_key(key);
+        self.metadata
+            .iter()
+            .rev()
+            .find(|(k, _)| normalize_key(k) == key)
+            .map(|(_, span)| *span)
+    }
+}
+
+/// Runs [LintRule]s over recipes.
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+    /// Severities by rule name. [None] turns the rule off.
+    levels: HashMap<String, Option<Severity>>,
+}
+
+impl Default for Linter {
+    /// A linter with all built-in rules.
+    fn default() -> Self {
+        Linter::new()
+            .add_rule(UnusedCookware)
+            .add_rule(MissingQuantity)
+            .add_rule(TimerWithoutUnit)
+            .add_rule(DuplicateMetadata)
+            .add_rule(IngredientOnlyInMetadata)
+    }
+}
+
+impl Linter {
+    /// Create a linter without any rules.
+    pub fn new() -> Linter {
+        Linter {
+            rules: vec![],
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Register a rule.
+    pub fn add_rule(mut self, rule: impl LintRule + 'static) -> Linter {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Turn the rule off.
+    pub fn allow(mut self, rule: &str) -> Linter {
+        self.levels.insert(rule.to_string(), None);
+        self
+    }
+
+    /// Report the issues found by the rule with the severity.
+    pub fn set_severity(mut self, rule: &str, severity: Severity) -> Linter {
+        self.levels.insert(rule.to_string(), Some(severity));
+        self
+    }
+
+    /// Names of the registered rules.
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name())
+    }
+
+    /// Issues found by the rules which are turned on, sorted by their position.
+    pub fn lint(&self, recipe: &Recipe) -> Vec<Diagnostic> {
+        let context = LintContext::new(recipe);
+        let mut diagnostics = vec![];
+        self.rules.iter().for_each(|rule| {
+            let severity = match self.levels.get(rule.name()) {
+                Some(None) => return,
+                Some(severity) => *severity,
+                None => None,
+            };
+            diagnostics.extend(rule.check(&context).into_iter().map(|mut diagnostic| {
+                diagnostic.severity = severity.unwrap_or(diagnostic.severity);
+                diagnostic
+            }));
+        });
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        diagnostics
+    }
+}
+
+/// `unused-cookware`: cookware listed in the `cookware` metadata, but never used in a step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnusedCookware;
+
+impl LintRule for UnusedCookware {
+    fn name(&self) -> &str {
+        "unused-cookware"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let used = &context.recipe.metadata.cookware;
+        unmentioned(context, "cookware", used.iter().map(String::as_str))
+            .map(|(name, span)| {
+                Diagnostic::warning(span, format!("Cookware {} is never used", name))
+                    .with_hint(format!("Mention it in a step, like #{}", name))
+            })
+            .collect()
+    }
+}
+
+/// `missing-quantity`: an ingredient mentioned without an amount.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MissingQuantity;
+
+impl LintRule for MissingQuantity {
+    fn name(&self) -> &str {
+        "missing-quantity"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let specifiers = &context.recipe.metadata.ingredients_specifiers;
+        specifiers
+            .iter()
+            .zip(&context.ingredients)
+            .filter(|(specifier, _)| !specifier.reference && !specifier.recipe)
+            .filter(|(specifier, _)| specifier.amount_in_step == Amount::Single(0.0))
+            .map(|(specifier, span)| {
+                let message = format!("Ingredient {} has no quantity", specifier.ingredient);
+                Diagnostic::warning(*span, message).with_hint("Add an amount, like @salt{1%tsp}")
+            })
+            .collect()
+    }
+}
+
+/// `timer-without-unit`: a timer like `~{10}`, which doesn't say how long it runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimerWithoutUnit;
+
+impl LintRule for TimerWithoutUnit {
+    fn name(&self) -> &str {
+        "timer-without-unit"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let timers = &context.recipe.metadata.timer;
+        timers
+            .iter()
+            .zip(&context.timers)
+            .filter(|(timer, _)| timer.unit.is_empty())
+            .map(|(_, span)| {
+                Diagnostic::warning(*span, "Timer has no unit")
+                    .with_hint("Add a unit, like ~{10%minutes}")
+            })
+            .collect()
+    }
+}
+
+/// `duplicate-metadata`: a metadata key which is set more than once, so all but the last value
+/// are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DuplicateMetadata;
+
+impl LintRule for DuplicateMetadata {
+    fn name(&self) -> &str {
+        "duplicate-metadata"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let mut seen = vec![];
+        let mut diagnostics = vec![];
+        context.metadata.iter().for_each(|(key, span)| {
+            let normalized = normalize_key(key);
+            if seen.contains(&normalized) {
+                let message = format!("Duplicate metadata key {}", key);
+                diagnostics.push(
+                    Diagnostic::warning(*span, message)
+                        .with_hint("Remove the key, only the last value is used"),
+                );
+            } else {
+                seen.push(normalized);
+            }
+        });
+        diagnostics
+    }
+}
+
+/// `ingredient-only-in-metadata`: an ingredient listed in the `ingredients` metadata, but never
+/// mentioned in a step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngredientOnlyInMetadata;
+
+impl LintRule for IngredientOnlyInMetadata {
+    fn name(&self) -> &str {
+        "ingredient-only-in-metadata"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let mentioned = context.recipe.metadata.ingredients.keys();
+        unmentioned(context, "ingredients", mentioned.map(String::as_str))
+            .map(|(name, span)| {
+                let message = format!("Ingredient {} is only listed in the metadata", name);
+                Diagnostic::warning(span, message)
+                    .with_hint(format!("Mention it in a step, like @{}{{}}", name))
+            })
+            .collect()
+    }
+}
+
+/// Names of the list in the metadata key, which are not part of `mentioned`.
+pub(crate) fn unmentioned<'a>(
+    context: &LintContext<'a>,
+    key: &str,
+    mentioned: impl Iterator<Item = &'a str>,
+) -> impl Iterator<Item = (&'a str, Span)> {
+    let mentioned: Vec<&str> = mentioned.collect();
+    let span = context.metadata_span(key).unwrap_or(Span::new(0, 0));
+    let listed = context.recipe.metadata.list(key);
+    listed
+        .into_iter()
+        .filter(move |name| {
+            !mentioned
+                .iter()
+                .any(|mentioned| mentioned.eq_ignore_ascii_case(name))
+        })
+        .map(move |name| (name, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{Diagnostic, Severity, Span};
+    use crate::lint::{LintContext, LintRule, Linter};
+    use crate::parse;
+
+    #[test]
+    fn built_in_rules() {
+        let source = "\
+---
+cookware: pot, whisk
+ingredients: eggs, Milk, sugar
+---
+>> source: grandma
+>> Source: aunt
+Whisk the @eggs{2} with @milk in a #pot for ~{2}.
+";
+        let recipe = parse(source).unwrap();
+        let diagnostics = Linter::default().lint(&recipe);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Cookware whisk is never used",
+                "Ingredient sugar is only listed in the metadata",
+                "Duplicate metadata key Source",
+                "Ingredient milk has no quantity",
+                "Timer has no unit",
+            ]
+        );
+        assert_eq!(
+            &source[diagnostics[3].span.start..diagnostics[3].span.end],
+            "@milk"
+        );
+        assert_eq!(
+            &source[diagnostics[4].span.start..diagnostics[4].span.end],
+            "~{2}"
+        );
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+
+        let linter = Linter::default()
+            .allow("duplicate-metadata")
+            .set_severity("timer-without-unit", Severity::Error);
+        let diagnostics = linter.lint(&recipe);
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics[3].severity, Severity::Error);
+    }
+
+    #[test]
+    fn custom_rule() {
+        struct NoTitle;
+
+        impl LintRule for NoTitle {
+            fn name(&self) -> &str {
+                "no-title"
+            }
+
+            fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+                match context.recipe.metadata.title() {
+                    Some(_) => vec![],
+                    None => vec![Diagnostic::warning(Span::new(0, 0), "Recipe has no title")],
+                }
+            }
+        }
+
+        let linter = Linter::new().add_rule(NoTitle);
+        assert_eq!(linter.rules().collect::<Vec<_>>(), vec!["no-title"]);
+        assert_eq!(linter.lint(&parse("Boil @water{1%l}.\n").unwrap()).len(), 1);
+        let recipe = parse("---\ntitle: Tea\n---\nBoil @water{1%l}.\n").unwrap();
+        assert!(linter.lint(&recipe).is_empty());
+    }
+}
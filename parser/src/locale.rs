@@ -0,0 +1,116 @@
+//! Localization of the [renderers](crate::render).
+//!
+//! A [Locale] names the headings and units of a rendered recipe and tells the direction of its
+//! language. All methods default to [English], so a translation only overrides what differs:
+//!
+//! ```
+//! use cook_with_rust_parser::locale::{Label, Locale};
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::render::{to_markdown_localized, RenderOptions};
+//!
+//! struct German;
+//!
+//! impl Locale for German {
+//!     fn language(&self) -> &str {
+//!         "de"
+//!     }
+//!
+//!     fn label(&self, label: Label) -> String {
+//!         match label {
+//!             Label::Ingredients => "Zutaten",
+//!             Label::Cookware => "Kochgeschirr",
+//!             Label::Steps => "Zubereitung",
+//!             Label::Optional => "optional",
+//!         }
+//!         .to_string()
+//!     }
+//!
+//!     fn unit(&self, unit: &str) -> String {
+//!         match unit {
+//!             "cup" => "Tasse".to_string(),
+//!             unit => unit.to_string(),
+//!         }
+//!     }
+//! }
+//!
+//! let recipe = parse("Add @milk{1%cup}.\n").unwrap();
+//! let markdown = to_markdown_localized(&recipe, &RenderOptions::default(), &German);
+//! assert!(markdown.starts_with("## Zutaten\n\n- 1 Tasse milk\n"));
+//! ```
+//!
+//! In a right-to-left language, amounts are isolated with the Unicode characters `U+2066` and
+//! `U+2069`, so `125 g` isn't reordered by the surrounding text.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A fixed text of the rendered recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Label {
+    /// Heading of the list of ingredients.
+    Ingredients,
+    /// Heading of the list of cookware.
+    Cookware,
+    /// Heading of the steps.
+    Steps,
+    /// Marks optional ingredients.
+    Optional,
+}
+
+/// Direction in which a language is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Direction {
+    /// Like English.
+    #[default]
+    LeftToRight,
+    /// Like Arabic or Hebrew.
+    RightToLeft,
+}
+
+/// Translations of the rendered recipe.
+pub trait Locale {
+    /// The language tag, like `en` or `pt-BR`.
+    fn language(&self) -> &str {
+        "en"
+    }
+
+    /// Direction of the language.
+    fn direction(&self) -> Direction {
+        Direction::LeftToRight
+    }
+
+    /// The text of the label.
+    fn label(&self, label: Label) -> String {
+        match label {
+            Label::Ingredients => "Ingredients",
+            Label::Cookware => "Cookware",
+            Label::Steps => "Steps",
+            Label::Optional => "optional",
+        }
+        .to_string()
+    }
+
+    /// Name of a unit of an ingredient or timer, as written in the recipe, like `g` or
+    /// `minutes`.
+    fn unit(&self, unit: &str) -> String {
+        unit.to_string()
+    }
+}
+
+/// The default locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct English;
+
+impl Locale for English {}
+
+/// The text isolated from the surrounding text in a right-to-left locale.
+pub(crate) fn isolate(locale: &dyn Locale, text: String) -> String {
+    match locale.direction() {
+        Direction::LeftToRight => text,
+        Direction::RightToLeft => format!("\u{2066}{}\u{2069}", text),
+    }
+}
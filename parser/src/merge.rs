@@ -0,0 +1,291 @@
+//! Three-way merge of two edited versions of a recipe.
+//!
+//! [merge] combines the changes of `ours` and `theirs` to a common `base`, like `git merge`, but
+//! on the structure of the recipe instead of its lines:
+//!
+//! * metadata is merged by key, so both sides can add different keys,
+//! * the steps are merged as a whole, separated by blank lines as written in the source.
+//!
+//! If both sides changed the same key or step differently, the merged source contains both
+//! versions between conflict markers, and the conflict is listed in [Merge::conflicts].
+//!
+//! ```text
+//! <<<<<<< ours
+//! Bake for ~{25%minutes}.
+//! =======
+//! Bake for ~{30%minutes}.
+//! >>>>>>> theirs
+//! ```
+//!
+//! The metadata of the merged source is written as `>>` lines, also if it was written in a front
+//! matter.
+
+use crate::diff::{common_subsequence, metadata};
+use crate::document::front_matter_end;
+use crate::{parse_with, ParseError, ParseOptions, ParseOutput, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Result of [merge].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Merge {
+    /// CookLang source of the merged recipe, with conflict markers if there are conflicts.
+    pub source: String,
+    /// Parts which have been changed differently on both sides, in order.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A part of the recipe which has been changed differently on both sides.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Conflict {
+    /// The part in the common ancestor, empty if it has been added.
+    pub base: String,
+    /// The part in our version, empty if it has been removed.
+    pub ours: String,
+    /// The part in their version, empty if it has been removed.
+    pub theirs: String,
+}
+
+impl Merge {
+    /// Whether there are no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Parse the merged source.
+    pub fn parse(&self, options: &ParseOptions) -> Result<ParseOutput, ParseError> {
+        parse_with(&self.source, options)
+    }
+}
+
+/// Merge the changes of our and their version to the common ancestor.
+pub fn merge(base: &Recipe, ours: &Recipe, theirs: &Recipe) -> Merge {
+    let mut merged = Merge {
+        source: String::new(),
+        conflicts: vec![],
+    };
+
+    let (base_metadata, our_metadata, their_metadata) =
+        (metadata(base), metadata(ours), metadata(theirs));
+    let mut keys: Vec<&String> = our_metadata.keys().collect();
+    their_metadata
+        .keys()
+        .filter(|key| !our_metadata.contains_key(*key))
+        .for_each(|key| keys.push(key));
+    let removed: Vec<&String> = base_metadata
+        .keys()
+        .filter(|key| !keys.contains(key))
+        .collect();
+    keys.extend(removed);
+    let line = |value: Option<&String>, key: &str| {
+        value.map_or(String::new(), |value| format!(">> {}: {}", key, value))
+    };
+    keys.iter().for_each(|key| {
+        let (base, ours, theirs) = (
+            line(base_metadata.get(*key), key),
+            line(our_metadata.get(*key), key),
+            line(their_metadata.get(*key), key),
+        );
+        merged.push(&base, &ours, &theirs, "\n");
+    });
+    if !merged.source.is_empty() {
+        merged.source.push('\n');
+    }
+
+    let (base, ours, theirs) = (blocks(base), blocks(ours), blocks(theirs));
+    let (base_ours, base_theirs) = (
+        common_subsequence(&base, &ours),
+        common_subsequence(&base, &theirs),
+    );
+    // Blocks unchanged on both sides split the merge into chunks, which are merged as a whole.
+    let stable = base_ours.iter().filter_map(|&(b, o)| {
+        let &(_, t) = base_theirs.iter().find(|(bt, _)| *bt == b)?;
+        Some((b, o, t))
+    });
+    let end = (base.len(), ours.len(), theirs.len());
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    for (stable_b, stable_o, stable_t) in stable.chain(Some(end)) {
+        if stable_o < o || stable_t < t {
+            continue;
+        }
+        let chunk = |blocks: &[String], start, end| blocks[start..end].join("\n\n");
+        merged.push(
+            &chunk(&base, b, stable_b),
+            &chunk(&ours, o, stable_o),
+            &chunk(&theirs, t, stable_t),
+            "\n\n",
+        );
+        if stable_b < base.len() {
+            merged.push(&base[stable_b], &base[stable_b], &base[stable_b], "\n\n");
+        }
+        b = stable_b + 1;
+        o = stable_o + 1;
+        t = stable_t + 1;
+    }
+    let trimmed = merged.source.trim_end().len();
+    merged.source.truncate(trimmed);
+    merged.source.push('\n');
+    merged
+}
+
+impl Merge {
+    /// Add the merged part, followed by the separator if it isn't empty.
+    fn push(&mut self, base: &str, ours: &str, theirs: &str, separator: &str) {
+        let merged = if ours == theirs || theirs == base {
+            ours
+        } else if ours == base {
+            theirs
+        } else {
+            self.source += &format!(
+                "<<<<<<< ours\n{}\n=======\n{}\n>>>>>>> theirs",
+                ours, theirs
+            );
+            self.source += separator;
+            self.conflicts.push(Conflict {
+                base: base.to_string(),
+                ours: ours.to_string(),
+                theirs: theirs.to_string(),
+            });
+            return;
+        };
+        if !merged.is_empty() {
+            self.source += merged;
+            self.source += separator;
+        }
+    }
+}
+
+/// The steps as written in the source, without the front matter and metadata lines.
+fn blocks(recipe: &Recipe) -> Vec<String> {
+    let lines = recipe.source[front_matter_end(&recipe.source)..].lines();
+    let mut blocks = vec![];
+    let mut block: Vec<&str> = vec![];
+    for line in lines {
+        if line.trim_start().starts_with(">>") {
+            continue;
+        }
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                blocks.push(block.join("\n"));
+                block.clear();
+            }
+            continue;
+        }
+        block.push(line.trim_end());
+    }
+    if !block.is_empty() {
+        blocks.push(block.join("\n"));
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merge::merge;
+    use crate::{parse, ParseOptions};
+
+    #[test]
+    fn three_way_merge() {
+        let base = parse(
+            "\
+>> servings: 2
+>> source: Grandma
+Mix @flour{200%g} and @eggs{2}.
+
+Rest for ~{10%minutes}.
+
+Bake for ~{25%minutes}.
+",
+        )
+        .unwrap();
+        let ours = parse(
+            "\
+>> servings: 4
+>> source: Grandma
+Mix @flour{400%g} and @eggs{4}.
+
+Rest for ~{10%minutes}.
+
+Bake for ~{25%minutes}.
+",
+        )
+        .unwrap();
+        let theirs = parse(
+            "\
+>> servings: 2
+>> course: dessert
+Mix @flour{200%g} and @eggs{2}.
+
+Rest for ~{10%minutes}.
+
+Bake for ~{25%minutes}.
+
+Dust with @sugar.
+",
+        )
+        .unwrap();
+        let merged = merge(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.source,
+            "\
+>> servings: 4
+>> course: dessert
+
+Mix @flour{400%g} and @eggs{4}.
+
+Rest for ~{10%minutes}.
+
+Bake for ~{25%minutes}.
+
+Dust with @sugar.
+"
+        );
+        let recipe = merged.parse(&ParseOptions::default()).unwrap().recipe;
+        assert_eq!(recipe.steps.len(), 4);
+
+        let conflicting = parse(
+            "\
+>> servings: 2
+>> source: Grandma
+Mix @flour{200%g} and @eggs{2}.
+
+Rest for ~{10%minutes}.
+
+Bake for ~{30%minutes}.
+",
+        )
+        .unwrap();
+        let edited = parse(
+            "\
+>> servings: 2
+>> source: Grandma
+Mix @flour{200%g} and @eggs{2}.
+
+Bake for ~{20%minutes}.
+",
+        )
+        .unwrap();
+        let merged = merge(&base, &conflicting, &edited);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(
+            merged.conflicts[0].base,
+            "Rest for ~{10%minutes}.\n\nBake for ~{25%minutes}."
+        );
+        assert!(merged.source.ends_with(
+            "\
+<<<<<<< ours
+Rest for ~{10%minutes}.
+
+Bake for ~{30%minutes}.
+=======
+Bake for ~{20%minutes}.
+>>>>>>> theirs
+"
+        ));
+    }
+}
@@ -0,0 +1,394 @@
+//! Metadata entries and typed access to well-known [Metadata] keys.
+//!
+//! For the typed getters, keys are compared case-insensitively, and spaces, `_` and `-` are
+//! treated as equal, so `prep time`, `Prep_Time` and `prep-time` are the same key.
+
+use crate::time::parse_duration;
+use crate::{Metadata, Recipe};
+#[cfg(feature = "serde")]
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::time::Duration;
+
+/// Metadata entries in the order of the source. A key can appear more than once.
+///
+/// Serialized as a list of key value pairs. A map is accepted when deserializing, to read
+/// recipes serialized before keys could be repeated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct MetadataMap {
+    entries: Vec<(String, String)>,
+}
+
+impl MetadataMap {
+    /// Create an empty map.
+    pub fn new() -> MetadataMap {
+        MetadataMap::default()
+    }
+
+    /// Append an entry. Existing entries with the same key are kept.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.entries.push((key.into(), value.into()));
+    }
+
+    /// Join the value to the last value of the key with the separator, like `vegan, quick`.
+    /// Appended like [MetadataMap::insert] if the key is missing.
+    pub(crate) fn concat(&mut self, key: &str, value: &str, separator: char) {
+        match self.entries.iter_mut().rev().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = format!("{}{} {}", existing, separator, value),
+            None => self.insert(key, value),
+        }
+    }
+
+    /// The last value of the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All values of the key in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the key has at least one value.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.iter().any(|(k, _)| k == key)
+    }
+
+    /// All entries in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Number of entries, counting repeated keys.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Index<&str> for MetadataMap {
+    type Output = String;
+
+    /// The last value of the key. Panics if the key is missing.
+    fn index(&self, key: &str) -> &String {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+            .expect("Missing metadata key")
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for MetadataMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = MetadataMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for MetadataMap {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter()
+            .for_each(|(key, value)| self.insert(key, value));
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MetadataMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            List(Vec<(String, String)>),
+            Map(IndexMap<String, String>),
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::List(entries) => MetadataMap { entries },
+            Repr::Map(map) => map.into_iter().collect(),
+        })
+    }
+}
+
+/// Where a recipe comes from, see [Metadata::source].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source<'a> {
+    /// A web address like `https://example.com/pancakes`.
+    Url(&'a str),
+    /// Anything else, like the name of a book.
+    Name(&'a str),
+}
+
+impl<'a> Source<'a> {
+    /// Parse the value of a `source` key.
+    pub fn parse(source: &'a str) -> Source<'a> {
+        let source = source.trim();
+        let is_url = match source.split_once("://") {
+            Some((scheme, rest)) => {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+                    && !rest.is_empty()
+                    && !source.contains(char::is_whitespace)
+            }
+            None => false,
+        };
+        if is_url {
+            Source::Url(source)
+        } else {
+            Source::Name(source)
+        }
+    }
+
+    /// Host of a [Source::Url], e.g. `example.com`.
+    pub fn host(&self) -> Option<&'a str> {
+        match self {
+            Source::Url(url) => {
+                let rest = url.split_once("://")?.1;
+                let authority = rest.split(['/', '?', '#']).next()?;
+                let host = authority.rsplit('@').next()?;
+                let host = match host.rsplit_once(':') {
+                    Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                    _ => host,
+                };
+                Some(host)
+            }
+            Source::Name(_) => None,
+        }
+    }
+}
+
+impl Metadata {
+    /// Last value of the key, which is compared as described in the [module](self)
+    /// documentation.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        self.entries
+            .iter()
+            .filter(|(k, _)| normalize_key(k) == key)
+            .map(|(_, value)| value)
+            .last()
+    }
+
+    /// The `title` of the recipe.
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    /// Separator of the values of lists like [Metadata::tags], the [list
+    /// separator](crate::Separators::list) of the [ParseOptions](crate::ParseOptions).
+    pub fn list_separator(&self) -> char {
+        self.list_separator
+    }
+
+    /// The values of the key, separated by the [list separator](Metadata::list_separator) or
+    /// `|`, like `vegan, quick` or `vegan|quick`. The raw value stays available with
+    /// [Metadata::get].
+    pub fn list(&self, key: &str) -> Vec<&str> {
+        self.get(key)
+            .map(|list| {
+                list.split([self.list_separator, '|'])
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The `tags` of the recipe, see [Metadata::list].
+    pub fn tags(&self) -> Vec<&str> {
+        self.list("tags")
+    }
+
+    /// Whether the [list](Metadata::list) of the key contains the value, compared
+    /// case-insensitively.
+    pub fn list_contains(&self, key: &str, value: &str) -> bool {
+        let value = value.trim();
+        self.list(key).iter().any(|v| v.eq_ignore_ascii_case(value))
+    }
+
+    /// Whether the recipe has the tag, compared case-insensitively.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.list_contains("tags", tag)
+    }
+
+    /// The `source` of the recipe.
+    pub fn source(&self) -> Option<Source<'_>> {
+        self.get("source").map(Source::parse)
+    }
+
+    /// Total time of the recipe from the `time` or `duration` key. If neither is set, the sum of
+    /// [Metadata::prep_time] and [Metadata::cook_time].
+    pub fn time(&self) -> Option<Duration> {
+        match self.get("time").or_else(|| self.get("duration")) {
+            Some(time) => parse_duration(time),
+            None => match (self.prep_time(), self.cook_time()) {
+                (Some(prep), Some(cook)) => Some(prep.saturating_add(cook)),
+                (prep, cook) => prep.or(cook),
+            },
+        }
+    }
+
+    /// The `prep time` of the recipe.
+    pub fn prep_time(&self) -> Option<Duration> {
+        self.get("prep time").and_then(parse_duration)
+    }
+
+    /// The `cook time` of the recipe.
+    pub fn cook_time(&self) -> Option<Duration> {
+        self.get("cook time").and_then(parse_duration)
+    }
+
+    /// The `course` of the recipe, e.g. `dessert`.
+    pub fn course(&self) -> Option<&str> {
+        self.get("course")
+    }
+
+    /// The `cuisine` of the recipe, e.g. `italian`.
+    pub fn cuisine(&self) -> Option<&str> {
+        self.get("cuisine")
+    }
+
+    /// The `difficulty` of the recipe, e.g. `easy`.
+    pub fn difficulty(&self) -> Option<&str> {
+        self.get("difficulty")
+    }
+}
+
+impl Recipe {
+    /// The [title](Metadata::title) of the recipe, or the name of its file without the extension
+    /// if it has been read with [parse_file](crate::parse_file).
+    pub fn title(&self) -> Option<&str> {
+        self.metadata.title().or_else(|| {
+            let path = self.path.as_ref()?;
+            path.file_stem()?.to_str()
+        })
+    }
+}
+
+pub(crate) fn normalize_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .map(|c| match c {
+            '_' | '-' => ' ',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::Source;
+    use crate::{parse, parse_file};
+    use std::time::Duration;
+
+    #[test]
+    fn well_known_keys() {
+        let recipe = parse(
+            "---\n\
+title: Pancakes\n\
+tags: breakfast, quick,\n\
+source: https://user@example.com:8080/pancakes?print=1\n\
+Prep_Time: 10 min\n\
+cook-time: 1/2 hour\n\
+---\n\
+>> cuisine: american\n\
+Mix @flour.\n",
+        )
+        .unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.title(), Some("Pancakes"));
+        assert_eq!(metadata.tags(), vec!["breakfast", "quick"]);
+        assert_eq!(
+            metadata.source().and_then(|s| s.host()),
+            Some("example.com")
+        );
+        assert_eq!(metadata.prep_time(), Some(Duration::from_secs(600)));
+        assert_eq!(metadata.time(), Some(Duration::from_secs(2400)));
+        assert_eq!(metadata.cuisine(), Some("american"));
+        assert_eq!(metadata.course(), None);
+        let recipe = parse(">> time: 99999999999999999999999 days\nMix @flour.\n").unwrap();
+        assert_eq!(recipe.metadata.time(), None);
+
+        assert_eq!(
+            Source::parse("Grandma's book"),
+            Source::Name("Grandma's book")
+        );
+        assert_eq!(
+            Source::parse("see https://example.com"),
+            Source::Name("see https://example.com")
+        );
+    }
+
+    #[test]
+    fn recipe_title() {
+        let path = std::env::temp_dir().join(format!("Tomato Soup {}.cook", std::process::id()));
+        std::fs::write(&path, "Blend @tomatoes{4}.\n").unwrap();
+        let mut recipe = parse_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let name = format!("Tomato Soup {}", std::process::id());
+        assert_eq!(recipe.title(), Some(name.as_str()));
+        recipe.metadata.entries.insert("title", "Soup");
+        assert_eq!(recipe.title(), Some("Soup"));
+        assert_eq!(parse("Blend @tomatoes{4}.\n").unwrap().title(), None);
+    }
+
+    #[test]
+    fn metadata_lists() {
+        let recipe =
+            parse(">> tags: vegan, Quick|dinner\n>> diet: low-carb\nMix @flour.\n").unwrap();
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.tags(), vec!["vegan", "Quick", "dinner"]);
+        assert_eq!(metadata.get("tags"), Some("vegan, Quick|dinner"));
+        assert!(metadata.has_tag(" quick"));
+        assert!(!metadata.has_tag("vegetarian"));
+        assert_eq!(metadata.list("diet"), vec!["low-carb"]);
+        assert!(metadata.list("cuisine").is_empty());
+    }
+
+    #[test]
+    fn metadata_map() {
+        let recipe = parse(">> tag: b\n>> author: me\n>> tag: a\n").unwrap();
+        let entries = &recipe.metadata.entries;
+        assert_eq!(
+            entries.iter().collect::<Vec<_>>(),
+            vec![("tag", "b"), ("author", "me"), ("tag", "a")]
+        );
+        assert_eq!(entries.get_all("tag").collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(entries["tag"], "a");
+        #[allow(deprecated)]
+        let ominous = recipe.metadata.ominous();
+        assert_eq!(ominous["tag"], "a");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_metadata_map() {
+        use crate::metadata::MetadataMap;
+        let map: MetadataMap = serde_yaml::from_str("{a: b, c: d}").unwrap();
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![("a", "b"), ("c", "d")]);
+        let map: MetadataMap = serde_yaml::from_str("[[a, b], [a, c]]").unwrap();
+        assert_eq!(map.len(), 2);
+    }
+}
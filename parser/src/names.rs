@@ -0,0 +1,170 @@
+//! Normalization of ingredient names, so `Onions`, `onion` and ` onion ` are the same ingredient.
+//!
+//! A [NameNormalizer] lowercases a name, collapses its whitespace, folds the plural of its last
+//! word and looks it up in a table of synonyms. It's used when aggregating the ingredients of a
+//! recipe with [ParseOptions::names](crate::ParseOptions::names) and when building a shopping
+//! list with [ShoppingList::add_recipe_with](crate::ShoppingList::add_recipe_with). The
+//! aggregated ingredient keeps the spelling of its first mention.
+//!
+//! ```
+//! use cook_with_rust_parser::names::NameNormalizer;
+//!
+//! let mut names = NameNormalizer::default();
+//! names.add_synonym("scallion", "green onion");
+//! assert_eq!(names.normalize("  Green   Onions"), "green onion");
+//! assert!(names.same("scallions", "green onion"));
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Rules to normalize ingredient names.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NameNormalizer {
+    /// Fold plurals, like `tomatoes` into `tomato`.
+    pub fold_plurals: bool,
+    /// Plurals which don't follow the rules, like `leaves`, by the lowercase plural, pointing to
+    /// the singular.
+    pub plurals: HashMap<String, String>,
+    /// Normalized names by a normalized synonym, like `green onion` for `scallion`.
+    pub synonyms: HashMap<String, String>,
+}
+
+impl Default for NameNormalizer {
+    /// Normalizer folding English plurals, without synonyms.
+    fn default() -> Self {
+        NameNormalizer {
+            fold_plurals: true,
+            plurals: [
+                ("leaves", "leaf"),
+                ("halves", "half"),
+                ("loaves", "loaf"),
+                ("geese", "goose"),
+                ("chives", "chives"),
+                ("molasses", "molasses"),
+            ]
+            .iter()
+            .map(|(plural, singular)| (plural.to_string(), singular.to_string()))
+            .collect(),
+            synonyms: HashMap::new(),
+        }
+    }
+}
+
+impl NameNormalizer {
+    /// Normalizer which only lowercases and trims, without folding plurals.
+    pub fn case_insensitive() -> NameNormalizer {
+        NameNormalizer {
+            fold_plurals: false,
+            plurals: HashMap::new(),
+            synonyms: HashMap::new(),
+        }
+    }
+
+    /// Treat the synonym as the name. Both are normalized, so `Scallions` becomes `green onion`
+    /// as well.
+    pub fn add_synonym(&mut self, synonym: &str, name: &str) {
+        let name = self.normalize(name);
+        let synonym = self.fold(synonym);
+        self.synonyms.insert(synonym, name);
+    }
+
+    /// The normalized name: lowercase, with single spaces, the last word in singular and
+    /// synonyms replaced.
+    pub fn normalize(&self, name: &str) -> String {
+        let folded = self.fold(name);
+        match self.synonyms.get(&folded) {
+            Some(name) => name.clone(),
+            None => folded,
+        }
+    }
+
+    /// Whether the names are the same after normalizing.
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        self.normalize(a) == self.normalize(b)
+    }
+
+    /// The name lowercased, with single spaces and the last word in singular.
+    fn fold(&self, name: &str) -> String {
+        let mut words: Vec<String> = name.split_whitespace().map(str::to_lowercase).collect();
+        if self.fold_plurals {
+            if let Some(last) = words.last_mut() {
+                *last = self.singular(last);
+            }
+        }
+        words.join(" ")
+    }
+
+    fn singular(&self, word: &str) -> String {
+        if let Some(singular) = self.plurals.get(word) {
+            return singular.clone();
+        }
+        let rules = [
+            ("ies", "y"),
+            ("oes", "o"),
+            ("ches", "ch"),
+            ("shes", "sh"),
+            ("sses", "ss"),
+            ("xes", "x"),
+            ("ss", "ss"),
+            ("us", "us"),
+            ("is", "is"),
+            ("s", ""),
+        ];
+        rules
+            .iter()
+            .find_map(|(suffix, replacement)| {
+                let stem = word.strip_suffix(suffix)?;
+                Some(stem.to_string() + replacement).filter(|_| stem.len() > 1)
+            })
+            .unwrap_or_else(|| word.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::names::NameNormalizer;
+
+    #[test]
+    fn normalize() {
+        let mut names = NameNormalizer::default();
+        let normalized: Vec<String> = [
+            "Onions",
+            " red  onion ",
+            "tomatoes",
+            "berries",
+            "peaches",
+            "bay leaves",
+            "glass",
+            "asparagus",
+            "couscous",
+            "Egg",
+        ]
+        .iter()
+        .map(|name| names.normalize(name))
+        .collect();
+        assert_eq!(
+            normalized,
+            vec![
+                "onion",
+                "red onion",
+                "tomato",
+                "berry",
+                "peach",
+                "bay leaf",
+                "glass",
+                "asparagus",
+                "couscous",
+                "egg"
+            ]
+        );
+        names.add_synonym("Scallions", "green onions");
+        assert_eq!(names.normalize("scallion"), "green onion");
+        assert!(names.same("Scallion", "green onion"));
+        assert!(!NameNormalizer::case_insensitive().same("onions", "Onion"));
+        assert!(NameNormalizer::case_insensitive().same(" Onion", "onion"));
+    }
+}
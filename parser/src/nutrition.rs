@@ -0,0 +1,240 @@
+//! Nutrition facts of recipes from a food database.
+//!
+//! The crate doesn't ship nutrition data. A [NutritionProvider] looks up the nutrients of an
+//! ingredient by its name, per 100 g, and [Recipe::nutrition] weighs the ingredients and adds up
+//! their nutrients. Amounts by volume are weighed with the density of the ingredient and amounts
+//! without a unit, like `@eggs{2}`, with the mass of a piece.
+//!
+//! ```
+//! use cook_with_rust_parser::nutrition::Nutrients;
+//! use cook_with_rust_parser::parse;
+//! use std::collections::HashMap;
+//!
+//! let mut database = HashMap::new();
+//! database.insert("oats".to_string(), Nutrients::new(370.0, 13.0, 7.0, 60.0));
+//! let recipe = parse(">> servings: 2\nCook @oats{80%g} in @water{300%ml}.\n").unwrap();
+//! let nutrition = recipe.nutrition(&database);
+//! assert_eq!(nutrition.total.calories, 296.0);
+//! assert_eq!(nutrition.per_serving.unwrap().calories, 148.0);
+//! assert_eq!(nutrition.unknown, vec!["water"]);
+//! ```
+
+use crate::units::{PhysicalQuantity, UnitRegistry};
+use crate::{Ingredient, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Energy and macronutrients of an amount of food.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Nutrients {
+    /// Energy in kcal.
+    pub calories: f64,
+    /// Protein in gram.
+    pub protein: f64,
+    /// Fat in gram.
+    pub fat: f64,
+    /// Carbohydrates in gram.
+    pub carbohydrates: f64,
+}
+
+impl Nutrients {
+    /// Create nutrients from kcal and grams of protein, fat and carbohydrates.
+    pub fn new(calories: f64, protein: f64, fat: f64, carbohydrates: f64) -> Nutrients {
+        Nutrients {
+            calories,
+            protein,
+            fat,
+            carbohydrates,
+        }
+    }
+
+    /// Add the other nutrients to these.
+    pub fn add(&mut self, other: &Nutrients) {
+        self.calories += other.calories;
+        self.protein += other.protein;
+        self.fat += other.fat;
+        self.carbohydrates += other.carbohydrates;
+    }
+
+    /// The nutrients multiplied by the factor.
+    pub fn scaled(&self, factor: f64) -> Nutrients {
+        Nutrients::new(
+            self.calories * factor,
+            self.protein * factor,
+            self.fat * factor,
+            self.carbohydrates * factor,
+        )
+    }
+}
+
+/// A food database looking up ingredients by their name.
+pub trait NutritionProvider {
+    /// Nutrients of 100 g of the ingredient. [None] if it isn't known.
+    fn per_100g(&self, name: &str) -> Option<Nutrients>;
+
+    /// Density of the ingredient in g/ml, to weigh amounts by volume. Water by default.
+    fn density(&self, _name: &str) -> Option<f64> {
+        Some(1.0)
+    }
+
+    /// Mass of a piece of the ingredient in gram, to weigh amounts without a unit.
+    fn piece_mass(&self, _name: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// Nutrients per 100 g by ingredient name.
+impl NutritionProvider for HashMap<String, Nutrients> {
+    fn per_100g(&self, name: &str) -> Option<Nutrients> {
+        self.get(name).copied()
+    }
+}
+
+/// Nutrition facts of a [Recipe].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Nutrition {
+    /// Nutrients of the whole recipe.
+    pub total: Nutrients,
+    /// Nutrients of a serving, if the recipe lists its servings.
+    pub per_serving: Option<Nutrients>,
+    /// Names of the ingredients which couldn't be looked up or weighed, in order. They are left
+    /// out of the nutrients.
+    pub unknown: Vec<String>,
+}
+
+impl Recipe {
+    /// Nutrition facts of the recipe for its first listed servings, using the units of the
+    /// built-in [UnitRegistry].
+    pub fn nutrition(&self, provider: &impl NutritionProvider) -> Nutrition {
+        self.nutrition_with(provider, &UnitRegistry::default())
+    }
+
+    /// Nutrition facts of the recipe using the units of the registry.
+    ///
+    /// Amounts per serving are taken for the first listed servings, or a single serving if there
+    /// are none. Optional ingredients are left out.
+    pub fn nutrition_with(
+        &self,
+        provider: &impl NutritionProvider,
+        registry: &UnitRegistry,
+    ) -> Nutrition {
+        let servings = self
+            .metadata
+            .servings
+            .as_ref()
+            .and_then(|servings| servings.first().copied());
+        let mut nutrition = Nutrition::default();
+        self.metadata
+            .ingredients
+            .values()
+            .filter(|ingredient| !ingredient.optional)
+            .for_each(|ingredient| {
+                let nutrients = provider.per_100g(&ingredient.name).and_then(|nutrients| {
+                    let mass = mass(provider, registry, ingredient, servings.unwrap_or(1))?;
+                    Some(nutrients.scaled(mass / 100.0))
+                });
+                match nutrients {
+                    Some(nutrients) => nutrition.total.add(&nutrients),
+                    None => nutrition.unknown.push(ingredient.name.clone()),
+                }
+            });
+        nutrition.per_serving = servings
+            .filter(|&servings| servings > 0)
+            .map(|servings| nutrition.total.scaled(1.0 / servings as f64));
+        nutrition
+    }
+}
+
+/// Mass of the ingredient in gram for the servings. [None] if a quantity can't be weighed.
+fn mass(
+    provider: &impl NutritionProvider,
+    registry: &UnitRegistry,
+    ingredient: &Ingredient,
+    servings: usize,
+) -> Option<f64> {
+    let name = ingredient.name.as_str();
+    ingredient
+        .quantities
+        .quantities
+        .iter()
+        .map(|quantity| {
+            let value = quantity.amount.resolve(0, servings).ok()?;
+            let unit = match quantity.unit.as_deref() {
+                None => return Some(value * provider.piece_mass(name)?),
+                Some(unit) => registry.get(unit)?,
+            };
+            match unit.quantity {
+                PhysicalQuantity::Mass => registry.convert(value, &unit.symbol, "g"),
+                PhysicalQuantity::Volume => {
+                    Some(registry.convert(value, &unit.symbol, "ml")? * provider.density(name)?)
+                }
+                _ => None,
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nutrition::{Nutrients, NutritionProvider};
+    use crate::{parse, parse_with, ParseOptions};
+
+    struct Database;
+
+    impl NutritionProvider for Database {
+        fn per_100g(&self, name: &str) -> Option<Nutrients> {
+            match name {
+                "flour" => Some(Nutrients::new(360.0, 10.0, 1.0, 75.0)),
+                "milk" => Some(Nutrients::new(64.0, 3.4, 3.6, 4.8)),
+                "eggs" => Some(Nutrients::new(150.0, 12.0, 10.0, 1.0)),
+                "oil" => Some(Nutrients::new(900.0, 0.0, 100.0, 0.0)),
+                _ => None,
+            }
+        }
+
+        fn density(&self, name: &str) -> Option<f64> {
+            match name {
+                "milk" => Some(1.03),
+                "oil" => Some(0.9),
+                _ => None,
+            }
+        }
+
+        fn piece_mass(&self, name: &str) -> Option<f64> {
+            Some(50.0).filter(|_| name == "eggs")
+        }
+    }
+
+    #[test]
+    fn nutrition() {
+        let source = ">> servings: 4|8\nMix @flour{1/4%kg}, @milk{100*%ml} and @eggs{2|4}, fry \
+                      in @oil{15%ml} and serve with @?syrup{2%tbsp} and @berries{1%cup}.\n";
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        let nutrition = recipe.nutrition(&Database);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        // 250 g flour, 412 g milk, 100 g eggs and 13.5 g oil.
+        assert!(close(
+            nutrition.total.calories,
+            900.0 + 263.68 + 150.0 + 121.5
+        ));
+        assert!(close(nutrition.total.fat, 2.5 + 14.832 + 10.0 + 13.5));
+        let per_serving = nutrition.per_serving.unwrap();
+        assert!(close(per_serving.calories, nutrition.total.calories / 4.0));
+        assert_eq!(nutrition.unknown, vec!["berries"]);
+
+        let recipe = parse("Fry @eggs{2%kg} with @flour{2}.\n").unwrap();
+        let nutrition = recipe.nutrition(&Database);
+        assert_eq!(nutrition.total.protein, 240.0);
+        assert_eq!(nutrition.per_serving, None);
+        assert_eq!(nutrition.unknown, vec!["flour"]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Output of a [Recipe] as JSON, YAML or TOML.
+//!
+//! Requires the `json`, `yaml` or `toml` feature. The field naming is stable:
+//!
+//! * Fields are named like the fields of the structs, e.g. `servings_labels`. With
+//!   [FieldNaming::CamelCase] they are named like `servingsLabels`, as in other CookLang tooling.
+//! * Variants of enums are written with their name as key, like `{"Single": 2.0}` for
+//!   [Amount::Single](crate::Amount::Single).
+//! * Ingredient names, the keys of [Metadata::ingredients](crate::Metadata::ingredients), and
+//!   metadata keys are kept as they are.
+//! * Fields without a value are `null` in JSON and YAML and left out in TOML, which has no null.
+
+use crate::Recipe;
+use serde_json::{Map, Value};
+
+/// Naming of the fields of the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FieldNaming {
+    /// Fields named like `servings_labels`.
+    #[default]
+    SnakeCase,
+    /// Fields named like `servingsLabels`.
+    CamelCase,
+}
+
+impl Recipe {
+    /// The recipe as indented JSON.
+    #[cfg(any(test, feature = "json"))]
+    pub fn to_json_pretty(&self, naming: FieldNaming) -> String {
+        serde_json::to_string_pretty(&to_value(self, naming)).unwrap()
+    }
+
+    /// The recipe as YAML.
+    #[cfg(any(test, feature = "yaml"))]
+    pub fn to_yaml(&self, naming: FieldNaming) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(&to_value(self, naming))
+    }
+
+    /// The recipe as TOML.
+    #[cfg(any(test, feature = "toml"))]
+    pub fn to_toml(&self, naming: FieldNaming) -> Result<String, toml::ser::Error> {
+        let mut value = to_value(self, naming);
+        remove_nulls(&mut value);
+        toml::to_string_pretty(&value)
+    }
+}
+
+fn to_value(recipe: &Recipe, naming: FieldNaming) -> Value {
+    let mut value = serde_json::to_value(recipe).unwrap();
+    if naming == FieldNaming::CamelCase {
+        value = camel_case(value, false);
+    }
+    value
+}
+
+/// Rename the fields to camel case. The keys of `ingredients` maps are names and kept.
+fn camel_case(value: Value, names: bool) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = camel_case(value, !names && key == "ingredients");
+                    match names {
+                        true => (key, value),
+                        false => (camel_case_name(&key), value),
+                    }
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| camel_case(value, false))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// `servings_labels` as `servingsLabels`.
+fn camel_case_name(name: &str) -> String {
+    let mut parts = name.split('_');
+    let mut result = parts.next().unwrap_or_default().to_string();
+    parts.for_each(|part| {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.extend(first.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    });
+    result
+}
+
+/// Leave out fields without a value.
+#[cfg(any(test, feature = "toml"))]
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|_, value| !value.is_null());
+            fields.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::FieldNaming;
+    use crate::parse;
+
+    #[test]
+    fn output() {
+        let recipe =
+            parse(">> servings: 2|4\n>> prep_time: 10 min\nAdd @olive_oil{2%tbsp}.\n").unwrap();
+        let json = recipe.to_json_pretty(FieldNaming::SnakeCase);
+        assert!(json.contains("\"servings_labels\": ["));
+        let json = recipe.to_json_pretty(FieldNaming::CamelCase);
+        assert!(json.contains("\"servingsLabels\": [") && json.contains("\"olive_oil\": {"));
+        assert!(json.contains("\"prep_time\""));
+        assert!(json.contains("\"ingredientsSpecifiers\"") && json.contains("\"amountInStep\""));
+
+        let yaml = recipe.to_yaml(FieldNaming::CamelCase).unwrap();
+        assert!(yaml.contains("servingsLabels:"));
+        let toml = recipe.to_toml(FieldNaming::SnakeCase).unwrap();
+        assert!(toml.contains("servings_labels = ["));
+        let value: toml::Table = toml::from_str(&toml).unwrap();
+        assert_eq!(value["source"].as_str(), Some(recipe.source.as_str()));
+    }
+}
@@ -0,0 +1,142 @@
+//! Parser for the pantry configuration, listing the ingredients at hand.
+//!
+//! Like the [aisle configuration](crate::aisle), the pantry is split into sections by names in
+//! square brackets, usually the place the ingredients are stored. Every line contains an
+//! ingredient and optionally the quantity at hand, written like the amount of an ingredient.
+//! Without a quantity, there is always enough of the ingredient. Lines starting with `#` are
+//! comments.
+//!
+//! ```text
+//! [fridge]
+//! milk = 1%l
+//! eggs = 6
+//!
+//! [spices]
+//! salt
+//! ```
+
+use crate::time::parse_number;
+use crate::{Amount, Quantity};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The parsed pantry configuration.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Pantry {
+    /// Ingredients in the order they appear in the configuration.
+    pub items: Vec<PantryItem>,
+}
+
+/// An ingredient at hand.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PantryItem {
+    /// Name of the ingredient.
+    pub name: String,
+    /// The section the ingredient is listed in, e.g. `fridge`.
+    pub location: Option<String>,
+    /// Quantity at hand. [None] if there is always enough.
+    pub quantity: Option<Quantity>,
+}
+
+impl Pantry {
+    /// Parse the content of a pantry configuration.
+    pub fn parse(inp: &str) -> Result<Pantry, Box<dyn std::error::Error>> {
+        let mut items = vec![];
+        let mut location = None;
+        for (number, line) in inp.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("Missing ] in section on line {}", number + 1))?;
+                location = Some(name.trim().to_string());
+                continue;
+            }
+            let (name, quantity) = match line.split_once('=') {
+                Some((name, quantity)) => {
+                    let quantity = quantity.trim().trim_matches('"');
+                    let quantity = parse_quantity(quantity)
+                        .ok_or_else(|| format!("Invalid quantity on line {}", number + 1))?;
+                    (name, Some(quantity))
+                }
+                None => (line, None),
+            };
+            let name = name.trim().trim_matches('"');
+            if name.is_empty() {
+                return Err(format!("Missing ingredient on line {}", number + 1).into());
+            }
+            items.push(PantryItem {
+                name: name.to_string(),
+                location: location.clone(),
+                quantity,
+            });
+        }
+        Ok(Pantry { items })
+    }
+
+    /// The item of the ingredient, compared case-insensitively.
+    pub fn get(&self, ingredient: &str) -> Option<&PantryItem> {
+        let ingredient = ingredient.trim();
+        self.items
+            .iter()
+            .find(|item| item.name.eq_ignore_ascii_case(ingredient))
+    }
+}
+
+/// Parse a quantity like `1%kg`, `1/2` or `6`.
+fn parse_quantity(quantity: &str) -> Option<Quantity> {
+    let (amount, unit) = match quantity.split_once('%') {
+        Some((amount, unit)) => (amount, Some(unit.trim().to_string())),
+        None => (quantity, None),
+    };
+    Some(Quantity {
+        amount: Amount::Single(parse_number(amount)?),
+        unit: unit.filter(|unit| !unit.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pantry::Pantry;
+    use crate::{Amount, Quantity};
+
+    #[test]
+    fn parse() {
+        let pantry = Pantry::parse(
+            "\
+# at home
+[fridge]
+milk = 1%l
+\"eggs\" = \"6\"
+
+[spices]
+Salt
+",
+        )
+        .unwrap();
+        assert_eq!(pantry.items.len(), 3);
+        assert_eq!(pantry.items[0].location.as_deref(), Some("fridge"));
+        assert_eq!(
+            pantry.items[0].quantity,
+            Some(Quantity {
+                amount: Amount::Single(1.0),
+                unit: Some("l".to_string())
+            })
+        );
+        assert_eq!(
+            pantry.get("eggs").unwrap().quantity.as_ref().unwrap().unit,
+            None
+        );
+        assert!(pantry.get("salt").unwrap().quantity.is_none());
+
+        assert!(Pantry::parse("[fridge\nmilk\n").is_err());
+        assert!(Pantry::parse("milk = lots\n").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Planning to cook several recipes at once.
+//!
+//! A [CookPlan] interleaves the steps of the recipes for a single cook. Every step takes the
+//! cook [PlanOptions::step_time], after which the timers of the step run on their own, see
+//! [Recipe::step_duration]. The next step of the recipe waits for the timers, but the cook can
+//! work on the other recipes in the meantime, e.g. prepare the salad while the roast is in the
+//! oven.
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::plan::{CookPlan, PlanOptions};
+//! use std::time::Duration;
+//!
+//! let roast = parse("Put the @roast in the oven for ~{40%minutes}.\n\nCarve it.\n").unwrap();
+//! let salad = parse("Wash the @lettuce.\n\nDress it.\n").unwrap();
+//! let plan = CookPlan::from_recipes(vec![&roast, &salad], &PlanOptions::default());
+//! let order: Vec<(usize, usize)> = plan.steps.iter().map(|s| (s.recipe, s.step)).collect();
+//! assert_eq!(order, vec![(0, 1), (1, 1), (1, 2), (0, 2)]);
+//! assert_eq!(plan.duration, Duration::from_secs(50 * 60));
+//! ```
+
+use crate::duration::sum;
+use crate::render::step_text;
+use crate::Recipe;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Options of [CookPlan::from_recipes].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlanOptions {
+    /// Time the cook works on a step, before its timers start. Defaults to 5 minutes.
+    pub step_time: Duration,
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        PlanOptions {
+            step_time: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Timeline of cooking several recipes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CookPlan {
+    /// The steps in the order the cook works on them.
+    pub steps: Vec<PlannedStep>,
+    /// Time from the start until all recipes are done.
+    pub duration: Duration,
+}
+
+/// A step of a [CookPlan].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PlannedStep {
+    /// Index of the recipe in the recipes of the plan.
+    pub recipe: usize,
+    /// Number of the step in the recipe.
+    pub step: usize,
+    /// Plain text of the step.
+    pub text: String,
+    /// Time from the start until the cook starts the step.
+    pub start: Duration,
+    /// Time from the start until the cook is done with the step.
+    pub end: Duration,
+    /// Time from the start until the timers of the step have fired. The same as
+    /// [end](PlannedStep::end) for steps without timers.
+    pub ready: Duration,
+}
+
+impl CookPlan {
+    /// Plan to cook the recipes. Of the recipes whose next step can be started first, the one
+    /// with the most time left goes first, so long timers are started early.
+    ///
+    /// Timers with a unit which isn't a time unit are left out.
+    pub fn from_recipes<'a, I>(recipes: I, options: &PlanOptions) -> CookPlan
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        let recipes: Vec<&Recipe> = recipes.into_iter().collect();
+        // The next step and the time it can be started at, per recipe.
+        let mut next = vec![(0, Duration::ZERO); recipes.len()];
+        let mut cook = Duration::ZERO;
+        let mut plan = CookPlan::default();
+        loop {
+            let left = |recipe: usize, step: usize| -> Duration {
+                let steps = recipes[recipe].steps[step..].iter();
+                sum(steps.map(|step| {
+                    let duration = recipes[recipe].step_duration(step);
+                    options.step_time.saturating_add(duration)
+                }))
+            };
+            let chosen = next
+                .iter()
+                .enumerate()
+                .filter(|(recipe, (step, _))| *step < recipes[*recipe].steps.len())
+                .min_by_key(|(recipe, (step, ready))| {
+                    ((*ready).max(cook), std::cmp::Reverse(left(*recipe, *step)))
+                })
+                .map(|(recipe, (step, ready))| (recipe, *step, *ready));
+            let (recipe, step, ready) = match chosen {
+                Some(chosen) => chosen,
+                None => break,
+            };
+            let start = ready.max(cook);
+            let end = start.saturating_add(options.step_time);
+            let planned = &recipes[recipe].steps[step];
+            let ready = end.saturating_add(recipes[recipe].step_duration(planned));
+            plan.steps.push(PlannedStep {
+                recipe,
+                step: planned.number,
+                text: step_text(recipes[recipe], planned),
+                start,
+                end,
+                ready,
+            });
+            plan.duration = plan.duration.max(ready);
+            next[recipe] = (step + 1, ready);
+            cook = end;
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::plan::{CookPlan, PlanOptions};
+    use std::time::Duration;
+
+    #[test]
+    fn cook_plan() {
+        let roast = parse(
+            "Season the @roast{1%kg}.\n\nRoast it in the #oven for ~{1%hour}.\n\n\
+             Rest it for ~{10%minutes}.\n\nCarve it.\n",
+        )
+        .unwrap();
+        let salad = parse("Wash the @lettuce.\n\nDress it with @oil.\n").unwrap();
+        let rice = parse("Cook the @rice for ~{20%minutes}.\n\nFluff it.\n").unwrap();
+        let options = PlanOptions {
+            step_time: Duration::from_secs(10 * 60),
+        };
+        let plan = CookPlan::from_recipes(vec![&salad, &rice, &roast], &options);
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|step| (step.recipe, step.step, step.start, step.ready))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (2, 1, minutes(0), minutes(10)),
+                (2, 2, minutes(10), minutes(80)),
+                (1, 1, minutes(20), minutes(50)),
+                (0, 1, minutes(30), minutes(40)),
+                (0, 2, minutes(40), minutes(50)),
+                (1, 2, minutes(50), minutes(60)),
+                (2, 3, minutes(80), minutes(100)),
+                (2, 4, minutes(100), minutes(110)),
+            ]
+        );
+        assert_eq!(plan.steps[1].text, "Roast it in the oven for 1 hour.");
+        assert_eq!(plan.duration, minutes(110));
+        assert!(CookPlan::from_recipes(vec![], &options).steps.is_empty());
+
+        let long = parse("Wait ~{200000000000000%days}.\n\nWait ~{200000000000000%days}.\n");
+        let plan = CookPlan::from_recipes(vec![&long.unwrap()], &options);
+        assert_eq!(plan.duration, Duration::MAX);
+    }
+}
@@ -0,0 +1,168 @@
+//! Bindings for Python, e.g. to analyze recipe collections in notebooks.
+//!
+//! Requires the `python` feature. Build the `cook_with_rust` module with
+//! `maturin develop -m parser/Cargo.toml --features python`, then
+//!
+//! ```python
+//! import cook_with_rust
+//!
+//! recipe = cook_with_rust.parse(open("Pancakes.cook").read())
+//! recipe["ingredients"]["flour"]["amount"]
+//! ```
+//!
+//! A parsed recipe is read like a dict with the keys `source`, `instruction`, `metadata`,
+//! `servings`, `ingredients`, `cookware`, `timers`, `steps` and `sections`. The values are plain
+//! Python dicts, lists and numbers, in the shape of the JSON of the [Recipe].
+
+use crate::Recipe;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::{json, Map, Value};
+
+/// A parsed recipe, read like a dict.
+#[pyclass(name = "Recipe", frozen)]
+pub struct PyRecipe {
+    entries: Map<String, Value>,
+}
+
+#[pymethods]
+impl PyRecipe {
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.entries.get(key) {
+            Some(value) => to_python(py, value),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The keys of the recipe.
+    fn keys(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    /// The recipe as a dict.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_python(py, &Value::Object(self.entries.clone()))
+    }
+
+    fn __repr__(&self) -> String {
+        let title = self.entries["metadata"]
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        format!("Recipe({:?})", title)
+    }
+}
+
+impl From<&Recipe> for PyRecipe {
+    fn from(recipe: &Recipe) -> Self {
+        let metadata = &recipe.metadata;
+        // Of repeated keys the last value is used, like Metadata::get.
+        let entries: Map<String, Value> = metadata
+            .entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), json!(value)))
+            .collect();
+        let recipe = json!({
+            "source": recipe.source,
+            "instruction": recipe.instruction,
+            "metadata": entries,
+            "servings": metadata.servings,
+            "ingredients": metadata.ingredients,
+            "cookware": metadata.cookware,
+            "timers": metadata.timer,
+            "steps": recipe.steps,
+            "sections": recipe.sections,
+        });
+        match recipe {
+            Value::Object(entries) => PyRecipe { entries },
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Parse the source into a [PyRecipe]. Raises a `ValueError` if it can't be parsed.
+#[pyfunction]
+fn parse(source: &str) -> PyResult<PyRecipe> {
+    let recipe = crate::parse(source).map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(PyRecipe::from(&recipe))
+}
+
+/// The `cook_with_rust` Python module.
+#[pymodule]
+fn cook_with_rust(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    module.add_class::<PyRecipe>()?;
+    Ok(())
+}
+
+/// Convert JSON into Python dicts, lists, strings, numbers and `None`.
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(bool) => bool.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => int.into_pyobject(py)?.into_any(),
+            None => number.as_f64().into_pyobject(py)?.into_any(),
+        },
+        Value::String(string) => string.into_pyobject(py)?.into_any(),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(to_python(py, value)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(entries) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::python::parse;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn parse_recipe() {
+        Python::initialize();
+        Python::attach(|py| {
+            let recipe = parse(">> title: Tea\nSteep @tea{2%tsp} in #cup.\n").unwrap();
+            let recipe = Bound::new(py, recipe).unwrap();
+            let tea = recipe
+                .get_item("ingredients")
+                .unwrap()
+                .get_item("tea")
+                .unwrap();
+            assert_eq!(
+                tea.get_item("unit").unwrap().extract::<String>().unwrap(),
+                "tsp"
+            );
+            let title = recipe
+                .get_item("metadata")
+                .unwrap()
+                .get_item("title")
+                .unwrap();
+            assert_eq!(title.extract::<String>().unwrap(), "Tea");
+            assert!(recipe.contains("steps").unwrap());
+            assert!(recipe.get_item("missing").is_err());
+            let dict = recipe.call_method0("to_dict").unwrap();
+            assert!(dict.cast::<PyDict>().is_ok());
+            assert!(parse("").is_ok());
+        });
+    }
+}
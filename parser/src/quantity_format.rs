@@ -0,0 +1,151 @@
+//! Formatting of amounts for reading, used by the [renderers](crate::render).
+//!
+//! A [QuantityFormatter] writes numbers as decimals with up to [QuantityFormatter::precision]
+//! decimals, like `1.25`, or as fractions, like `1 ½`. The decimal separator depends on the
+//! locale, e.g. `1,25` in German.
+
+use crate::{Amount, Quantity};
+
+/// Fractions written as a single character.
+const FRACTIONS: [(f64, char); 15] = [
+    (1.0 / 8.0, '⅛'),
+    (1.0 / 6.0, '⅙'),
+    (1.0 / 5.0, '⅕'),
+    (1.0 / 4.0, '¼'),
+    (1.0 / 3.0, '⅓'),
+    (3.0 / 8.0, '⅜'),
+    (2.0 / 5.0, '⅖'),
+    (1.0 / 2.0, '½'),
+    (3.0 / 5.0, '⅗'),
+    (5.0 / 8.0, '⅝'),
+    (2.0 / 3.0, '⅔'),
+    (3.0 / 4.0, '¾'),
+    (4.0 / 5.0, '⅘'),
+    (5.0 / 6.0, '⅚'),
+    (7.0 / 8.0, '⅞'),
+];
+
+/// Difference up to which a number is written as a fraction.
+const FRACTION_TOLERANCE: f64 = 0.005;
+
+/// Languages writing decimals with a comma, by their ISO 639-1 code.
+const COMMA_LANGUAGES: [&str; 20] = [
+    "cs", "da", "de", "el", "es", "fi", "fr", "hr", "hu", "id", "it", "nb", "nl", "pl", "pt", "ro",
+    "ru", "sv", "tr", "uk",
+];
+
+/// How numbers of amounts are written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuantityFormatter {
+    /// Write numbers close to a common fraction like `1 ½`. Others are written as decimals.
+    pub fractions: bool,
+    /// Maximum number of decimals. Trailing zeros are left out.
+    pub precision: usize,
+    /// Separator of the decimals, like `.` in `1.5`.
+    pub decimal_separator: char,
+}
+
+impl Default for QuantityFormatter {
+    fn default() -> Self {
+        QuantityFormatter {
+            fractions: false,
+            precision: 2,
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl QuantityFormatter {
+    /// The formatter for a locale like `de` or `pt-BR`, which decides the decimal separator.
+    pub fn for_locale(locale: &str) -> QuantityFormatter {
+        let language = locale.split(['-', '_']).next().unwrap_or_default();
+        let comma = COMMA_LANGUAGES
+            .iter()
+            .any(|comma| comma.eq_ignore_ascii_case(language));
+        QuantityFormatter {
+            decimal_separator: if comma { ',' } else { '.' },
+            ..QuantityFormatter::default()
+        }
+    }
+
+    /// The number, like `1.25` or `1 ¼`.
+    pub fn number(&self, number: f64) -> String {
+        if let Some(fraction) = Some(number).filter(|_| self.fractions).and_then(fraction) {
+            return fraction;
+        }
+        let formatted = format!("{:.*}", self.precision, number);
+        let formatted = match formatted.contains('.') {
+            true => formatted.trim_end_matches('0').trim_end_matches('.'),
+            false => &formatted,
+        };
+        formatted.replace('.', &self.decimal_separator.to_string())
+    }
+
+    /// The amount, like `1.5`, `2 per serving` or `1|2`.
+    pub fn amount(&self, amount: &Amount) -> String {
+        match amount {
+            Amount::Single(value) => self.number(*value),
+            Amount::Multi(value) => format!("{} per serving", self.number(*value)),
+            Amount::PerServing(values) => {
+                let values: Vec<String> = values.iter().map(|value| self.number(*value)).collect();
+                values.join("|")
+            }
+        }
+    }
+
+    /// The amount followed by the unit, like `125 g`.
+    pub fn quantity(&self, quantity: &Quantity) -> String {
+        match &quantity.unit {
+            Some(unit) => format!("{} {}", self.amount(&quantity.amount), unit),
+            None => self.amount(&quantity.amount),
+        }
+    }
+}
+
+/// The number as a whole number and a fraction, if it's close to one.
+fn fraction(number: f64) -> Option<String> {
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+    let whole = number.trunc();
+    let (_, fraction) = FRACTIONS
+        .iter()
+        .find(|(fraction, _)| (number - whole - fraction).abs() < FRACTION_TOLERANCE)?;
+    Some(match whole as u64 {
+        0 => fraction.to_string(),
+        whole => format!("{} {}", whole, fraction),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quantity_format::QuantityFormatter;
+    use crate::{Amount, Quantity};
+
+    #[test]
+    fn format_quantities() {
+        let formatter = QuantityFormatter::default();
+        assert_eq!(formatter.number(1.5), "1.5");
+        assert_eq!(formatter.number(2.0), "2");
+        assert_eq!(formatter.number(1.0 / 3.0), "0.33");
+
+        let formatter = QuantityFormatter {
+            fractions: true,
+            precision: 1,
+            ..QuantityFormatter::for_locale("de-AT")
+        };
+        assert_eq!(formatter.number(1.5), "1 ½");
+        assert_eq!(formatter.number(1.0 / 3.0), "⅓");
+        assert_eq!(formatter.number(3.0), "3");
+        assert_eq!(formatter.number(2.45), "2,5");
+        let quantity = Quantity {
+            amount: Amount::PerServing(vec![0.75, 1.5]),
+            unit: Some("cup".to_string()),
+        };
+        assert_eq!(formatter.quantity(&quantity), "¾|1 ½ cup");
+        assert_eq!(
+            QuantityFormatter::for_locale("en-US").decimal_separator,
+            '.'
+        );
+    }
+}
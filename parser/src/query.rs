@@ -0,0 +1,140 @@
+//! Searching the recipes of a [Collection].
+//!
+//! A [Query] combines conditions which all have to match:
+//!
+//! ```
+//! use cook_with_rust_parser::query::Query;
+//! use std::time::Duration;
+//!
+//! let query = Query::new()
+//!     .tag("dinner")
+//!     .max_time(Duration::from_secs(30 * 60))
+//!     .without_ingredient("peanut");
+//! ```
+
+use crate::{Collection, CollectionRecipe, Recipe};
+use std::time::Duration;
+
+/// Conditions on recipes. An empty query matches every recipe.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// Tags the recipe must have, compared case-insensitively.
+    pub tags: Vec<String>,
+    /// Longest total [time](crate::Metadata::time). Recipes without a time don't match.
+    pub max_time: Option<Duration>,
+    /// Terms which must be part of the name of an ingredient, compared case-insensitively.
+    pub with_ingredients: Vec<String>,
+    /// Terms which must not be part of the name of any ingredient, compared case-insensitively.
+    pub without_ingredients: Vec<String>,
+    /// Cookware at hand. If set, recipes needing other cookware don't match.
+    pub cookware: Option<Vec<String>>,
+}
+
+impl Query {
+    /// Create an empty query.
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    /// Require a tag.
+    pub fn tag(mut self, tag: &str) -> Query {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Limit the total time.
+    pub fn max_time(mut self, time: Duration) -> Query {
+        self.max_time = Some(time);
+        self
+    }
+
+    /// Require an ingredient.
+    pub fn with_ingredient(mut self, ingredient: &str) -> Query {
+        self.with_ingredients.push(ingredient.to_string());
+        self
+    }
+
+    /// Exclude an ingredient, like `peanut`.
+    pub fn without_ingredient(mut self, ingredient: &str) -> Query {
+        self.without_ingredients.push(ingredient.to_string());
+        self
+    }
+
+    /// Add cookware to the cookware at hand.
+    pub fn cookware(mut self, cookware: &str) -> Query {
+        self.cookware
+            .get_or_insert_with(Vec::new)
+            .push(cookware.to_string());
+        self
+    }
+
+    /// Whether the recipe matches all conditions.
+    pub fn matches(&self, recipe: &Recipe) -> bool {
+        let metadata = &recipe.metadata;
+        let tags = metadata.tags();
+        let has_ingredient = |term: &String| {
+            let term = term.trim().to_lowercase();
+            metadata
+                .ingredients
+                .keys()
+                .any(|name| name.to_lowercase().contains(&term))
+        };
+        self.tags
+            .iter()
+            .all(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())))
+            && self
+                .max_time
+                .is_none_or(|max| metadata.time().is_some_and(|time| time <= max))
+            && self.with_ingredients.iter().all(has_ingredient)
+            && !self.without_ingredients.iter().any(has_ingredient)
+            && self.cookware.as_ref().is_none_or(|available| {
+                metadata.cookware.iter().all(|needed| {
+                    available
+                        .iter()
+                        .any(|cookware| cookware.trim().eq_ignore_ascii_case(needed))
+                })
+            })
+    }
+}
+
+impl Collection {
+    /// Recipes matching the query, in the order of [Collection::recipes].
+    pub fn query<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = &'a CollectionRecipe> {
+        self.recipes
+            .iter()
+            .filter(move |recipe| query.matches(&recipe.recipe))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::query::Query;
+    use std::time::Duration;
+
+    #[test]
+    fn query() {
+        let recipe = parse(
+            "---\n\
+tags: [dinner, Asian]\n\
+time: 25 min\n\
+---\n\
+Fry the @noodles{200%g} in a #wok with @peanut oil{2%tbsp}.\n",
+        )
+        .unwrap();
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        assert!(Query::new().matches(&recipe));
+        assert!(Query::new().tag("asian").tag("Dinner").matches(&recipe));
+        assert!(!Query::new().tag("dessert").matches(&recipe));
+        assert!(Query::new().max_time(minutes(30)).matches(&recipe));
+        assert!(!Query::new().max_time(minutes(20)).matches(&recipe));
+        assert!(Query::new().with_ingredient("Noodles").matches(&recipe));
+        assert!(!Query::new().without_ingredient("peanut").matches(&recipe));
+        assert!(Query::new().cookware("WOK").matches(&recipe));
+        assert!(!Query::new().cookware("pan").matches(&recipe));
+
+        let recipe = parse("Mix @flour.\n").unwrap();
+        assert!(!Query::new().max_time(minutes(30)).matches(&recipe));
+        assert!(Query::new().cookware("pan").matches(&recipe));
+    }
+}
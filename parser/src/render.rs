@@ -0,0 +1,904 @@
+//! Rendering a [Recipe] for reading.
+//!
+//! [to_markdown] writes the title, a table of the metadata, the ingredients and cookware, and the
+//! numbered steps grouped by section. Ingredients and timers are bold and cookware is italic.
+//!
+//! The [Display](std::fmt::Display) implementation of [Recipe] writes the title, the ingredients,
+//! the cookware and the numbered steps as plain text. [to_text_with] writes the same with the
+//! amounts written by a [QuantityFormatter].
+//!
+//! [to_html] writes the same parts as semantic HTML. Mentionings in the steps link to the lists
+//! by `data-` attributes:
+//!
+//! - `data-ingredient` is the index in [Metadata::ingredients](crate::Metadata::ingredients),
+//!   `data-specifier` the index in
+//!   [Metadata::ingredients_specifiers](crate::Metadata::ingredients_specifiers). `data-quantity`
+//!   and `data-note` are the amount and the preparation note of the mentioning.
+//! - `data-cookware` is the index in [Metadata::cookware](crate::Metadata::cookware).
+//! - `data-timer` is the index in [Metadata::timer](crate::Metadata::timer).
+//!
+//! [annotate_source] writes the source of the recipe as HTML with the same `data-` attributes,
+//! e.g. to highlight the source next to the rendered recipe.
+//!
+//! [to_markdown_localized] and [to_html_localized] write the headings and units in the language of
+//! a [Locale].
+
+use crate::lint::LintContext;
+use crate::locale::{isolate, Direction, English, Label, Locale};
+use crate::{
+    Amount, Ingredient, Item, Quantity, QuantityFormatter, Recipe, ShoppingItem, ShoppingList,
+    Span, Step, Timer,
+};
+use std::fmt;
+
+/// What [to_markdown] writes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    /// Write the metadata as a table.
+    pub metadata: bool,
+    /// Write the list of ingredients.
+    pub ingredients: bool,
+    /// Write the list of cookware.
+    pub cookware: bool,
+    /// Write the amounts of the ingredients in the steps.
+    pub step_amounts: bool,
+    /// How the amounts are written.
+    pub quantities: QuantityFormatter,
+    /// Write the [baker's percentages](crate::bakers) of the ingredients relative to this base
+    /// ingredient, like `325 g water (65%)`.
+    pub bakers_percentages: Option<String>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            metadata: true,
+            ingredients: true,
+            cookware: true,
+            step_amounts: true,
+            quantities: QuantityFormatter::default(),
+            bakers_percentages: None,
+        }
+    }
+}
+
+/// Render the recipe as a Markdown document.
+pub fn to_markdown(recipe: &Recipe, options: &RenderOptions) -> String {
+    to_markdown_localized(recipe, options, &English)
+}
+
+/// Render the recipe as a Markdown document like [to_markdown], in the language of the locale.
+pub fn to_markdown_localized(
+    recipe: &Recipe,
+    options: &RenderOptions,
+    locale: &dyn Locale,
+) -> String {
+    let metadata = &recipe.metadata;
+    let quantities = &options.quantities;
+    let mut result = String::new();
+
+    if let Some(title) = metadata.title() {
+        result += &format!("# {}\n\n", title);
+    }
+
+    if options.metadata {
+        let mut rows = vec![];
+        if !metadata.servings_labels.is_empty() {
+            rows.push(("servings", metadata.servings_labels.join("|")));
+        } else if let Some(servings) = &metadata.servings {
+            let servings: Vec<String> = servings.iter().map(|s| s.to_string()).collect();
+            rows.push(("servings", servings.join("|")));
+        }
+        metadata
+            .entries
+            .iter()
+            .filter(|(key, _)| !key.eq_ignore_ascii_case("title"))
+            .for_each(|(key, value)| rows.push((key, value.to_string())));
+        if !rows.is_empty() {
+            result += "| Key | Value |\n|-----|-------|\n";
+            rows.iter().for_each(|(key, value)| {
+                result += &format!("| {} | {} |\n", escape_cell(key), escape_cell(value));
+            });
+            result.push('\n');
+        }
+    }
+
+    if options.ingredients && !metadata.ingredients.is_empty() {
+        result += &format!("## {}\n\n", locale.label(Label::Ingredients));
+        let list = ShoppingList::from_recipes(vec![recipe]);
+        let bakers = options
+            .bakers_percentages
+            .as_ref()
+            .and_then(|base| recipe.bakers_percentages(base));
+        list.items.values().for_each(|item| {
+            result += "- ";
+            result += &localized_ingredient_text(recipe, item, quantities, locale);
+            let percentage = bakers
+                .as_ref()
+                .and_then(|bakers| bakers.percentages.get(&item.name));
+            if let Some(percentage) = percentage {
+                result += &format!(" ({}%)", quantities.number(*percentage));
+            }
+            if item.optional {
+                result += &format!(" *({})*", locale.label(Label::Optional));
+            }
+            result.push('\n');
+        });
+        result.push('\n');
+    }
+
+    if options.cookware && !metadata.cookware.is_empty() {
+        result += &format!("## {}\n\n", locale.label(Label::Cookware));
+        recipe
+            .equipment_list()
+            .iter()
+            .for_each(|equipment| result += &format!("- {}\n", equipment));
+        result.push('\n');
+    }
+
+    if !recipe.steps().is_empty() {
+        result += &format!("## {}\n\n", locale.label(Label::Steps));
+    }
+    recipe.sections().iter().for_each(|section| {
+        if let Some(name) = &section.name {
+            result += &format!("### {}\n\n", name);
+        }
+        recipe.section_steps(section).iter().for_each(|step| {
+            let mut text = String::new();
+            let mut notes = vec![];
+            step.items.iter().for_each(|item| match item {
+                Item::Text(value) => text += value,
+                Item::Ingredient(index) => {
+                    let specifier = &metadata.ingredients_specifiers[*index];
+                    text += &format!("**{}**", specifier.ingredient);
+                    let quantity = specifier.quantity().filter(|_| options.step_amounts);
+                    let details: Vec<String> = quantity
+                        .map(|quantity| {
+                            isolate(locale, localized_quantity(quantities, locale, &quantity))
+                        })
+                        .into_iter()
+                        .chain(specifier.note.clone())
+                        .collect();
+                    if !details.is_empty() {
+                        text += &format!(" ({})", details.join(", "));
+                    }
+                }
+                Item::Cookware(index) => text += &format!("*{}*", metadata.cookware[*index]),
+                Item::Timer(index) => {
+                    let timer = timer_text(quantities, locale, &metadata.timer[*index]);
+                    text += &format!("**{}**", isolate(locale, timer));
+                }
+                Item::Note(note) => notes.push(note.as_str()),
+                Item::RecipeRef { path, amount } => {
+                    let name = path.rsplit('/').next().unwrap_or(path);
+                    text += &format!("[{}]({}.cook)", name, path);
+                    if let Some(quantity) = amount.as_ref().filter(|_| options.step_amounts) {
+                        let quantity = localized_quantity(quantities, locale, quantity);
+                        text += &format!(" ({})", isolate(locale, quantity));
+                    }
+                }
+                Item::Temperature { value, unit } => {
+                    let temperature = format!("{}{}", quantities.number(*value), unit);
+                    text += &isolate(locale, temperature)
+                }
+            });
+            let text = text.trim();
+            if text.is_empty() {
+                result += &format!("{}.", step.number);
+            } else {
+                result += &format!("{}. {}", step.number, text);
+            }
+            result.push('\n');
+            notes
+                .iter()
+                .for_each(|note| result += &format!("\n   > {}\n", note));
+            result.push('\n');
+        });
+    });
+
+    while result.ends_with("\n\n") {
+        result.pop();
+    }
+    result
+}
+
+/// Render the recipe as HTML. All text is escaped.
+pub fn to_html(recipe: &Recipe) -> String {
+    to_html_with(recipe, &QuantityFormatter::default())
+}
+
+/// Render the recipe as HTML like [to_html], with the amounts written by the formatter.
+pub fn to_html_with(recipe: &Recipe, quantities: &QuantityFormatter) -> String {
+    to_html_localized(recipe, quantities, &English)
+}
+
+/// Render the recipe as HTML like [to_html_with], in the language of the locale. The `lang` and
+/// `dir` of the article are set unless the locale is English.
+pub fn to_html_localized(
+    recipe: &Recipe,
+    quantities: &QuantityFormatter,
+    locale: &dyn Locale,
+) -> String {
+    let metadata = &recipe.metadata;
+    let mut html = Html::default();
+    let mut attributes = vec![("class", "recipe")];
+    if locale.language() != English.language() {
+        attributes.push(("lang", locale.language()));
+    }
+    if locale.direction() == Direction::RightToLeft {
+        attributes.push(("dir", "rtl"));
+    }
+    html.open("article", &attributes);
+    if let Some(title) = metadata.title() {
+        html.element("h1", NO_ATTRIBUTES, title);
+    }
+
+    if !metadata.ingredients.is_empty() {
+        html.open("section", &[("class", "ingredients")]);
+        html.element("h2", NO_ATTRIBUTES, &locale.label(Label::Ingredients));
+        html.open("ul", NO_ATTRIBUTES);
+        let list = ShoppingList::from_recipes(vec![recipe]);
+        list.items.values().for_each(|item| {
+            let index = metadata.ingredients.get_index_of(&item.name).unwrap();
+            let mut attributes = vec![("data-ingredient", index.to_string())];
+            if item.optional {
+                attributes.push(("class", "optional".to_string()));
+            }
+            html.open_inline("li", &attributes);
+            let text: Vec<String> = item
+                .quantities
+                .quantities
+                .iter()
+                .map(|quantity| localized_quantity(quantities, locale, quantity))
+                .collect();
+            if !text.is_empty() {
+                let text = isolate(locale, text.join(" + "));
+                html.element_inline("span", &[("class", "quantity")], &text);
+                html.text(" ");
+            }
+            html.element_inline("span", &[("class", "name")], &item.name);
+            let notes = &metadata.ingredients[&item.name].notes;
+            if !notes.is_empty() {
+                html.text(" ");
+                html.element_inline("span", &[("class", "note")], &notes.join(", "));
+            }
+            html.close_inline("li");
+        });
+        html.close("ul");
+        html.close("section");
+    }
+
+    if !metadata.cookware.is_empty() {
+        html.open("section", &[("class", "cookware")]);
+        html.element("h2", NO_ATTRIBUTES, &locale.label(Label::Cookware));
+        html.open("ul", NO_ATTRIBUTES);
+        recipe.equipment_list().iter().for_each(|equipment| {
+            html.element("li", NO_ATTRIBUTES, &equipment.to_string());
+        });
+        html.close("ul");
+        html.close("section");
+    }
+
+    if !recipe.steps().is_empty() {
+        html.open("section", &[("class", "steps")]);
+        html.element("h2", NO_ATTRIBUTES, &locale.label(Label::Steps));
+    }
+    recipe.sections().iter().for_each(|section| {
+        let steps = recipe.section_steps(section);
+        if steps.is_empty() {
+            return;
+        }
+        if let Some(name) = &section.name {
+            html.element("h3", NO_ATTRIBUTES, name);
+        }
+        html.open("ol", &[("start", steps[0].number.to_string())]);
+        steps.iter().for_each(|step| {
+            html.open_inline("li", &[("data-step", step.number.to_string())]);
+            step.items.iter().for_each(|item| match item {
+                Item::Text(value) => html.text(value),
+                Item::Ingredient(index) => {
+                    let specifier = &metadata.ingredients_specifiers[*index];
+                    let ingredient = metadata
+                        .ingredients
+                        .get_index_of(specifier.ingredient_key())
+                        .unwrap();
+                    let mut attributes = vec![
+                        ("class", "ingredient".to_string()),
+                        ("data-ingredient", ingredient.to_string()),
+                        ("data-specifier", index.to_string()),
+                    ];
+                    if let Some(quantity) = specifier.quantity() {
+                        let quantity = localized_quantity(quantities, locale, &quantity);
+                        attributes.push(("data-quantity", quantity));
+                    }
+                    if let Some(note) = &specifier.note {
+                        attributes.push(("data-note", note.clone()));
+                    }
+                    html.element_inline("span", &attributes, &specifier.ingredient);
+                }
+                Item::Cookware(index) => html.element_inline(
+                    "span",
+                    &[("class", "cookware"), ("data-cookware", &index.to_string())],
+                    &metadata.cookware[*index],
+                ),
+                Item::Timer(index) => {
+                    let timer = &metadata.timer[*index];
+                    let mut attributes = vec![
+                        ("class", "timer".to_string()),
+                        ("data-timer", index.to_string()),
+                    ];
+                    if let Ok(duration) = timer.duration() {
+                        let seconds = duration.as_secs_f64().round();
+                        attributes.push(("datetime", format!("PT{}S", seconds)));
+                    }
+                    let text = isolate(locale, timer_text(quantities, locale, timer));
+                    html.element_inline("time", &attributes, &text);
+                }
+                Item::Note(note) => {
+                    html.element_inline("aside", &[("class", "note")], note);
+                }
+                Item::RecipeRef { path, amount } => {
+                    let name = path.rsplit('/').next().unwrap_or(path);
+                    let mut attributes = vec![
+                        ("class", "recipe-ref".to_string()),
+                        ("href", format!("{}.cook", path)),
+                    ];
+                    if let Some(quantity) = amount {
+                        let quantity = localized_quantity(quantities, locale, quantity);
+                        attributes.push(("data-quantity", quantity));
+                    }
+                    html.element_inline("a", &attributes, name);
+                }
+                Item::Temperature { value, unit } => html.element_inline(
+                    "span",
+                    &[("class", "temperature")],
+                    &isolate(locale, format!("{}{}", quantities.number(*value), unit)),
+                ),
+            });
+            html.close_inline("li");
+        });
+        html.close("ol");
+    });
+    if !recipe.steps().is_empty() {
+        html.close("section");
+    }
+    html.close("article");
+    html.output
+}
+
+/// The [source](Recipe::source) of the recipe as HTML, with the ingredients, cookware and timers
+/// in `<span>`s of the classes `ingredient`, `cookware` and `timer` and the `data-` attributes of
+/// [to_html]. All text is escaped and the whitespace is kept, so it's meant to be put in a
+/// `<pre>`.
+pub fn annotate_source(recipe: &Recipe) -> String {
+    let metadata = &recipe.metadata;
+    let context = LintContext::new(recipe);
+    let mut parts: Vec<(Span, Vec<(&str, String)>)> = vec![];
+    context
+        .ingredients
+        .iter()
+        .zip(&metadata.ingredients_specifiers)
+        .enumerate()
+        .for_each(|(index, (span, specifier))| {
+            let mut attributes = vec![("class", "ingredient".to_string())];
+            let key = specifier.ingredient_key();
+            if let Some(ingredient) = metadata.ingredients.get_index_of(key) {
+                attributes.push(("data-ingredient", ingredient.to_string()));
+            }
+            attributes.push(("data-specifier", index.to_string()));
+            parts.push((*span, attributes));
+        });
+    context
+        .cookware
+        .iter()
+        .enumerate()
+        .for_each(|(index, span)| {
+            let attributes = vec![
+                ("class", "cookware".to_string()),
+                ("data-cookware", index.to_string()),
+            ];
+            parts.push((*span, attributes));
+        });
+    context.timers.iter().enumerate().for_each(|(index, span)| {
+        let attributes = vec![
+            ("class", "timer".to_string()),
+            ("data-timer", index.to_string()),
+        ];
+        parts.push((*span, attributes));
+    });
+    parts.sort_by_key(|(span, _)| span.start);
+
+    let source = &recipe.source;
+    let mut html = Html::default();
+    let mut position = 0;
+    parts.iter().for_each(|(span, attributes)| {
+        html.text(&source[position..span.start]);
+        html.element_inline("span", attributes, &source[span.start..span.end]);
+        position = span.end;
+    });
+    html.text(&source[position..]);
+    html.output
+}
+
+pub(crate) const NO_ATTRIBUTES: &[(&str, &str)] = &[];
+
+/// Builds HTML with escaped text and attributes. Block elements are put on their own lines.
+#[derive(Default)]
+pub(crate) struct Html {
+    pub(crate) output: String,
+}
+
+impl Html {
+    pub(crate) fn open<V: AsRef<str>>(&mut self, tag: &str, attributes: &[(&str, V)]) {
+        self.open_inline(tag, attributes);
+        self.output.push('\n');
+    }
+
+    pub(crate) fn open_inline<V: AsRef<str>>(&mut self, tag: &str, attributes: &[(&str, V)]) {
+        self.output.push('<');
+        self.output.push_str(tag);
+        attributes.iter().for_each(|(name, value)| {
+            self.output.push(' ');
+            self.output.push_str(name);
+            self.output.push_str("=\"");
+            self.text(value.as_ref());
+            self.output.push('"');
+        });
+        self.output.push('>');
+    }
+
+    pub(crate) fn close(&mut self, tag: &str) {
+        self.close_inline(tag);
+        self.output.push('\n');
+    }
+
+    pub(crate) fn close_inline(&mut self, tag: &str) {
+        self.output.push_str("</");
+        self.output.push_str(tag);
+        self.output.push('>');
+    }
+
+    pub(crate) fn element<V: AsRef<str>>(
+        &mut self,
+        tag: &str,
+        attributes: &[(&str, V)],
+        text: &str,
+    ) {
+        self.element_inline(tag, attributes, text);
+        self.output.push('\n');
+    }
+
+    pub(crate) fn element_inline<V: AsRef<str>>(
+        &mut self,
+        tag: &str,
+        attributes: &[(&str, V)],
+        text: &str,
+    ) {
+        self.open_inline(tag, attributes);
+        self.text(text);
+        self.close_inline(tag);
+    }
+
+    pub(crate) fn text(&mut self, text: &str) {
+        text.chars().for_each(|char| match char {
+            '&' => self.output.push_str("&amp;"),
+            '<' => self.output.push_str("&lt;"),
+            '>' => self.output.push_str("&gt;"),
+            '"' => self.output.push_str("&quot;"),
+            '\'' => self.output.push_str("&#39;"),
+            char => self.output.push(char),
+        });
+    }
+}
+
+/// Plain text of an ingredient, like `125 g flour (sifted)`.
+pub(crate) fn ingredient_text(
+    recipe: &Recipe,
+    item: &ShoppingItem,
+    quantities: &QuantityFormatter,
+) -> String {
+    localized_ingredient_text(recipe, item, quantities, &English)
+}
+
+/// Plain text of an ingredient like [ingredient_text], with the units named by the locale.
+fn localized_ingredient_text(
+    recipe: &Recipe,
+    item: &ShoppingItem,
+    quantities: &QuantityFormatter,
+    locale: &dyn Locale,
+) -> String {
+    let mut text = String::new();
+    let written: Vec<String> = item
+        .quantities
+        .quantities
+        .iter()
+        .map(|quantity| localized_quantity(quantities, locale, quantity))
+        .collect();
+    if !written.is_empty() {
+        text += &format!("{} ", isolate(locale, written.join(" + ")));
+    }
+    text += &item.name;
+    let notes = &recipe.metadata.ingredients[&item.name].notes;
+    if !notes.is_empty() {
+        text += &format!(" ({})", notes.join(", "));
+    }
+    text
+}
+
+/// Plain text of a step. Notes are left out.
+pub(crate) fn step_text(recipe: &Recipe, step: &Step) -> String {
+    step_text_with(recipe, step, &QuantityFormatter::default())
+}
+
+/// Plain text of a step like [step_text], with the numbers written by the formatter.
+pub(crate) fn step_text_with(
+    recipe: &Recipe,
+    step: &Step,
+    quantities: &QuantityFormatter,
+) -> String {
+    let metadata = &recipe.metadata;
+    let mut text = String::new();
+    step.items.iter().for_each(|item| match item {
+        Item::Text(value) => text += value,
+        Item::Ingredient(index) => text += &metadata.ingredients_specifiers[*index].ingredient,
+        Item::Cookware(index) => text += &metadata.cookware[*index],
+        Item::Timer(index) => {
+            text += &timer_text(quantities, &English, &metadata.timer[*index]);
+        }
+        Item::Note(_) => {}
+        Item::RecipeRef { path, .. } => text += path.rsplit('/').next().unwrap_or(path),
+        Item::Temperature { value, unit } => {
+            text += &format!("{}{}", quantities.number(*value), unit)
+        }
+    });
+    text.trim().to_string()
+}
+
+/// The recipe as plain text like its [Display](fmt::Display), with the amounts written by the
+/// formatter.
+pub fn to_text_with(recipe: &Recipe, quantities: &QuantityFormatter) -> String {
+    let mut text = String::new();
+    write_text(recipe, quantities, &mut text).expect("Writing to a String doesn't fail");
+    text
+}
+
+impl fmt::Display for Recipe {
+    /// The title, the ingredients, the cookware and the numbered steps as plain text.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_text(self, &QuantityFormatter::default(), f)
+    }
+}
+
+/// Write the recipe as plain text, see [to_text_with].
+fn write_text(
+    recipe: &Recipe,
+    quantities: &QuantityFormatter,
+    f: &mut impl fmt::Write,
+) -> fmt::Result {
+    let metadata = &recipe.metadata;
+    if let Some(title) = metadata.title() {
+        writeln!(f, "{}\n", title)?;
+    }
+    let list = ShoppingList::from_recipes(vec![recipe]);
+    if !list.is_empty() {
+        writeln!(f, "Ingredients:")?;
+        for item in list.items.values() {
+            let text = ingredient_text(recipe, item, quantities);
+            writeln!(f, "- {}", text)?;
+        }
+        writeln!(f)?;
+    }
+    let equipment = recipe.equipment_list();
+    if !equipment.is_empty() {
+        writeln!(f, "Cookware:")?;
+        for equipment in equipment {
+            writeln!(f, "- {}", equipment)?;
+        }
+        writeln!(f)?;
+    }
+    for (index, section) in recipe.sections().iter().enumerate() {
+        if let Some(name) = &section.name {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}:", name)?;
+        }
+        for step in recipe.section_steps(section) {
+            writeln!(f, "{}. {}", step.number, step_text_with(recipe, step, quantities))?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Amount {
+    /// Numbers with up to two decimals, like `1.5`, `2 per serving` or `1|2`. See
+    /// [QuantityFormatter] for other ways to write them.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&QuantityFormatter::default().amount(self))
+    }
+}
+
+impl fmt::Display for Quantity {
+    /// The amount followed by the unit, like `125 g`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&QuantityFormatter::default().quantity(self))
+    }
+}
+
+impl fmt::Display for Timer {
+    /// The amount followed by the unit, like `10 minutes` or `1-2 hours`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&timer_text(&QuantityFormatter::default(), &English, self))
+    }
+}
+
+/// The amount or range followed by the unit named by the locale, like `1-2 hours`.
+fn timer_text(quantities: &QuantityFormatter, locale: &dyn Locale, timer: &Timer) -> String {
+    let unit = locale.unit(&timer.unit);
+    match timer.max {
+        Some(max) => format!(
+            "{}-{} {}",
+            quantities.number(timer.amount),
+            quantities.number(max),
+            unit
+        ),
+        None => format!("{} {}", quantities.number(timer.amount), unit),
+    }
+}
+
+/// The amount followed by the unit named by the locale, like `125 g`.
+fn localized_quantity(
+    quantities: &QuantityFormatter,
+    locale: &dyn Locale,
+    quantity: &Quantity,
+) -> String {
+    let amount = quantities.amount(&quantity.amount);
+    match &quantity.unit {
+        Some(unit) => format!("{} {}", amount, locale.unit(unit)),
+        None => amount,
+    }
+}
+
+impl fmt::Display for Ingredient {
+    /// The quantities, the name and the notes, like `125 g + 1 tbsp flour (sifted)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quantities: Vec<String> = self
+            .quantities
+            .quantities
+            .iter()
+            .map(Quantity::to_string)
+            .collect();
+        if !quantities.is_empty() {
+            write!(f, "{} ", quantities.join(" + "))?;
+        }
+        write!(f, "{}", self.name)?;
+        if !self.notes.is_empty() {
+            write!(f, " ({})", self.notes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::{Direction, Label, Locale};
+    use crate::render::{
+        annotate_source, to_html, to_html_localized, to_html_with, to_markdown,
+        to_markdown_localized, to_text_with, RenderOptions,
+    };
+    use crate::{parse, parse_with, ParseOptions, QuantityFormatter};
+
+    #[test]
+    fn markdown() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let source = "\
+---
+title: Pancakes
+---
+>> servings: 2|4
+Mix @flour{125%g} and @milk{1/4%l}(cold) in a #bowl.
+> Don't overmix.
+
+== Frying ==
+Fry in a #pan for ~{2%minutes}, top with @?syrup and @flour{1%tbsp}.
+";
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        let markdown = to_markdown(&recipe, &RenderOptions::default());
+        assert_eq!(
+            markdown,
+            "\
+# Pancakes
+
+| Key | Value |
+|-----|-------|
+| servings | 2\\|4 |
+
+## Ingredients
+
+- 125 g + 1 tbsp flour
+- 0.25 l milk (cold)
+- syrup *(optional)*
+
+## Cookware
+
+- bowl
+- pan
+
+## Steps
+
+1. Mix **flour** (125 g) and **milk** (0.25 l, cold) in a *bowl*.
+
+   > Don't overmix.
+
+### Frying
+
+2. Fry in a *pan* for **2 minutes**, top with **syrup** and **flour** (1 tbsp).
+"
+        );
+
+        let options = RenderOptions {
+            metadata: false,
+            ingredients: false,
+            cookware: false,
+            step_amounts: false,
+            quantities: QuantityFormatter::default(),
+            bakers_percentages: None,
+        };
+        let markdown = to_markdown(&recipe, &options);
+        assert!(markdown
+            .starts_with("# Pancakes\n\n## Steps\n\n1. Mix **flour** and **milk** (cold) in"));
+    }
+
+    #[test]
+    fn html() {
+        let source = "\
+Mix @flour{125%g} & @salt(fine) in a #bowl.
+> Use <b>cold</b> \"milk\".
+
+== Frying ==
+Fry for ~{1/2%hour}, add @flour{1%tbsp}.
+";
+        let recipe = parse_with(source, &ParseOptions::default()).unwrap().recipe;
+        let html = to_html(&recipe);
+        assert!(html.starts_with("<article class=\"recipe\">\n<section class=\"ingredients\">"));
+        assert!(html.contains(
+            "<li data-ingredient=\"0\"><span class=\"quantity\">125 g + 1 tbsp</span> \
+             <span class=\"name\">flour</span></li>"
+        ));
+        assert!(html.contains("<ol start=\"1\">\n<li data-step=\"1\">Mix <span class=\"ingredient\" \
+             data-ingredient=\"0\" data-specifier=\"0\" data-quantity=\"125 g\">flour</span> &amp; "));
+        assert!(html.contains("data-specifier=\"1\" data-note=\"fine\">salt</span>"));
+        assert!(html.contains(
+            "<aside class=\"note\">Use &lt;b&gt;cold&lt;/b&gt; &quot;milk&quot;.</aside>"
+        ));
+        assert!(html.contains("<h3>Frying</h3>\n<ol start=\"2\">"));
+        assert!(html.contains(
+            "<time class=\"timer\" data-timer=\"0\" datetime=\"PT1800S\">0.5 hour</time>"
+        ));
+        let quantities = QuantityFormatter {
+            fractions: true,
+            ..QuantityFormatter::default()
+        };
+        assert!(to_html_with(&recipe, &quantities).contains("PT1800S\">½ hour</time>"));
+        assert!(html.ends_with("</ol>\n</section>\n</article>\n"));
+    }
+
+    #[test]
+    fn annotated_source() {
+        let source = "// <b>\nMix @flour{125%g} & @salt in a #mixing bowl{} for ~{2%min}.\n\
+                      Add @flour{1%tbsp}.\n";
+        let recipe = parse(source).unwrap();
+        assert_eq!(
+            annotate_source(&recipe),
+            "\
+// &lt;b&gt;
+Mix <span class=\"ingredient\" data-ingredient=\"0\" data-specifier=\"0\">@flour{125%g}</span> \
+&amp; <span class=\"ingredient\" data-ingredient=\"1\" data-specifier=\"1\">@salt</span> in a \
+<span class=\"cookware\" data-cookware=\"0\">#mixing bowl{}</span> for \
+<span class=\"timer\" data-timer=\"0\">~{2%min}</span>.
+Add <span class=\"ingredient\" data-ingredient=\"0\" data-specifier=\"2\">@flour{1%tbsp}</span>.
+"
+        );
+    }
+
+    #[test]
+    fn localized() {
+        struct Hebrew;
+
+        impl Locale for Hebrew {
+            fn language(&self) -> &str {
+                "he"
+            }
+
+            fn direction(&self) -> Direction {
+                Direction::RightToLeft
+            }
+
+            fn label(&self, label: Label) -> String {
+                match label {
+                    Label::Ingredients => "מצרכים".to_string(),
+                    label => format!("{:?}", label),
+                }
+            }
+
+            fn unit(&self, unit: &str) -> String {
+                match unit {
+                    "g" => "גרם".to_string(),
+                    unit => unit.to_string(),
+                }
+            }
+        }
+
+        let recipe = parse("Mix @flour{125%g} for ~{2%minutes}.\n").unwrap();
+        let options = RenderOptions {
+            metadata: false,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            to_markdown_localized(&recipe, &options, &Hebrew),
+            "\
+## מצרכים
+
+- \u{2066}125 גרם\u{2069} flour
+
+## Steps
+
+1. Mix **flour** (\u{2066}125 גרם\u{2069}) for **\u{2066}2 minutes\u{2069}**.
+"
+        );
+        let html = to_html_localized(&recipe, &QuantityFormatter::default(), &Hebrew);
+        assert!(html.starts_with("<article class=\"recipe\" lang=\"he\" dir=\"rtl\">\n"));
+        assert!(html.contains("<h2>מצרכים</h2>"));
+        assert!(html.contains("data-quantity=\"125 גרם\">flour</span>"));
+    }
+
+    #[test]
+    fn display() {
+        let recipe = parse(
+            "\
+---
+title: Pancakes
+---
+Mix @flour{125%g}(sifted) and @milk{1/4%l} in a #bowl.
+
+== Frying ==
+Fry in a #pan for ~{2%minutes}, then add @flour{1%tbsp}.
+",
+        )
+        .unwrap();
+        assert_eq!(
+            recipe.to_string(),
+            "\
+Pancakes
+
+Ingredients:
+- 125 g + 1 tbsp flour (sifted)
+- 0.25 l milk
+
+Cookware:
+- bowl
+- pan
+
+1. Mix flour and milk in a bowl.
+
+Frying:
+2. Fry in a pan for 2 minutes, then add flour.
+"
+        );
+        let metadata = &recipe.metadata;
+        assert_eq!(metadata.ingredients["milk"].to_string(), "0.25 l milk");
+        assert_eq!(metadata.timer[0].to_string(), "2 minutes");
+        let quantities = QuantityFormatter {
+            fractions: true,
+            ..QuantityFormatter::default()
+        };
+        assert!(to_text_with(&recipe, &quantities).contains("- ¼ l milk\n"));
+    }
+}
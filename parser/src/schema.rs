@@ -0,0 +1,41 @@
+//! JSON Schema of the serialized [Recipe].
+
+use crate::Recipe;
+use schemars::{schema_for, Schema};
+
+impl Recipe {
+    /// The JSON Schema of the serialized recipe, e.g. to generate typed clients in other
+    /// languages. Requires the `schemars` feature.
+    ///
+    /// The schema describes the default serialization, with field names like
+    /// `servings_labels`.
+    pub fn json_schema() -> Schema {
+        schema_for!(Recipe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Recipe};
+
+    #[test]
+    fn json_schema() {
+        let schema = serde_json::to_value(Recipe::json_schema()).unwrap();
+        assert_eq!(schema["title"], "Recipe");
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["source", "metadata", "instruction"])
+        );
+        let metadata = &schema["$defs"]["Metadata"]["properties"];
+        assert_eq!(metadata["ingredients"]["type"], "object");
+        assert_eq!(schema["$defs"]["MetadataMap"]["type"], "array");
+
+        let recipe = serde_json::to_value(parse("Add @salt{1%pinch}.\n").unwrap()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        assert!(recipe
+            .as_object()
+            .unwrap()
+            .keys()
+            .all(|key| properties.contains_key(key)));
+    }
+}
@@ -0,0 +1,214 @@
+//! Full-text search over recipes.
+//!
+//! A [SearchIndex] is an inverted index of the words of the title, the tags and other metadata,
+//! the ingredient and cookware names and the steps of every recipe. Words are compared like
+//! ingredient names by the default [NameNormalizer], so `Lemons` finds `lemon`.
+//!
+//! Recipes matching any word of the query are found. They are ranked by how rare the matching
+//! words are across the recipes and where they appear: a word of the title counts the most,
+//! followed by tags and ingredients, while words of the steps and other metadata count the least.
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::search::SearchIndex;
+//!
+//! let recipes = vec![
+//!     parse(">> title: Lemon tart\nBake the @pastry with @lemons{3}.\n").unwrap(),
+//!     parse(">> title: Roast chicken\nRoast the @chicken with a @lemon{1}.\n").unwrap(),
+//! ];
+//! let index = SearchIndex::from_recipes(&recipes);
+//! let hits: Vec<usize> = index.search("chicken lemon").iter().map(|hit| hit.recipe).collect();
+//! assert_eq!(hits, vec![1, 0]);
+//! ```
+
+use crate::names::NameNormalizer;
+use crate::render::step_text;
+use crate::{Collection, CollectionRecipe, Recipe};
+use std::collections::HashMap;
+
+/// Weight of a word of the title.
+const TITLE: f64 = 4.0;
+/// Weight of a tag or the name of an ingredient.
+const NAME: f64 = 2.0;
+/// Weight of a word of the steps, the cookware and other metadata.
+const TEXT: f64 = 1.0;
+
+/// Inverted index of recipes. See the [module](crate::search).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchIndex {
+    /// Recipes containing the word with the weighted number of occurrences, by normalized word.
+    words: HashMap<String, Vec<(usize, f64)>>,
+    /// Number of indexed recipes.
+    recipes: usize,
+    names: NameNormalizer,
+}
+
+/// A recipe found by [SearchIndex::search].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchHit {
+    /// Index of the recipe in the indexed recipes.
+    pub recipe: usize,
+    /// Relevance of the recipe, higher is better.
+    pub score: f64,
+}
+
+impl SearchIndex {
+    /// Index the recipes.
+    pub fn from_recipes<'a, I>(recipes: I) -> SearchIndex
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        let mut index = SearchIndex::default();
+        recipes.into_iter().for_each(|recipe| index.add(recipe));
+        index
+    }
+
+    /// Add a recipe with the next index.
+    fn add(&mut self, recipe: &Recipe) {
+        let metadata = &recipe.metadata;
+        let mut counts: HashMap<String, f64> = HashMap::new();
+        let mut count = |text: &str, weight: f64| {
+            self.names.words(text).for_each(|word| {
+                *counts.entry(word).or_default() += weight;
+            })
+        };
+        if let Some(title) = recipe.title() {
+            count(title, TITLE);
+        }
+        metadata.tags().iter().for_each(|tag| count(tag, NAME));
+        metadata
+            .entries
+            .iter()
+            .filter(|(key, _)| !["title", "tags"].contains(key))
+            .for_each(|(_, value)| count(value, TEXT));
+        let ingredients = metadata.ingredients.values();
+        ingredients.for_each(|ingredient| count(&ingredient.name, NAME));
+        metadata
+            .cookware
+            .iter()
+            .for_each(|cookware| count(cookware, TEXT));
+        recipe
+            .steps
+            .iter()
+            .for_each(|step| count(&step_text(recipe, step), TEXT));
+
+        let number = self.recipes;
+        counts.into_iter().for_each(|(word, count)| {
+            self.words.entry(word).or_default().push((number, count));
+        });
+        self.recipes += 1;
+    }
+
+    /// The recipes matching any word of the query, the most relevant first. Recipes with the
+    /// same score keep their order.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let mut words: Vec<String> = self.names.words(query).collect();
+        words.sort();
+        words.dedup();
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        words
+            .iter()
+            .filter_map(|word| self.words.get(word))
+            .for_each(|recipes| {
+                let rarity = (1.0 + self.recipes as f64 / recipes.len() as f64).ln();
+                recipes.iter().for_each(|(recipe, count)| {
+                    *scores.entry(*recipe).or_default() += rarity * (1.0 + count.ln());
+                });
+            });
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(recipe, score)| SearchHit { recipe, score })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.recipe.cmp(&b.recipe))
+        });
+        hits
+    }
+}
+
+impl NameNormalizer {
+    /// The normalized words of the text.
+    fn words<'a>(&'a self, text: &'a str) -> impl Iterator<Item = String> + 'a {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(move |word| self.normalize(word))
+    }
+}
+
+impl Collection {
+    /// Index the recipes of the collection for [SearchIndex::search]. The indices of the hits
+    /// point into [Collection::recipes].
+    pub fn search_index(&self) -> SearchIndex {
+        SearchIndex::from_recipes(self.recipes.iter().map(|recipe| &recipe.recipe))
+    }
+
+    /// The recipes matching any word of the query, the most relevant first. Use
+    /// [Collection::search_index] to search more than once.
+    pub fn search(&self, query: &str) -> Vec<&CollectionRecipe> {
+        self.search_index()
+            .search(query)
+            .iter()
+            .map(|hit| &self.recipes[hit.recipe])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::SearchIndex;
+    use crate::{parse, Collection, CollectionRecipe};
+    use std::path::PathBuf;
+
+    #[test]
+    fn search() {
+        let sources = [
+            (
+                "Lemonade.cook",
+                "Squeeze the @lemons{4} and mix with @water{1%l}.\n",
+            ),
+            (
+                "Chicken.cook",
+                "---\ntags: [dinner]\n---\nRoast the @chicken{1} with @lemon{1}.\n",
+            ),
+            (
+                "Salad.cook",
+                ">> title: Chicken salad\nToss the @chicken{200%g}.\n",
+            ),
+            ("Soup.cook", "Boil the @carrots{3}. Serve for dinner.\n"),
+        ];
+        let mut collection = Collection::default();
+        sources.iter().for_each(|(path, source)| {
+            let mut recipe = parse(source).unwrap();
+            recipe.path = Some(PathBuf::from(path));
+            collection.recipes.push(CollectionRecipe {
+                path: PathBuf::from(path),
+                recipe,
+                image: None,
+                step_images: vec![],
+                references: vec![],
+            });
+        });
+        let titles = |query: &str| -> Vec<&str> {
+            let hits = collection.search(query);
+            hits.into_iter().map(CollectionRecipe::title).collect()
+        };
+        assert_eq!(
+            titles("chicken lemon"),
+            vec!["Chicken", "Chicken salad", "Lemonade"]
+        );
+        assert_eq!(titles("Lemons"), vec!["Lemonade", "Chicken"]);
+        assert_eq!(titles("dinner"), vec!["Chicken", "Soup"]);
+        assert!(titles("pasta").is_empty());
+        assert!(titles(" ").is_empty());
+
+        let index = collection.search_index();
+        let hits = index.search("chicken");
+        // Same score, in the order of the collection.
+        assert_eq!((hits[0].recipe, hits[1].recipe), (1, 2));
+        assert_eq!(hits[0].score, hits[1].score);
+        assert_eq!(SearchIndex::from_recipes(vec![]).search("chicken"), vec![]);
+    }
+}
@@ -0,0 +1,252 @@
+//! State of guided cooking along a recipe.
+//!
+//! A [CookSession] walks through the [Step]s of a [Recipe], keeps track of the ingredients which
+//! have been checked off and of the started timers. Its [SessionState] can be serialized, so a
+//! cooking app can resume the session after a restart with [CookSession::resume].
+//!
+//! Timers are started and read with the current time passed in, usually [SystemTime::now].
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::session::CookSession;
+//! use std::time::{Duration, SystemTime};
+//!
+//! let recipe = parse("Boil the @eggs{2} for ~{6%minutes}.\n\nPeel them.\n").unwrap();
+//! let mut session = CookSession::new(recipe);
+//! session.check_ingredient("eggs");
+//! let start = SystemTime::now();
+//! session.start_timer(0, start).unwrap();
+//! let later = start + Duration::from_secs(60);
+//! assert_eq!(session.remaining(0, later), Some(Duration::from_secs(300)));
+//! assert_eq!(session.next_step().unwrap().number, 2);
+//! ```
+
+use crate::steps::Step;
+use crate::units::UnitError;
+use crate::Recipe;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// A recipe being cooked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookSession {
+    recipe: Recipe,
+    state: SessionState,
+}
+
+/// The progress of a [CookSession], to save and resume it.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SessionState {
+    /// Index of the current step in [Recipe::steps]. The number of steps once all are done.
+    pub step: usize,
+    /// Names of the ingredients which have been checked off, in order.
+    pub checked: Vec<String>,
+    /// The started timers, in the order they were started.
+    pub timers: Vec<StartedTimer>,
+}
+
+/// A timer which has been started.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StartedTimer {
+    /// Index of the timer in [Metadata::timer](crate::Metadata::timer).
+    pub timer: usize,
+    /// When the timer was started.
+    pub started: SystemTime,
+    /// How long the timer runs.
+    pub duration: Duration,
+}
+
+impl StartedTimer {
+    /// Time left at `now`, zero once the timer has run out.
+    pub fn remaining(&self, now: SystemTime) -> Duration {
+        let elapsed = now.duration_since(self.started).unwrap_or_default();
+        self.duration.saturating_sub(elapsed)
+    }
+}
+
+impl CookSession {
+    /// Start cooking the recipe at its first step.
+    pub fn new(recipe: Recipe) -> CookSession {
+        CookSession::resume(recipe, SessionState::default())
+    }
+
+    /// Continue cooking the recipe with a saved state. A step beyond the end of the recipe is
+    /// treated as finished.
+    pub fn resume(recipe: Recipe, mut state: SessionState) -> CookSession {
+        state.step = state.step.min(recipe.steps.len());
+        CookSession { recipe, state }
+    }
+
+    /// The recipe being cooked.
+    pub fn recipe(&self) -> &Recipe {
+        &self.recipe
+    }
+
+    /// The progress, to save it.
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    /// The step being cooked. [None] once all steps are done.
+    pub fn current_step(&self) -> Option<&Step> {
+        self.recipe.steps.get(self.state.step)
+    }
+
+    /// Move on to the next step and return it. [None] if the last step is done.
+    pub fn next_step(&mut self) -> Option<&Step> {
+        self.state.step = (self.state.step + 1).min(self.recipe.steps.len());
+        self.current_step()
+    }
+
+    /// Go back to the previous step and return it. Stays at the first step.
+    pub fn previous_step(&mut self) -> Option<&Step> {
+        self.state.step = self.state.step.saturating_sub(1);
+        self.current_step()
+    }
+
+    /// Go to the step with the index in [Recipe::steps] and return it.
+    pub fn go_to_step(&mut self, step: usize) -> Option<&Step> {
+        self.state.step = step.min(self.recipe.steps.len());
+        self.current_step()
+    }
+
+    /// Whether all steps are done.
+    pub fn is_finished(&self) -> bool {
+        self.state.step >= self.recipe.steps.len()
+    }
+
+    /// Check off the ingredient. Returns whether it's part of the recipe.
+    pub fn check_ingredient(&mut self, name: &str) -> bool {
+        if !self.recipe.metadata.ingredients.contains_key(name) {
+            return false;
+        }
+        if !self.is_checked(name) {
+            self.state.checked.push(name.to_string());
+        }
+        true
+    }
+
+    /// Undo checking off the ingredient.
+    pub fn uncheck_ingredient(&mut self, name: &str) {
+        self.state.checked.retain(|checked| checked != name);
+    }
+
+    /// Whether the ingredient has been checked off.
+    pub fn is_checked(&self, name: &str) -> bool {
+        self.state.checked.iter().any(|checked| checked == name)
+    }
+
+    /// Names of the ingredients which haven't been checked off, in order of the recipe.
+    pub fn unchecked_ingredients(&self) -> Vec<&str> {
+        self.recipe
+            .metadata
+            .ingredients
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !self.is_checked(name))
+            .collect()
+    }
+
+    /// Start the timer with the index in [Metadata::timer](crate::Metadata::timer) at `now`. A
+    /// running timer is started again.
+    ///
+    /// Fails if the unit of the timer isn't a known time unit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no timer with the index.
+    pub fn start_timer(&mut self, timer: usize, now: SystemTime) -> Result<(), UnitError> {
+        let duration = self.recipe.metadata.timer[timer].duration()?;
+        self.stop_timer(timer);
+        self.state.timers.push(StartedTimer {
+            timer,
+            started: now,
+            duration,
+        });
+        Ok(())
+    }
+
+    /// Stop the timer and forget about it.
+    pub fn stop_timer(&mut self, timer: usize) {
+        self.state.timers.retain(|started| started.timer != timer);
+    }
+
+    /// Time left of the timer at `now`. [None] if it hasn't been started.
+    pub fn remaining(&self, timer: usize, now: SystemTime) -> Option<Duration> {
+        self.state
+            .timers
+            .iter()
+            .find(|started| started.timer == timer)
+            .map(|started| started.remaining(now))
+    }
+
+    /// Started timers which haven't run out at `now`, with the time left.
+    pub fn running_timers(&self, now: SystemTime) -> Vec<(usize, Duration)> {
+        self.state
+            .timers
+            .iter()
+            .map(|started| (started.timer, started.remaining(now)))
+            .filter(|(_, remaining)| !remaining.is_zero())
+            .collect()
+    }
+
+    /// Started timers which have run out at `now`.
+    pub fn finished_timers(&self, now: SystemTime) -> Vec<usize> {
+        self.state
+            .timers
+            .iter()
+            .filter(|started| started.remaining(now).is_zero())
+            .map(|started| started.timer)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::session::CookSession;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn session() {
+        let recipe = parse(
+            "Soak the @rice{200%g} for ~{1/2%hour}.\n\nCook it with @water{400%ml} for \
+             ~{15%min}, rest for ~{5%min}.\n\nServe.\n",
+        )
+        .unwrap();
+        let mut session = CookSession::new(recipe.clone());
+        assert_eq!(session.current_step().unwrap().number, 1);
+        assert!(session.check_ingredient("rice"));
+        assert!(!session.check_ingredient("salt"));
+        assert_eq!(session.unchecked_ingredients(), vec!["water"]);
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        session.start_timer(0, start).unwrap();
+        session.next_step();
+        let minutes = |minutes: u64| start + Duration::from_secs(minutes * 60);
+        session.start_timer(1, minutes(20)).unwrap();
+        assert_eq!(
+            session.running_timers(minutes(31)),
+            vec![(1, Duration::from_secs(4 * 60))]
+        );
+        assert_eq!(session.finished_timers(minutes(31)), vec![0]);
+        assert_eq!(session.remaining(2, minutes(31)), None);
+
+        let state = session.state().clone();
+        #[cfg(feature = "serde")]
+        let state = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        let mut resumed = CookSession::resume(recipe, state);
+        assert_eq!(resumed, session);
+        assert_eq!(resumed.current_step().unwrap().number, 2);
+        assert_eq!(resumed.next_step().unwrap().number, 3);
+        assert!(resumed.next_step().is_none() && resumed.is_finished());
+        assert_eq!(resumed.previous_step().unwrap().number, 3);
+        resumed.stop_timer(1);
+        assert!(resumed.running_timers(minutes(31)).is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+//! Shopping lists aggregated from the ingredients of one or more [Recipe]s.
+
+use crate::aisle::AisleConf;
+use crate::names::NameNormalizer;
+use crate::pantry::Pantry;
+use crate::units::UnitRegistry;
+use crate::{Amount, GroupedQuantity, Ingredient, Quantity, Recipe};
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Ingredients needed for a set of recipes, in order of their first mentioning.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShoppingList {
+    /// Items by ingredient name.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, ShoppingItem>")
+    )]
+    pub items: IndexMap<String, ShoppingItem>,
+}
+
+/// An ingredient on the [ShoppingList].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShoppingItem {
+    /// Name of the ingredient.
+    pub name: String,
+    /// Needed quantities. Quantities with the same unit and kind of [Amount](crate::Amount) are
+    /// added up.
+    pub quantities: GroupedQuantity,
+    /// The ingredient is optional in every recipe it is used in. See
+    /// [Ingredient::optional](crate::Ingredient::optional).
+    pub optional: bool,
+    /// [Titles](crate::Recipe::title) of the recipes using the ingredient, in the order they were
+    /// added. Recipes without a title are left out.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recipes: Vec<String>,
+}
+
+/// What [ShoppingList::subtract_pantry] took off an item.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Deduction {
+    /// Name of the ingredient.
+    pub name: String,
+    /// The deducted quantities.
+    pub quantities: Vec<Quantity>,
+    /// The item is at hand completely and has been removed from the list.
+    pub removed: bool,
+}
+
+impl ShoppingList {
+    /// Create an empty shopping list.
+    pub fn new() -> ShoppingList {
+        ShoppingList::default()
+    }
+
+    /// Shopping list of the ingredients of the recipes.
+    pub fn from_recipes<'a, I>(recipes: I) -> ShoppingList
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        let mut list = ShoppingList::new();
+        recipes
+            .into_iter()
+            .for_each(|recipe| list.add_recipe(recipe));
+        list
+    }
+
+    /// Shopping list of the ingredients of the recipes, with ingredients whose names are the
+    /// same after normalizing on one item. See [ShoppingList::add_recipe_with].
+    pub fn from_recipes_with<'a, I>(recipes: I, names: &NameNormalizer) -> ShoppingList
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        let mut list = ShoppingList::new();
+        recipes
+            .into_iter()
+            .for_each(|recipe| list.add_recipe_with(recipe, names));
+        list
+    }
+
+    /// Add the ingredients of the recipe.
+    pub fn add_recipe(&mut self, recipe: &Recipe) {
+        recipe.metadata.ingredients.values().for_each(|ingredient| {
+            self.add_ingredient(ingredient.name.clone(), ingredient, recipe);
+        });
+    }
+
+    /// Add the ingredients of the recipe. An ingredient is added to the item whose name is the
+    /// same after normalizing, like `Onions` to `onion`, which keeps its name.
+    pub fn add_recipe_with(&mut self, recipe: &Recipe, names: &NameNormalizer) {
+        recipe.metadata.ingredients.values().for_each(|ingredient| {
+            let name = self
+                .items
+                .keys()
+                .find(|name| names.same(name, &ingredient.name))
+                .unwrap_or(&ingredient.name)
+                .clone();
+            self.add_ingredient(name, ingredient, recipe);
+        });
+    }
+
+    fn add_ingredient(&mut self, name: String, ingredient: &Ingredient, recipe: &Recipe) {
+        let item = self.items.entry(name).or_insert_with(|| ShoppingItem {
+            name: ingredient.name.clone(),
+            quantities: GroupedQuantity::default(),
+            optional: ingredient.optional,
+            recipes: vec![],
+        });
+        item.optional &= ingredient.optional;
+        if let Some(title) = recipe.title() {
+            if !item.recipes.iter().any(|recipe| recipe == title) {
+                item.recipes.push(title.to_string());
+            }
+        }
+        ingredient
+            .quantities
+            .quantities
+            .iter()
+            .for_each(|quantity| item.quantities.add(quantity));
+    }
+
+    /// Remove the items which are optional.
+    pub fn remove_optional(&mut self) {
+        self.items.retain(|_, item| !item.optional);
+    }
+
+    /// The shopping list without the items which are optional.
+    pub fn without_optional(mut self) -> ShoppingList {
+        self.remove_optional();
+        self
+    }
+
+    /// Remove the items which are in the pantry, or reduce them by the quantity at hand.
+    ///
+    /// Static amounts are reduced if the pantry uses the same unit, or a unit of the same kind
+    /// known to the default [UnitRegistry]. Items without quantities are removed if they are in
+    /// the pantry at all.
+    pub fn subtract_pantry(&mut self, pantry: &Pantry) -> Vec<Deduction> {
+        self.subtract_pantry_with(pantry, &UnitRegistry::default())
+    }
+
+    /// Remove the items which are in the pantry, or reduce them by the quantity at hand, using
+    /// the units of the registry. See [ShoppingList::subtract_pantry].
+    pub fn subtract_pantry_with(
+        &mut self,
+        pantry: &Pantry,
+        registry: &UnitRegistry,
+    ) -> Vec<Deduction> {
+        let mut deductions = vec![];
+        self.items.retain(|name, item| {
+            let deduction = match pantry.get(name) {
+                None => return true,
+                Some(at_hand) => match &at_hand.quantity {
+                    None => Deduction {
+                        name: name.clone(),
+                        quantities: std::mem::take(&mut item.quantities.quantities),
+                        removed: true,
+                    },
+                    Some(quantity) => item.deduct(quantity, registry),
+                },
+            };
+            let removed = deduction.removed;
+            if removed || !deduction.quantities.is_empty() {
+                deductions.push(deduction);
+            }
+            !removed
+        });
+        deductions
+    }
+
+    /// Group the items by the categories of the aisle configuration. See
+    /// [AisleConf::categorize].
+    pub fn categorize(&self, aisle: &AisleConf) -> IndexMap<String, Vec<&ShoppingItem>> {
+        aisle
+            .categorize(self.items.keys().map(String::as_str))
+            .into_iter()
+            .map(|(category, names)| {
+                let items = names.iter().map(|name| &self.items[name]).collect();
+                (category, items)
+            })
+            .collect()
+    }
+
+    /// Number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl ShoppingItem {
+    fn deduct(&mut self, at_hand: &Quantity, registry: &UnitRegistry) -> Deduction {
+        let mut left = match at_hand.amount {
+            Amount::Single(amount) => amount,
+            _ => 0.0,
+        };
+        let removed = self.quantities.quantities.is_empty();
+        let mut deducted = vec![];
+        self.quantities.quantities.iter_mut().for_each(|quantity| {
+            let needed = match quantity.amount {
+                Amount::Single(needed) => needed,
+                _ => return,
+            };
+            let available = match (&at_hand.unit, &quantity.unit) {
+                (from, to) if from == to => Some(left),
+                (Some(from), Some(to)) => registry.convert(left, from, to),
+                _ => None,
+            };
+            let available = match available {
+                Some(available) if available > 0.0 => available,
+                _ => return,
+            };
+            let taken = available.min(needed);
+            left *= 1.0 - taken / available;
+            quantity.amount = Amount::Single(needed - taken);
+            deducted.push(Quantity {
+                amount: Amount::Single(taken),
+                unit: quantity.unit.clone(),
+            });
+        });
+        self.quantities
+            .quantities
+            .retain(|quantity| quantity.amount != Amount::Single(0.0));
+        Deduction {
+            name: self.name.clone(),
+            quantities: deducted,
+            removed: removed || self.quantities.quantities.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aisle::AisleConf;
+    use crate::names::NameNormalizer;
+    use crate::pantry::Pantry;
+    use crate::shopping::{Deduction, ShoppingList};
+    use crate::units::UnitRegistry;
+    use crate::{parse_with, Amount, ParseOptions, Quantity, Recipe};
+
+    fn recipe(source: &str) -> Recipe {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        parse_with(source, &options).unwrap().recipe
+    }
+
+    #[test]
+    fn optional_ingredients() {
+        let first = recipe("Cook @pasta{200%g}, garnish with @?parsley{1%bunch} and @?salt.\n");
+        let second = recipe("Add @pasta{100%g} and @pasta{1%pack}, season with @salt.\n");
+        assert!(first.metadata.ingredients["parsley"].optional);
+        assert!(!second.metadata.ingredients["salt"].optional);
+
+        let list = ShoppingList::from_recipes(vec![&first, &second]);
+        assert_eq!(list.len(), 3);
+        assert!(list.items["parsley"].optional);
+        assert!(!list.items["salt"].optional);
+        let pasta = &list.items["pasta"].quantities.quantities;
+        assert_eq!(pasta.len(), 2);
+        assert!(matches!(pasta[0].amount, Amount::Single(a) if a == 300.0));
+
+        let list = list.without_optional();
+        assert_eq!(list.items.keys().collect::<Vec<_>>(), vec!["pasta", "salt"]);
+
+        let aisle = AisleConf::parse("[spices]\nsalt\n").unwrap();
+        let categories = list.categorize(&aisle);
+        assert_eq!(categories["spices"][0].name, "salt");
+        assert_eq!(categories["other"][0].name, "pasta");
+    }
+
+    #[test]
+    fn normalized_names() {
+        let mut names = NameNormalizer::default();
+        names.add_synonym("scallion", "green onion");
+        let options = ParseOptions {
+            names: Some(names.clone()),
+            ..ParseOptions::default()
+        };
+        let source = "Chop the @Onions{2} and @onion{1}, add @tomatoes{400%g}.\n";
+        let first = parse_with(source, &options).unwrap().recipe;
+        let names_in = |recipe: &Recipe| recipe.metadata.ingredients.keys().cloned().collect();
+        let ingredients: Vec<String> = names_in(&first);
+        assert_eq!(ingredients, vec!["Onions", "tomatoes"]);
+        assert_eq!(
+            first.metadata.ingredients_specifiers[1].ingredient,
+            "Onions"
+        );
+        assert_eq!(
+            first.metadata.ingredients["Onions"].amount,
+            Some(Amount::Single(3.0))
+        );
+
+        let second = recipe("Fry @onion{1}, @tomato{100%g} and @scallions{2}.\n");
+        let third = recipe("Top with @green onions{1}.\n");
+        let list = ShoppingList::from_recipes_with(vec![&first, &second, &third], &names);
+        let items: Vec<&str> = list.items.keys().map(String::as_str).collect();
+        assert_eq!(items, vec!["Onions", "tomatoes", "scallions"]);
+        let quantities = |name: &str| list.items[name].quantities.quantities.clone();
+        assert_eq!(quantities("Onions")[0].amount, Amount::Single(4.0));
+        assert_eq!(quantities("tomatoes")[0].amount, Amount::Single(500.0));
+        assert_eq!(quantities("scallions")[0].amount, Amount::Single(3.0));
+        assert_eq!(ShoppingList::from_recipes(vec![&first, &second]).len(), 5);
+    }
+
+    #[test]
+    fn subtract_pantry() {
+        let recipe = recipe(
+            "Mix @flour{500%g}, @milk{300%ml}, @eggs{3} and @sugar{1%cup} with @salt and @butter.\n",
+        );
+        let pantry =
+            Pantry::parse("[shelf]\nflour = 1%kg\nmilk = 0.1%l\nsalt\nbutter = 1\n").unwrap();
+        let mut list = ShoppingList::from_recipes(vec![&recipe]);
+        let deductions = list.subtract_pantry(&pantry);
+        let single = |amount: f64, unit: &str| Quantity {
+            amount: Amount::Single(amount),
+            unit: Some(unit.to_string()),
+        };
+        assert_eq!(
+            deductions,
+            vec![
+                Deduction {
+                    name: "flour".to_string(),
+                    quantities: vec![single(500.0, "g")],
+                    removed: true,
+                },
+                Deduction {
+                    name: "milk".to_string(),
+                    quantities: vec![single(100.0, "ml")],
+                    removed: false,
+                },
+                Deduction {
+                    name: "salt".to_string(),
+                    quantities: vec![],
+                    removed: true,
+                },
+                Deduction {
+                    name: "butter".to_string(),
+                    quantities: vec![],
+                    removed: true,
+                },
+            ]
+        );
+        assert_eq!(
+            list.items.keys().collect::<Vec<_>>(),
+            vec!["milk", "eggs", "sugar"]
+        );
+        assert_eq!(
+            list.items["milk"].quantities.quantities,
+            vec![single(200.0, "ml")]
+        );
+
+        let pantry = Pantry::parse("sugar = 2%Becher\n").unwrap();
+        let mut registry = UnitRegistry::default();
+        assert!(list.clone().subtract_pantry(&pantry).is_empty());
+        registry.add_alias("Becher", "cup");
+        list.subtract_pantry_with(&pantry, &registry);
+        assert_eq!(list.items.keys().collect::<Vec<_>>(), vec!["milk", "eggs"]);
+    }
+}
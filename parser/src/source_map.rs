@@ -0,0 +1,220 @@
+//! Mapping of the [instruction](crate::Recipe::instruction) and the [Step]s of a parsed
+//! [Recipe](crate::Recipe) back to its [source](crate::Recipe::source), e.g. to show errors in an
+//! editor or jump from a rendered step to its source.
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//!
+//! let source = ">> servings: 2\nBoil the @eggs{2} in a #pot.\n";
+//! let recipe = parse(source).unwrap();
+//! let map = recipe.source_map.as_ref().unwrap();
+//! let step = map.step_span(&recipe.steps[0]).unwrap();
+//! assert_eq!(&source[step.start..step.end], "Boil the @eggs{2} in a #pot.");
+//! let eggs = map.item_span(&recipe.steps[0].items[1]).unwrap();
+//! assert_eq!(&source[eggs.start..eggs.end], "@eggs{2}");
+//! ```
+
+use crate::diagnostics::Span;
+use crate::layout::MentionKind;
+use crate::{Item, Step};
+use std::ops::Range;
+
+/// Where the parts of the instruction come from. See the [module](crate::source_map).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Pieces of the instruction in order, with the part of the source they come from.
+    segments: Vec<Segment>,
+    /// Spans of the mentions, in the order of
+    /// [Metadata::ingredients_specifiers](crate::Metadata::ingredients_specifiers).
+    ingredients: Vec<Span>,
+    /// Spans of the mentions, in the order of [Metadata::cookware](crate::Metadata::cookware).
+    cookware: Vec<Span>,
+    /// Spans of the mentions, in the order of [Metadata::timer](crate::Metadata::timer).
+    timers: Vec<Span>,
+}
+
+/// A piece of the instruction. Pieces copied from the source have the same length, others like
+/// the `@` of an ingredient stand for the whole source range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    instruction: Range<usize>,
+    source: Range<usize>,
+}
+
+impl Segment {
+    fn copied(&self) -> bool {
+        self.instruction.len() == self.source.len()
+    }
+}
+
+impl SourceMap {
+    /// Push the text standing for the source range to the output, which starts at `offset` in
+    /// the instruction.
+    pub(crate) fn push(
+        &mut self,
+        output: &mut String,
+        offset: usize,
+        text: &str,
+        source: Range<usize>,
+    ) {
+        let start = offset + output.len();
+        output.push_str(text);
+        if text.is_empty() {
+            return;
+        }
+        let segment = Segment {
+            instruction: start..start + text.len(),
+            source,
+        };
+        match self.segments.last_mut() {
+            Some(last)
+                if last.copied()
+                    && segment.copied()
+                    && last.instruction.end == segment.instruction.start
+                    && last.source.end == segment.source.start =>
+            {
+                last.instruction.end = segment.instruction.end;
+                last.source.end = segment.source.end;
+            }
+            _ => self.segments.push(segment),
+        }
+    }
+
+    /// Copy the source range to the output, see [SourceMap::push].
+    pub(crate) fn copy(
+        &mut self,
+        output: &mut String,
+        offset: usize,
+        source: &str,
+        range: Range<usize>,
+    ) {
+        self.push(output, offset, &source[range.clone()], range);
+    }
+
+    /// Set the spans of the mentions, in the order of the source.
+    pub(crate) fn set_mentions(&mut self, mentions: &[(MentionKind, Span)]) {
+        mentions.iter().for_each(|(kind, span)| match kind {
+            MentionKind::Ingredient => self.ingredients.push(*span),
+            MentionKind::Cookware => self.cookware.push(*span),
+            MentionKind::Timer => self.timers.push(*span),
+        });
+    }
+
+    /// The source of a byte range of the [instruction](crate::Recipe::instruction). An empty
+    /// range gives the position in the source. [None] if the range is outside of the instruction.
+    pub fn to_source(&self, range: Range<usize>) -> Option<Span> {
+        let segments = self.segments.iter().filter(|segment| {
+            segment.instruction.start <= range.start && range.start < segment.instruction.end
+                || segment.instruction.start < range.end && range.end <= segment.instruction.end
+                || range.start < segment.instruction.start && segment.instruction.end < range.end
+        });
+        let spans = segments.map(|segment| match segment.copied() {
+            true => {
+                let start = range.start.max(segment.instruction.start);
+                let end = range.end.min(segment.instruction.end);
+                let offset = segment.source.start;
+                Span::new(
+                    offset + start - segment.instruction.start,
+                    offset + end - segment.instruction.start,
+                )
+            }
+            false => Span::new(segment.source.start, segment.source.end),
+        });
+        spans.reduce(|a, b| Span::new(a.start.min(b.start), a.end.max(b.end)))
+    }
+
+    /// The byte range of the [instruction](crate::Recipe::instruction) coming from the span of
+    /// the source, the reverse of [SourceMap::to_source]. [None] if nothing in the span is part
+    /// of the instruction, like a comment.
+    pub fn to_instruction(&self, span: Span) -> Option<Range<usize>> {
+        let segments = self.segments.iter().filter(|segment| {
+            segment.source.start <= span.start && span.start < segment.source.end
+                || segment.source.start < span.end && span.end <= segment.source.end
+                || span.start < segment.source.start && segment.source.end < span.end
+        });
+        let ranges = segments.map(|segment| match segment.copied() {
+            true => {
+                let start = span.start.max(segment.source.start);
+                let end = span.end.min(segment.source.end);
+                let offset = segment.instruction.start;
+                offset + start - segment.source.start..offset + end - segment.source.start
+            }
+            false => segment.instruction.clone(),
+        });
+        ranges.reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+    }
+
+    /// The source of the step, from the start of its first line to the end of its last line.
+    pub fn step_span(&self, step: &Step) -> Option<Span> {
+        self.to_source(step.instruction.clone())
+    }
+
+    /// The source of an ingredient, cookware or timer of a step. [None] for other items.
+    pub fn item_span(&self, item: &Item) -> Option<Span> {
+        match item {
+            Item::Ingredient(index) => self.ingredients.get(*index).copied(),
+            Item::Cookware(index) => self.cookware.get(*index).copied(),
+            Item::Timer(index) => self.timers.get(*index).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Span};
+
+    #[test]
+    fn source_map() {
+        let source = "\
+---
+title: Tea
+---
+// Morning tea
+Boil @water{1%l} [- hot -] in a #kettle, \\@ home.
+
+== Serving ==
+Steep for ~{3%minutes}.
+Serve with @milk.
+";
+        let recipe = parse(source).unwrap();
+        let map = recipe.source_map.as_ref().unwrap();
+        let text = |span: Span| &source[span.start..span.end];
+
+        let instruction = &recipe.instruction;
+        let boil = instruction.find("Boil").unwrap();
+        assert_eq!(text(map.to_source(boil..boil + 4).unwrap()), "Boil");
+        let water = instruction.find('@').unwrap();
+        assert_eq!(
+            text(map.to_source(water..water + 1).unwrap()),
+            "@water{1%l}"
+        );
+        let home = instruction.find("\\@ home").unwrap();
+        assert_eq!(text(map.to_source(home..home + 7).unwrap()), "\\@ home");
+        assert_eq!(
+            map.to_source(instruction.len() + 1..instruction.len() + 2),
+            None
+        );
+
+        let serving = source.find("Serving").unwrap();
+        let range = map.to_instruction(Span::new(serving, serving + 7)).unwrap();
+        assert_eq!(&instruction[range], "Serving");
+        let comment = source.find("hot").unwrap();
+        assert_eq!(map.to_instruction(Span::new(comment, comment + 3)), None);
+
+        let steps: Vec<&str> = recipe
+            .steps
+            .iter()
+            .map(|step| text(map.step_span(step).unwrap()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                "Boil @water{1%l} [- hot -] in a #kettle, \\@ home.",
+                "Steep for ~{3%minutes}.\nServe with @milk."
+            ]
+        );
+        let timer = &recipe.steps[1].items[1];
+        assert_eq!(text(map.item_span(timer).unwrap()), "~{3%minutes}");
+    }
+}
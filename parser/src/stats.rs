@@ -0,0 +1,248 @@
+//! Statistics over recipes, like the most used ingredients of a [Collection].
+//!
+//! Ingredient names are normalized with a [NameNormalizer], by default so `Onions` and `onion`
+//! count as the same ingredient, named `onion`. Recipe references like `@./sauces/Marinara` are
+//! not counted as ingredients.
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::stats::RecipeStats;
+//!
+//! let recipes = vec![
+//!     parse("Fry the @onions{2} in @butter{20%g}.\n").unwrap(),
+//!     parse("Glaze the @onion{1} with @butter{10%g} and @sugar{1%tbsp}.\n").unwrap(),
+//! ];
+//! let stats = RecipeStats::from_recipes(&recipes);
+//! assert_eq!(stats.ingredients[0], ("butter".to_string(), 2));
+//! assert_eq!(stats.pairs_with("onion")[0], ("butter".to_string(), 2));
+//! ```
+
+use crate::names::NameNormalizer;
+use crate::{Collection, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+/// Statistics of recipes. See the [module](crate::stats).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecipeStats {
+    /// Number of recipes.
+    pub recipes: usize,
+    /// Ingredients with the number of recipes using them, the most used first. Ingredients used
+    /// equally often are ordered by name.
+    pub ingredients: Vec<(String, usize)>,
+    /// Units of the ingredient mentions with the number of mentions, the most used first. Units
+    /// are compared case-insensitively and named in lowercase.
+    pub units: Vec<(String, usize)>,
+    /// Average [estimated duration](Recipe::estimated_duration) of the recipes which take any
+    /// time. [None] if none does.
+    pub average_time: Option<Duration>,
+    /// How often two ingredients are used in the same recipe.
+    pub co_occurrence: CoOccurrence,
+    /// How the names of the ingredients were normalized, also used by
+    /// [RecipeStats::pairs_with].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub names: NameNormalizer,
+}
+
+/// Number of recipes using two ingredients together, as a symmetric matrix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CoOccurrence {
+    /// The ingredients, in the order of [RecipeStats::ingredients].
+    pub ingredients: Vec<String>,
+    /// Number of recipes using the ingredients of the row and the column. The diagonal holds the
+    /// number of recipes using the ingredient.
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl CoOccurrence {
+    /// Number of recipes using both ingredients.
+    pub fn count(&self, a: &str, b: &str) -> usize {
+        let index = |name: &str| self.ingredients.iter().position(|i| i == name);
+        match (index(a), index(b)) {
+            (Some(a), Some(b)) => self.counts[a][b],
+            _ => 0,
+        }
+    }
+}
+
+impl RecipeStats {
+    /// The statistics of the recipes.
+    pub fn from_recipes<'a, I>(recipes: I) -> RecipeStats
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        RecipeStats::from_recipes_with(recipes, &NameNormalizer::default())
+    }
+
+    /// The statistics of the recipes, with the ingredient names normalized by `names`.
+    pub fn from_recipes_with<'a, I>(recipes: I, names: &NameNormalizer) -> RecipeStats
+    where
+        I: IntoIterator<Item = &'a Recipe>,
+    {
+        let mut stats = RecipeStats {
+            names: names.clone(),
+            ..RecipeStats::default()
+        };
+        let mut used: Vec<BTreeSet<String>> = vec![];
+        let mut units: HashMap<String, usize> = HashMap::new();
+        let mut times = vec![];
+        recipes.into_iter().for_each(|recipe| {
+            stats.recipes += 1;
+            let specifiers = recipe.metadata.ingredients_specifiers.iter();
+            let specifiers = specifiers.filter(|specifier| !specifier.recipe);
+            let mut ingredients = BTreeSet::new();
+            specifiers.for_each(|specifier| {
+                ingredients.insert(names.normalize(&specifier.ingredient));
+                if let Some(unit) = &specifier.unit {
+                    *units.entry(unit.trim().to_lowercase()).or_default() += 1;
+                }
+            });
+            used.push(ingredients);
+            let time = recipe.estimated_duration();
+            if time > Duration::ZERO {
+                times.push(time);
+            }
+        });
+
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        used.iter()
+            .flatten()
+            .for_each(|name| *counts.entry(name).or_default() += 1);
+        stats.ingredients = most_used(counts.into_iter().map(|(n, c)| (n.clone(), c)));
+        stats.units = most_used(units.into_iter());
+        if !times.is_empty() {
+            // Summed up in nanoseconds, as the sum of long times doesn't fit into a Duration.
+            let total: u128 = times.iter().map(Duration::as_nanos).sum();
+            let average = total / times.len() as u128;
+            let nanos = Duration::from_secs(1).as_nanos();
+            stats.average_time = Some(Duration::new(
+                (average / nanos) as u64,
+                (average % nanos) as u32,
+            ));
+        }
+
+        let ingredients: Vec<String> = stats.ingredients.iter().map(|(n, _)| n.clone()).collect();
+        let index: HashMap<&String, usize> = ingredients.iter().zip(0..).collect();
+        let mut matrix = vec![vec![0; ingredients.len()]; ingredients.len()];
+        used.iter().for_each(|recipe| {
+            recipe.iter().for_each(|a| {
+                recipe.iter().for_each(|b| matrix[index[a]][index[b]] += 1);
+            })
+        });
+        stats.co_occurrence = CoOccurrence {
+            ingredients,
+            counts: matrix,
+        };
+        stats
+    }
+
+    /// The ingredients used together with the ingredient, with the number of recipes using
+    /// both, the most often first. The name is normalized like the names of the statistics.
+    pub fn pairs_with(&self, ingredient: &str) -> Vec<(String, usize)> {
+        let name = self.names.normalize(ingredient);
+        let co_occurrence = &self.co_occurrence;
+        let row = match co_occurrence.ingredients.iter().position(|i| *i == name) {
+            Some(row) => &co_occurrence.counts[row],
+            None => return vec![],
+        };
+        let pairs = co_occurrence
+            .ingredients
+            .iter()
+            .zip(row)
+            .filter(|(other, count)| **other != name && **count > 0)
+            .map(|(other, count)| (other.clone(), *count));
+        most_used(pairs)
+    }
+}
+
+/// The names with their counts, the highest count first, then by name.
+fn most_used(counts: impl Iterator<Item = (String, usize)>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts
+}
+
+impl Collection {
+    /// The statistics of the recipes of the collection.
+    pub fn stats(&self) -> RecipeStats {
+        RecipeStats::from_recipes(self.recipes.iter().map(|recipe| &recipe.recipe))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::names::NameNormalizer;
+    use crate::stats::RecipeStats;
+    use crate::{parse_with, ParseOptions};
+    use std::time::Duration;
+
+    #[test]
+    fn recipe_stats() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let recipes: Vec<_> = [
+            "Boil @pasta{200%g} for ~{10%minutes}, add @./sauces/Pesto{100%g} and @Basil.\n",
+            "Fry @garlic{2%cloves} in @olive oil{2%TBSP}, add @tomatoes{400%g} and @basil.\n",
+            "Fry @garlic{1%clove} in @olive oil{1%tbsp} for ~{30%minutes}, add @pasta{100%g}.\n",
+        ]
+        .iter()
+        .map(|source| parse_with(source, &options).unwrap().recipe)
+        .collect();
+        let stats = RecipeStats::from_recipes(&recipes);
+        let count = |name: &str, count: usize| (name.to_string(), count);
+
+        assert_eq!(stats.recipes, 3);
+        assert_eq!(
+            stats.ingredients,
+            vec![
+                count("basil", 2),
+                count("garlic", 2),
+                count("olive oil", 2),
+                count("pasta", 2),
+                count("tomato", 1)
+            ]
+        );
+        assert_eq!(
+            stats.units,
+            vec![
+                count("g", 3),
+                count("tbsp", 2),
+                count("clove", 1),
+                count("cloves", 1)
+            ]
+        );
+        assert_eq!(stats.average_time, Some(Duration::from_secs(20 * 60)));
+        assert_eq!(stats.co_occurrence.count("garlic", "olive oil"), 2);
+        assert_eq!(stats.co_occurrence.count("pasta", "pasta"), 2);
+        assert_eq!(stats.co_occurrence.count("pasta", "pesto"), 0);
+        assert_eq!(
+            stats.pairs_with("Garlic"),
+            vec![
+                count("olive oil", 2),
+                count("basil", 1),
+                count("pasta", 1),
+                count("tomato", 1)
+            ]
+        );
+        assert!(stats.pairs_with("saffron").is_empty());
+        assert_eq!(RecipeStats::from_recipes(vec![]), RecipeStats::default());
+
+        let long = parse_with("Wait ~{200000000000000%days}.\n", &options)
+            .unwrap()
+            .recipe;
+        let stats = RecipeStats::from_recipes(vec![&long, &long]);
+        assert_eq!(stats.average_time, Some(long.estimated_duration()));
+
+        let stats = RecipeStats::from_recipes_with(&recipes, &NameNormalizer::case_insensitive());
+        assert_eq!(stats.ingredients.last(), Some(&count("tomatoes", 1)));
+        assert_eq!(stats.pairs_with("Tomatoes")[0], count("basil", 1));
+    }
+}
@@ -0,0 +1,610 @@
+//! Steps of a [Recipe].
+//!
+//! Per spec, the steps of a recipe are separated by blank lines. Every [Step] references the
+//! ingredients, cookware and timers mentioned in it by their index in the [Metadata].
+//!
+//! Steps can be grouped into [Section]s with a header line like `== Dough ==`. Lines starting with
+//! `>` are notes, which are commentary rather than instructions.
+//!
+//! Hidden ingredients, see [IngredientSpecifier::hidden](crate::IngredientSpecifier::hidden), are
+//! not part of the items of a step, but are listed in [Section::ingredients].
+//!
+//! With [ParseOptions::extensions](crate::ParseOptions::extensions), temperatures like `180°C` or
+//! `350 °F` in the text of a step become [Item::Temperature]s, which are converted by
+//! [Recipe::convert](crate::Recipe::convert).
+
+use crate::{instruction_parts, IngredientSpecifier, InstructionPart, Metadata, Quantity, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A step of the [Recipe].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Step {
+    /// Number of the step, starting at 1.
+    pub number: usize,
+    /// Text and mentionings of the step in order. Lines of a step are joined with a space.
+    pub items: Vec<Item>,
+    /// Range of the ingredient specifiers mentioned in this step in
+    /// [Metadata::ingredients_specifiers], including hidden ones and recipe references. See
+    /// [Recipe::step_ingredients].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub specifiers: Range<usize>,
+    /// Byte range of the step in [Recipe::instruction], from the start of its first line to the
+    /// end of its last line. See [SourceMap::step_span](crate::source_map::SourceMap::step_span).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub instruction: Range<usize>,
+}
+
+/// Part of a [Step].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Item {
+    /// Text of the step.
+    Text(String),
+    /// Index of the [IngredientSpecifier](crate::IngredientSpecifier) in
+    /// [Metadata::ingredients_specifiers](crate::Metadata::ingredients_specifiers).
+    Ingredient(usize),
+    /// Index of the cookware in [Metadata::cookware](crate::Metadata::cookware).
+    Cookware(usize),
+    /// Index of the [Timer](crate::Timer) in [Metadata::timer](crate::Metadata::timer).
+    Timer(usize),
+    /// A note, written as a line starting with `>`.
+    Note(String),
+    /// Reference to another recipe, like `@./sauces/Marinara{200%g}`. See
+    /// [IngredientSpecifier::recipe](crate::IngredientSpecifier::recipe).
+    RecipeRef {
+        /// Path of the recipe relative to the referencing one, without the `.cook` extension.
+        path: String,
+        /// Needed quantity of the referenced recipe.
+        amount: Option<Quantity>,
+    },
+    /// A temperature in the text, like `180°C`.
+    Temperature {
+        /// The number of degrees.
+        value: f64,
+        /// Symbol of the unit, `°C` or `°F`.
+        unit: String,
+    },
+}
+
+/// A group of [Step]s started by a header like `== Dough ==`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Section {
+    /// Name of the section. [None] for the steps before the first header and for headers
+    /// without a name.
+    pub name: Option<String>,
+    /// Range of the steps of this section in [Recipe::steps].
+    pub steps: Range<usize>,
+    /// Names of the ingredients used in this section, in order of their first mentioning.
+    pub ingredients: Vec<String>,
+}
+
+impl Step {
+    /// Indices of the ingredient specifiers mentioned in this step. Hidden ingredients are left
+    /// out, see [Step::specifiers].
+    pub fn ingredients(&self) -> impl Iterator<Item = usize> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Ingredient(index) => Some(*index),
+            _ => None,
+        })
+    }
+
+    /// Indices of the cookware mentioned in this step.
+    pub fn cookware(&self) -> impl Iterator<Item = usize> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Cookware(index) => Some(*index),
+            _ => None,
+        })
+    }
+
+    /// Notes of this step.
+    pub fn notes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Note(note) => Some(note.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Indices of the timers mentioned in this step.
+    pub fn timers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Timer(index) => Some(*index),
+            _ => None,
+        })
+    }
+}
+
+impl Recipe {
+    /// Steps of the recipe in order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Sections of the recipe in order. A recipe without section headers has a single section
+    /// without a name, unless it has no steps at all.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Steps of the [Section].
+    pub fn section_steps(&self, section: &Section) -> &[Step] {
+        &self.steps[section.steps.clone()]
+    }
+
+    /// Ingredients used in the [Step] with their amounts in the step, in order. Includes hidden
+    /// ingredients, but not references to other recipes.
+    pub fn step_ingredients(&self, step: &Step) -> Vec<&IngredientSpecifier> {
+        self.metadata.ingredients_specifiers[step.specifiers.clone()]
+            .iter()
+            .filter(|specifier| !specifier.recipe)
+            .collect()
+    }
+
+    /// Cookware used in the [Step], in order.
+    pub fn step_cookware(&self, step: &Step) -> Vec<&str> {
+        step.cookware()
+            .map(|index| self.metadata.cookware[index].as_str())
+            .collect()
+    }
+}
+
+/// Split the reduced instruction into [Step]s and [Section]s. Metadata lines are skipped.
+pub(crate) fn split_steps(
+    instruction: &str,
+    metadata: &Metadata,
+    temperatures: bool,
+) -> (Vec<Step>, Vec<Section>) {
+    let mut ingredient_index = 0;
+    let mut cookware_index = 0;
+    let mut timer_index = 0;
+    let mut steps = vec![];
+    let mut sections: Vec<Section> = vec![];
+    let mut items: Vec<Item> = vec![];
+    // Index of the first ingredient specifier of the current step.
+    let mut specifiers_start = 0;
+    // Range of the current step in the instruction.
+    let mut range: Option<Range<usize>> = None;
+
+    for (offset, line) in lines(instruction) {
+        if line.trim_start().starts_with(">>") {
+            continue;
+        }
+        let header = line.trim_start().starts_with('=');
+        if header || line.trim().is_empty() {
+            if !items.is_empty() {
+                let specifiers = specifiers_start..ingredient_index;
+                specifiers_start = ingredient_index;
+                push_step(
+                    &mut steps,
+                    &mut sections,
+                    std::mem::take(&mut items),
+                    specifiers,
+                    range.take().unwrap_or_default(),
+                );
+            }
+            if header {
+                let name = unescape(line.trim().trim_matches('='));
+                let name = name.trim();
+                sections.push(Section {
+                    name: if name.is_empty() {
+                        None
+                    } else {
+                        Some(name.to_string())
+                    },
+                    steps: steps.len()..steps.len(),
+                    ingredients: vec![],
+                });
+            }
+            continue;
+        }
+        let start = offset + line.len() - line.trim_start().len();
+        let end = offset + line.trim_end().len();
+        range = Some(range.map_or(start, |range| range.start)..end);
+        if let Some(note) = line.trim_start().strip_prefix('>') {
+            items.push(Item::Note(unescape(note).trim().to_string()));
+            continue;
+        }
+        match items.last() {
+            None | Some(Item::Note(_)) => {}
+            Some(_) => push_text(&mut items, ' '),
+        }
+        instruction_parts(line.trim()).for_each(|part| match part {
+            InstructionPart::Ingredient => {
+                let specifier = &metadata.ingredients_specifiers[ingredient_index];
+                if specifier.recipe {
+                    items.push(Item::RecipeRef {
+                        path: specifier.ingredient.clone(),
+                        amount: specifier.quantity(),
+                    });
+                } else {
+                    add_ingredient(current_section(&mut sections), &specifier.ingredient);
+                    if !specifier.hidden {
+                        items.push(Item::Ingredient(ingredient_index));
+                    }
+                }
+                ingredient_index += 1;
+            }
+            InstructionPart::Cookware => {
+                items.push(Item::Cookware(cookware_index));
+                cookware_index += 1;
+            }
+            InstructionPart::Timer => {
+                items.push(Item::Timer(timer_index));
+                timer_index += 1;
+            }
+            InstructionPart::Text(char) => push_text(&mut items, char),
+        });
+    }
+    if !items.is_empty() {
+        let specifiers = specifiers_start..ingredient_index;
+        let range = range.unwrap_or_default();
+        push_step(&mut steps, &mut sections, items, specifiers, range);
+    }
+    if temperatures {
+        steps.iter_mut().for_each(|step| {
+            step.items = std::mem::take(&mut step.items)
+                .into_iter()
+                .flat_map(|item| match item {
+                    Item::Text(text) => split_temperatures(&text),
+                    item => vec![item],
+                })
+                .collect();
+        });
+    }
+    (steps, sections)
+}
+
+fn push_step(
+    steps: &mut Vec<Step>,
+    sections: &mut Vec<Section>,
+    items: Vec<Item>,
+    specifiers: Range<usize>,
+    instruction: Range<usize>,
+) {
+    let section = current_section(sections);
+    steps.push(Step {
+        number: steps.len() + 1,
+        items,
+        specifiers,
+        instruction,
+    });
+    section.steps.end = steps.len();
+}
+
+/// The lines like [str::lines], with their byte offset.
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        Some((start, line.strip_suffix('\r').unwrap_or(line)))
+    })
+}
+
+/// The last section. An unnamed one is created for the steps before the first header.
+fn current_section(sections: &mut Vec<Section>) -> &mut Section {
+    if sections.is_empty() {
+        sections.push(Section {
+            name: None,
+            steps: 0..0,
+            ingredients: vec![],
+        });
+    }
+    sections.last_mut().unwrap()
+}
+
+fn add_ingredient(section: &mut Section, name: &str) {
+    if !section
+        .ingredients
+        .iter()
+        .any(|ingredient| ingredient == name)
+    {
+        section.ingredients.push(name.to_string());
+    }
+}
+
+/// Text of a line that can't contain any mentionings.
+fn unescape(line: &str) -> String {
+    instruction_parts(line)
+        .filter_map(|part| match part {
+            InstructionPart::Text(char) => Some(char),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Split the text into [Item::Text] and [Item::Temperature].
+fn split_temperatures(text: &str) -> Vec<Item> {
+    let mut items = vec![];
+    let mut rest = text;
+    while let Some((start, end, value, unit)) = find_temperature(rest) {
+        if start > 0 {
+            items.push(Item::Text(rest[..start].to_string()));
+        }
+        items.push(Item::Temperature {
+            value,
+            unit: unit.to_string(),
+        });
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        items.push(Item::Text(rest.to_string()));
+    }
+    items
+}
+
+/// Start, end, value and unit of the first temperature in the text.
+fn find_temperature(text: &str) -> Option<(usize, usize, f64, &'static str)> {
+    text.match_indices('°').find_map(|(degree, symbol)| {
+        let unit_start = degree + symbol.len();
+        let unit = match text[unit_start..].chars().next()? {
+            'C' | 'c' => "°C",
+            'F' | 'f' => "°F",
+            _ => return None,
+        };
+        let end = unit_start + 1;
+        if text[end..].starts_with(char::is_alphanumeric) {
+            return None;
+        }
+        let number = text[..degree].trim_end_matches(' ');
+        let mut start = number
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_ascii_digit() || *c == '.'))
+            .map(|(index, c)| index + c.len_utf8())
+            .unwrap_or(0);
+        let mut value: f64 = number[start..].parse().ok()?;
+        let before = &number[..start];
+        // The hyphen of a range like `350-400°F` isn't a sign.
+        if before.ends_with('-') && !before[..start - 1].ends_with(|c: char| c.is_ascii_digit()) {
+            start -= 1;
+            value = -value;
+        }
+        if number[..start].ends_with(char::is_alphanumeric) {
+            return None;
+        }
+        Some((start, end, value, unit))
+    })
+}
+
+fn push_text(items: &mut Vec<Item>, char: char) {
+    match items.last_mut() {
+        Some(Item::Text(text)) => text.push(char),
+        _ => items.push(Item::Text(char.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::steps::Item;
+    use crate::units::UnitSystem;
+    use crate::{parse, parse_with, Amount, ParseOptions, Quantity};
+
+    #[test]
+    fn steps() {
+        let recipe = parse(
+            "\
+>> servings: 2\n\
+Crack the @eggs{2} into a #bowl\n\
+and whisk.\n\
+\n\
+Fry in a #pan for ~{3%minutes}, add @salt.\n",
+        )
+        .unwrap();
+        let steps = recipe.steps();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].number, 1);
+        assert_eq!(
+            steps[0].items,
+            vec![
+                Item::Text("Crack the ".to_string()),
+                Item::Ingredient(0),
+                Item::Text(" into a ".to_string()),
+                Item::Cookware(0),
+                Item::Text(" and whisk.".to_string()),
+            ]
+        );
+        assert_eq!(steps[1].ingredients().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(steps[1].cookware().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(steps[1].timers().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn step_ingredients() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let source = "\
+Mix @flour{500%g} and @water{300%ml} in a #bowl.
+
+Season with @-salt{1%tsp} and @flour{2%tbsp}(for dusting), add @./sauces/Pesto{1%jar},
+then knead on a #board with a #scraper.
+";
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        let steps = recipe.steps();
+        assert_eq!(
+            (steps[0].specifiers.clone(), steps[1].specifiers.clone()),
+            (0..2, 2..5)
+        );
+        let ingredients: Vec<String> = recipe
+            .step_ingredients(&steps[1])
+            .iter()
+            .map(|specifier| format!("{} {}", specifier.quantity().unwrap(), specifier.ingredient))
+            .collect();
+        assert_eq!(ingredients, vec!["1 tsp salt", "2 tbsp flour"]);
+        assert_eq!(
+            recipe.step_ingredients(&steps[1])[1].note.as_deref(),
+            Some("for dusting")
+        );
+        assert_eq!(recipe.step_cookware(&steps[0]), vec!["bowl"]);
+        assert_eq!(recipe.step_cookware(&steps[1]), vec!["board", "scraper"]);
+    }
+
+    #[test]
+    fn sections() {
+        let recipe = parse(
+            "\
+Mix @flour{500%g} and @water{300%ml}.\n\
+\n\
+== Filling @home ==\n\
+Chop @onion{1}.\n\
+\n\
+Fry the @onion{1} with @salt.\n\
+=\n\
+Bake.\n",
+        )
+        .unwrap();
+        let sections = recipe.sections();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].name, None);
+        assert_eq!(sections[0].ingredients, vec!["flour", "water"]);
+        assert_eq!(sections[1].name.as_deref(), Some("Filling @home"));
+        assert_eq!(sections[1].steps, 1..3);
+        assert_eq!(sections[1].ingredients, vec!["onion", "salt"]);
+        assert_eq!(recipe.section_steps(&sections[2]).len(), 1);
+        assert_eq!(recipe.metadata.ingredients.len(), 4);
+
+        let recipe = parse("Just text.\n").unwrap();
+        assert_eq!(recipe.sections().len(), 1);
+    }
+
+    #[test]
+    fn notes() {
+        let recipe = parse(
+            "\
+> Best served warm @home.\n\
+\n\
+Boil the @water.\n\
+> Don't let it boil over!\n\
+Add @salt.\n",
+        )
+        .unwrap();
+        let steps = recipe.steps();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            steps[0].notes().collect::<Vec<_>>(),
+            vec!["Best served warm @home."]
+        );
+        assert_eq!(
+            steps[1].items,
+            vec![
+                Item::Text("Boil the ".to_string()),
+                Item::Ingredient(0),
+                Item::Text(".".to_string()),
+                Item::Note("Don't let it boil over!".to_string()),
+                Item::Text("Add ".to_string()),
+                Item::Ingredient(1),
+                Item::Text(".".to_string()),
+            ]
+        );
+        assert!(recipe.metadata.ingredients.get("home").is_none());
+    }
+
+    #[test]
+    fn hidden_ingredients() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let source = "Boil the @water{2%l}.@-salt{1%tbsp}\n";
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        assert_eq!(
+            recipe.steps()[0].items,
+            vec![
+                Item::Text("Boil the ".to_string()),
+                Item::Ingredient(0),
+                Item::Text(".".to_string()),
+            ]
+        );
+        assert_eq!(recipe.sections()[0].ingredients, vec!["water", "salt"]);
+        assert!(recipe.metadata.ingredients_specifiers[1].hidden);
+        assert!(recipe.to_cooklang().contains("@-salt{1%tbsp}"));
+    }
+
+    #[test]
+    fn recipe_references() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let source = "Add @./sauces/Marinara{200%g} and @../pesto.\n";
+        let recipe = parse_with(source, &options).unwrap().recipe;
+        assert_eq!(
+            recipe.steps()[0].items[1],
+            Item::RecipeRef {
+                path: "./sauces/Marinara".to_string(),
+                amount: Some(Quantity {
+                    amount: Amount::Single(200.0),
+                    unit: Some("g".to_string()),
+                }),
+            }
+        );
+        assert_eq!(
+            recipe.steps()[0].items[3],
+            Item::RecipeRef {
+                path: "../pesto".to_string(),
+                amount: None,
+            }
+        );
+        assert!(recipe.metadata.ingredients.is_empty());
+        assert!(recipe.sections()[0].ingredients.is_empty());
+        assert_eq!(recipe.to_cooklang(), source);
+
+        let output = parse_with(source, &ParseOptions::default()).unwrap();
+        assert_eq!(output.warnings.len(), 2);
+        let ingredients = &output.recipe.metadata.ingredients;
+        assert!(ingredients.contains_key("./sauces/Marinara"));
+    }
+
+    #[test]
+    fn temperatures() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let source = "Preheat to 180°C, or 350 °F. Freeze at -18°c.\nH2°Cs stays text.\n";
+        let mut recipe = parse_with(source, &options).unwrap().recipe;
+        let temperature = |value: f64, unit: &str| Item::Temperature {
+            value,
+            unit: unit.to_string(),
+        };
+        assert_eq!(
+            recipe.steps()[0].items,
+            vec![
+                Item::Text("Preheat to ".to_string()),
+                temperature(180.0, "°C"),
+                Item::Text(", or ".to_string()),
+                temperature(350.0, "°F"),
+                Item::Text(". Freeze at ".to_string()),
+                temperature(-18.0, "°C"),
+                Item::Text(". H2°Cs stays text.".to_string()),
+            ]
+        );
+
+        recipe.convert(UnitSystem::Imperial);
+        assert!(matches!(
+            &recipe.steps()[0].items[1],
+            Item::Temperature { value, unit } if (value - 356.0).abs() < 0.01 && unit == "°F"
+        ));
+
+        let recipe = parse(source).unwrap();
+        assert_eq!(recipe.steps()[0].items.len(), 1);
+
+        let recipe = parse_with("Bake at 350-400°F.\n", &options).unwrap().recipe;
+        assert_eq!(
+            recipe.steps()[0].items,
+            vec![
+                Item::Text("Bake at 350-".to_string()),
+                temperature(400.0, "°F"),
+                Item::Text(".".to_string()),
+            ]
+        );
+    }
+}
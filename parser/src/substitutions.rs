@@ -0,0 +1,235 @@
+//! Substitutions of ingredients, e.g. for allergies or diets.
+//!
+//! Like the [pantry](crate::pantry), the substitution table is split into sections by names in
+//! square brackets, here the ingredient which can be replaced. Every line contains an alternative
+//! and optionally the ratio to convert amounts of the ingredient into amounts of the alternative,
+//! which is 1 by default. Lines starting with `#` are comments.
+//!
+//! ```text
+//! [butter]
+//! margarine
+//! coconut oil = 0.8
+//!
+//! [sugar]
+//! honey = 3/4
+//! ```
+//!
+//! [Recipe::with_substitution] rewrites the mentions of an ingredient into the alternative.
+
+use crate::time::parse_number;
+use crate::{Ingredient, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The parsed substitution table.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Substitutions {
+    /// Substitutions in the order they appear in the table.
+    pub items: Vec<Substitution>,
+}
+
+/// An alternative to an ingredient.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Substitution {
+    /// Name of the replaced ingredient.
+    pub ingredient: String,
+    /// Name of the alternative.
+    pub alternative: String,
+    /// Amount of the alternative for an amount of 1 of the ingredient, in the same unit.
+    pub ratio: f64,
+}
+
+impl Substitutions {
+    /// Parse the content of a substitution table.
+    pub fn parse(inp: &str) -> Result<Substitutions, Box<dyn std::error::Error>> {
+        let mut items = vec![];
+        let mut ingredient = None;
+        for (number, line) in inp.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("Missing ] in section on line {}", number + 1))?;
+                ingredient = Some(name.trim().to_string());
+                continue;
+            }
+            let ingredient = ingredient
+                .clone()
+                .ok_or_else(|| format!("Alternative without ingredient on line {}", number + 1))?;
+            let (alternative, ratio) = match line.split_once('=') {
+                Some((alternative, ratio)) => {
+                    let ratio = parse_number(ratio.trim().trim_matches('"'))
+                        .filter(|ratio| *ratio > 0.0)
+                        .ok_or_else(|| format!("Invalid ratio on line {}", number + 1))?;
+                    (alternative, ratio)
+                }
+                None => (line, 1.0),
+            };
+            let alternative = alternative.trim().trim_matches('"');
+            if alternative.is_empty() {
+                return Err(format!("Missing alternative on line {}", number + 1).into());
+            }
+            items.push(Substitution {
+                ingredient,
+                alternative: alternative.to_string(),
+                ratio,
+            });
+        }
+        Ok(Substitutions { items })
+    }
+
+    /// The alternatives to the ingredient, compared case-insensitively.
+    pub fn alternatives(&self, ingredient: &str) -> Vec<&Substitution> {
+        let ingredient = ingredient.trim();
+        self.items
+            .iter()
+            .filter(|item| item.ingredient.eq_ignore_ascii_case(ingredient))
+            .collect()
+    }
+
+    /// The recipe with the ingredient replaced by the alternative, using the ratio of the table.
+    /// The ingredient is compared case-insensitively. [None] if the table doesn't list the
+    /// alternative for the ingredient or the recipe doesn't use the ingredient.
+    pub fn apply(&self, recipe: &Recipe, ingredient: &str, alternative: &str) -> Option<Recipe> {
+        let substitution = self
+            .alternatives(ingredient)
+            .into_iter()
+            .find(|item| item.alternative.eq_ignore_ascii_case(alternative.trim()))?;
+        let mut names = recipe.metadata.ingredients.keys();
+        let name = names.find(|name| name.eq_ignore_ascii_case(ingredient.trim()))?;
+        Some(recipe.with_scaled_substitution(
+            name,
+            &substitution.alternative,
+            substitution.ratio,
+        ))
+    }
+}
+
+impl Recipe {
+    /// The recipe with every mention of the ingredient replaced by the alternative, keeping the
+    /// amounts. See [Recipe::with_scaled_substitution].
+    pub fn with_substitution(&self, ingredient: &str, alternative: &str) -> Recipe {
+        self.with_scaled_substitution(ingredient, alternative, 1.0)
+    }
+
+    /// The recipe with every mention of the ingredient replaced by the alternative, with the
+    /// amounts multiplied by the ratio.
+    ///
+    /// If the alternative is already part of the recipe, the ingredients are merged. The
+    /// [source](Recipe::source) is left as it is, use [Recipe::to_cooklang] to write the
+    /// substituted recipe.
+    pub fn with_scaled_substitution(
+        &self,
+        ingredient: &str,
+        alternative: &str,
+        ratio: f64,
+    ) -> Recipe {
+        let mut recipe = self.clone();
+        let metadata = &mut recipe.metadata;
+        metadata
+            .ingredients_specifiers
+            .iter_mut()
+            .filter(|specifier| !specifier.recipe && specifier.ingredient == ingredient)
+            .for_each(|specifier| {
+                specifier.ingredient = alternative.to_string();
+                specifier.amount_in_step = specifier.amount_in_step.map(|value| value * ratio);
+            });
+        let ingredients = std::mem::take(&mut metadata.ingredients);
+        for (name, mut item) in ingredients {
+            if name == ingredient {
+                item.name = alternative.to_string();
+                #[cfg(feature = "uuid")]
+                {
+                    item.id = Ingredient::id_for(alternative);
+                }
+                item.quantities.quantities.iter_mut().for_each(|quantity| {
+                    quantity.amount = quantity.amount.map(|value| value * ratio)
+                });
+                item.amount = item.amount.map(|amount| amount.map(|value| value * ratio));
+            }
+            match metadata.ingredients.get_mut(&item.name) {
+                Some(existing) => merge_ingredients(existing, item),
+                None => {
+                    metadata.ingredients.insert(item.name.clone(), item);
+                }
+            }
+        }
+        recipe
+    }
+}
+
+/// Add the mentions of the other ingredient of the same name to the ingredient.
+pub(crate) fn merge_ingredients(ingredient: &mut Ingredient, other: Ingredient) {
+    ingredient
+        .quantities
+        .quantities
+        .extend(other.quantities.quantities);
+    other.notes.into_iter().for_each(|note| {
+        if !ingredient.notes.contains(&note) {
+            ingredient.notes.push(note);
+        }
+    });
+    ingredient.optional &= other.optional;
+    if let Some(total) = ingredient.total().quantities.into_iter().next() {
+        ingredient.amount = Some(total.amount);
+        ingredient.unit = total.unit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::substitutions::Substitutions;
+    use crate::{parse, Amount};
+
+    #[test]
+    fn substitute() {
+        let table = Substitutions::parse(
+            "\
+# vegan
+[butter]
+margarine
+\"coconut oil\" = 0.8
+
+[sugar]
+honey = 3/4
+",
+        )
+        .unwrap();
+        assert_eq!(table.alternatives("Butter").len(), 2);
+        assert_eq!(table.alternatives("sugar")[0].ratio, 0.75);
+        assert!(Substitutions::parse("margarine\n").is_err());
+        assert!(Substitutions::parse("[butter]\nmargarine = lots\n").is_err());
+
+        let recipe = parse(
+            "Melt @butter{100%g}(soft) with @coconut oil{20%g}, add @sugar{200%g} and \
+             @butter{50%g}.\n",
+        )
+        .unwrap();
+        let substituted = table.apply(&recipe, "Butter", "Coconut Oil").unwrap();
+        let names: Vec<&String> = substituted.metadata.ingredients.keys().collect();
+        assert_eq!(names, vec!["coconut oil", "sugar"]);
+        let oil = &substituted.metadata.ingredients["coconut oil"];
+        assert_eq!(oil.amount, Some(Amount::Single(140.0)));
+        assert_eq!(oil.notes, vec!["soft"]);
+        let specifier = &substituted.metadata.ingredients_specifiers[3];
+        assert_eq!(specifier.ingredient, "coconut oil");
+        assert_eq!(specifier.amount_in_step, Amount::Single(40.0));
+        assert!(substituted
+            .to_cooklang()
+            .contains("Melt @coconut oil{80%g}(soft) with @coconut oil{20%g}"));
+        assert!(table.apply(&recipe, "butter", "lard").is_none());
+        let salted = parse("Add @salt.\n").unwrap();
+        assert!(table.apply(&salted, "sugar", "honey").is_none());
+
+        let substituted = recipe.with_substitution("sugar", "maple syrup");
+        let syrup = &substituted.metadata.ingredients["maple syrup"];
+        assert_eq!(syrup.amount, Some(Amount::Single(200.0)));
+    }
+}
@@ -0,0 +1,253 @@
+//! Random suggestions of recipes from a [Collection], e.g. for a menu of the week.
+//!
+//! [Collection::suggest] picks random recipes satisfying the [Constraints]. The choice only
+//! depends on the [seed](Constraints::seed), so the same seed gives the same menu; use e.g. the
+//! current date as seed for a new menu every day.
+//!
+//! ```no_run
+//! use cook_with_rust_parser::suggest::Constraints;
+//! use cook_with_rust_parser::Collection;
+//! use std::path::PathBuf;
+//! use std::time::Duration;
+//!
+//! let collection = Collection::load_dir("recipes").unwrap();
+//! let constraints = Constraints {
+//!     count: 7,
+//!     max_time: Some(Duration::from_secs(45 * 60)),
+//!     tags: vec!["dinner".to_string()],
+//!     recent: vec![(PathBuf::from("Pancakes.cook"), 3)],
+//!     no_repeat_days: 14,
+//!     ..Constraints::default()
+//! };
+//! for recipe in collection.suggest(&constraints) {
+//!     println!("{}", recipe.title());
+//! }
+//! ```
+
+use crate::collection::{normalize, with_recipe_extension};
+use crate::names::NameNormalizer;
+use crate::pantry::Pantry;
+use crate::{Collection, CollectionRecipe, Recipe};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Constraints of [Collection::suggest].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraints {
+    /// Number of recipes to suggest. Defaults to 1.
+    pub count: usize,
+    /// Longest [estimated duration](Recipe::estimated_duration) of a suggested recipe.
+    pub max_time: Option<Duration>,
+    /// Tags every suggested recipe has, compared case-insensitively.
+    pub tags: Vec<String>,
+    /// The ingredients at hand.
+    pub pantry: Option<Pantry>,
+    /// Share of the ingredients of a suggested recipe which are in the
+    /// [pantry](Constraints::pantry), from 0 to 1. Ignored without a pantry.
+    pub min_pantry_coverage: f64,
+    /// How the ingredients are compared with the items of the pantry. Defaults to
+    /// [case-insensitive](NameNormalizer::case_insensitive), like [Pantry::get].
+    pub names: NameNormalizer,
+    /// Recently cooked recipes with the number of days since, by path relative to
+    /// [Collection::root]. The extension may be left out, like for [Collection::by_path].
+    pub recent: Vec<(PathBuf, u32)>,
+    /// Recipes cooked fewer days ago than this aren't suggested. Defaults to 0, which allows any
+    /// recent recipe.
+    pub no_repeat_days: u32,
+    /// Seed of the random choice.
+    pub seed: u64,
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Constraints {
+            count: 1,
+            max_time: None,
+            tags: vec![],
+            pantry: None,
+            min_pantry_coverage: 0.0,
+            names: NameNormalizer::case_insensitive(),
+            recent: vec![],
+            no_repeat_days: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl Constraints {
+    /// Whether the recipe may be suggested.
+    pub fn allows(&self, recipe: &CollectionRecipe) -> bool {
+        let metadata = &recipe.recipe.metadata;
+        let recent = self.recent.iter().any(|(path, days)| {
+            with_recipe_extension(&normalize(path)) == recipe.path && *days < self.no_repeat_days
+        });
+        !recent
+            && self.tags.iter().all(|tag| metadata.has_tag(tag))
+            && match self.max_time {
+                Some(max_time) => recipe.recipe.estimated_duration() <= max_time,
+                None => true,
+            }
+            && match &self.pantry {
+                Some(pantry) => {
+                    pantry_coverage_with(&recipe.recipe, pantry, &self.names)
+                        >= self.min_pantry_coverage
+                }
+                None => true,
+            }
+    }
+}
+
+/// Share of the ingredients of the recipe which are in the pantry. 1 for recipes without
+/// ingredients. The names are compared case-insensitively, like [Pantry::get].
+pub fn pantry_coverage(recipe: &Recipe, pantry: &Pantry) -> f64 {
+    pantry_coverage_with(recipe, pantry, &NameNormalizer::case_insensitive())
+}
+
+/// Share of the ingredients of the recipe which are in the pantry, with the names compared after
+/// normalizing them.
+pub fn pantry_coverage_with(recipe: &Recipe, pantry: &Pantry, names: &NameNormalizer) -> f64 {
+    let ingredients = &recipe.metadata.ingredients;
+    if ingredients.is_empty() {
+        return 1.0;
+    }
+    let at_hand = ingredients
+        .values()
+        .filter(|ingredient| {
+            let mut items = pantry.items.iter();
+            items.any(|item| names.same(&item.name, &ingredient.name))
+        })
+        .count();
+    at_hand as f64 / ingredients.len() as f64
+}
+
+impl Collection {
+    /// Random recipes satisfying the constraints, at most [Constraints::count] and each at most
+    /// once. See the [module](crate::suggest).
+    pub fn suggest(&self, constraints: &Constraints) -> Vec<&CollectionRecipe> {
+        let mut candidates: Vec<&CollectionRecipe> = self
+            .recipes
+            .iter()
+            .filter(|recipe| constraints.allows(recipe))
+            .collect();
+        let mut random = Random(constraints.seed);
+        let count = constraints.count.min(candidates.len());
+        // Partial Fisher-Yates shuffle.
+        (0..count).for_each(|i| {
+            let j = i + (random.next() % (candidates.len() - i) as u64) as usize;
+            candidates.swap(i, j);
+        });
+        candidates.truncate(count);
+        candidates
+    }
+}
+
+/// SplitMix64 pseudorandom numbers, enough for picking recipes.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::names::NameNormalizer;
+    use crate::pantry::Pantry;
+    use crate::suggest::Constraints;
+    use crate::{parse, Collection, CollectionRecipe};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn suggest() {
+        let sources = [
+            (
+                "Pasta.cook",
+                ">> tags: dinner\nBoil @pasta{200%g} for ~{10%minutes}, add @cheese.\n",
+            ),
+            (
+                "Roast.cook",
+                ">> tags: dinner\nRoast the @chicken{1} for ~{90%minutes}.\n",
+            ),
+            (
+                "Omelette.cook",
+                ">> tags: Dinner, breakfast\nFry @eggs{3} in @butter.\n",
+            ),
+            ("Porridge.cook", ">> tags: breakfast\nCook @oats{50%g}.\n"),
+            (
+                "Salad.cook",
+                ">> tags: dinner\nToss @lettuce and @cheese.\n",
+            ),
+        ];
+        let mut collection = Collection::default();
+        sources.iter().for_each(|(path, source)| {
+            collection.recipes.push(CollectionRecipe {
+                path: PathBuf::from(path),
+                recipe: parse(source).unwrap(),
+                image: None,
+                step_images: vec![],
+                references: vec![],
+            });
+        });
+        let suggest = |constraints: &Constraints| -> Vec<&str> {
+            let mut names: Vec<&str> = collection
+                .suggest(constraints)
+                .into_iter()
+                .map(CollectionRecipe::name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        let mut constraints = Constraints {
+            count: 10,
+            tags: vec!["dinner".to_string()],
+            max_time: Some(Duration::from_secs(30 * 60)),
+            ..Constraints::default()
+        };
+        assert_eq!(suggest(&constraints), vec!["Omelette", "Pasta", "Salad"]);
+        constraints.recent = vec![
+            (PathBuf::from("./Pasta"), 2),
+            (PathBuf::from("Salad.cook"), 10),
+        ];
+        constraints.no_repeat_days = 7;
+        assert_eq!(suggest(&constraints), vec!["Omelette", "Salad"]);
+        constraints.pantry = Some(Pantry::parse("eggs\nLettuce\n").unwrap());
+        constraints.min_pantry_coverage = 0.5;
+        assert_eq!(suggest(&constraints), vec!["Omelette", "Salad"]);
+        constraints.min_pantry_coverage = 1.0;
+        assert!(suggest(&constraints).is_empty());
+        constraints.pantry = Some(Pantry::parse("egg\nbutter\n").unwrap());
+        assert!(suggest(&constraints).is_empty());
+        constraints.names = NameNormalizer::default();
+        assert_eq!(suggest(&constraints), vec!["Omelette"]);
+
+        let constraints = Constraints {
+            count: 3,
+            seed: 42,
+            ..Constraints::default()
+        };
+        let menu = collection.suggest(&constraints);
+        assert_eq!(menu.len(), 3);
+        assert_eq!(menu, collection.suggest(&constraints));
+        let mut paths: Vec<_> = menu.iter().map(|recipe| &recipe.path).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), 3);
+        let menus: Vec<_> = (0..20)
+            .map(|seed| {
+                let constraints = Constraints {
+                    seed,
+                    ..Constraints::default()
+                };
+                collection.suggest(&constraints)[0].name()
+            })
+            .collect();
+        assert!(menus.iter().any(|name| *name != menus[0]));
+    }
+}
@@ -0,0 +1,155 @@
+//! Parsing of durations like `1 hour 30 minutes` or `1h30m`.
+
+use std::time::Duration;
+
+/// Seconds of a time unit like `min`, `hours` or `d`. Case-insensitive. Besides English, the
+/// German, French and Spanish names of the units are known.
+pub(crate) fn unit_seconds(unit: &str) -> Option<f64> {
+    let unit = unit.trim().trim_end_matches('.').to_lowercase();
+    let seconds = match unit.as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+        "sek" | "sekunde" | "sekunden" | "seconde" | "secondes" | "segundo" | "segundos" => 1.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+        "minuten" | "minuto" | "minutos" => 60.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+        "std" | "stunde" | "stunden" | "heure" | "heures" | "hora" | "horas" => 3600.0,
+        "d" | "day" | "days" => 86400.0,
+        "tag" | "tage" | "jour" | "jours" | "día" | "días" | "dia" | "dias" => 86400.0,
+        _ => return None,
+    };
+    Some(seconds)
+}
+
+/// Parse a number like `2`, `1.5` or `1/2`.
+pub(crate) fn parse_number(number: &str) -> Option<f64> {
+    match number.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator: f64 = denominator.trim().parse().ok()?;
+            if denominator == 0.0 {
+                return None;
+            }
+            Some(numerator.trim().parse::<f64>().ok()? / denominator)
+        }
+        None => number.trim().parse().ok(),
+    }
+}
+
+/// Parse a duration made of numbers followed by time units, like `1 hour 30 minutes`, `1h30m`
+/// or `1.5 hours`. A single number without a unit is read as minutes.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if let Some(minutes) = parse_number(text) {
+        return to_duration(minutes * 60.0);
+    }
+
+    let mut seconds = 0.0;
+    let mut rest = text;
+    let mut found = false;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '/'))
+            .unwrap_or(rest.len());
+        let number = parse_number(&rest[..number_end])?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_alphabetic() && c != '.')
+            .unwrap_or(rest.len());
+        seconds += number * unit_seconds(&rest[..unit_end])?;
+        rest = rest[unit_end..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+        found = true;
+    }
+    if found {
+        to_duration(seconds)
+    } else {
+        None
+    }
+}
+
+/// Format a duration in ISO 8601, like `PT1H30M`.
+pub(crate) fn iso_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let mut iso = "PT".to_string();
+    if hours > 0 {
+        iso += &format!("{}H", hours);
+    }
+    if minutes > 0 {
+        iso += &format!("{}M", minutes);
+    }
+    if seconds > 0 || (hours == 0 && minutes == 0) {
+        iso += &format!("{}S", seconds);
+    }
+    iso
+}
+
+/// Parse an ISO 8601 duration, like `PT1H30M` or `P1DT2H`.
+#[cfg_attr(not(any(test, feature = "json-ld")), allow(dead_code))]
+pub(crate) fn parse_iso_duration(iso: &str) -> Option<Duration> {
+    let rest = iso.trim().strip_prefix('P')?;
+    let mut seconds = 0.0;
+    let mut time = false;
+    let mut found = false;
+    let mut number = String::new();
+    for c in rest.chars() {
+        let unit = match c {
+            'T' if number.is_empty() && !time => {
+                time = true;
+                continue;
+            }
+            '0'..='9' | '.' | ',' => {
+                number.push(if c == ',' { '.' } else { c });
+                continue;
+            }
+            'W' if !time => 7.0 * 86400.0,
+            'D' if !time => 86400.0,
+            'H' if time => 3600.0,
+            'M' if time => 60.0,
+            'S' if time => 1.0,
+            _ => return None,
+        };
+        seconds += number.parse::<f64>().ok()? * unit;
+        number.clear();
+        found = true;
+    }
+    if found && number.is_empty() {
+        to_duration(seconds)
+    } else {
+        None
+    }
+}
+
+/// The duration of the seconds. [None] if they are negative or too many for a [Duration].
+fn to_duration(seconds: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::time::{iso_duration, parse_duration, parse_iso_duration};
+    use std::time::Duration;
+
+    #[test]
+    fn durations() {
+        let minutes = |m: u64| Some(Duration::from_secs(m * 60));
+        assert_eq!(parse_duration("45"), minutes(45));
+        assert_eq!(parse_duration("1 hour 30 minutes"), minutes(90));
+        assert_eq!(parse_duration("1h30m"), minutes(90));
+        assert_eq!(parse_duration("1.5 hours"), minutes(90));
+        assert_eq!(parse_duration("1/2 hr"), minutes(30));
+        assert_eq!(parse_duration("1 day, 2 hours and 5 min."), minutes(1565));
+        assert_eq!(parse_duration("a while"), None);
+        assert_eq!(parse_duration("10 parsecs"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("99999999999999999999999 days"), None);
+
+        assert_eq!(iso_duration(Duration::from_secs(5400)), "PT1H30M");
+        assert_eq!(iso_duration(Duration::from_secs(61)), "PT1M1S");
+        assert_eq!(iso_duration(Duration::from_secs(0)), "PT0S");
+        assert_eq!(parse_iso_duration("PT1H30M"), minutes(90));
+        assert_eq!(parse_iso_duration("P1DT0.5H"), minutes(1470));
+        assert_eq!(parse_iso_duration("PT"), None);
+        assert_eq!(parse_iso_duration("1H"), None);
+        assert_eq!(parse_iso_duration("PT99999999999999999999999H"), None);
+    }
+}
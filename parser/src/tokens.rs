@@ -0,0 +1,231 @@
+//! Token stream of CookLang source for syntax highlighting.
+//!
+//! [tokenize] only runs the grammar, without building a [Recipe](crate::Recipe), so it's cheap
+//! enough to run on every key press. Tokens don't overlap and text which isn't part of a token,
+//! like the instructions or the braces of an amount, is left out.
+
+use crate::diagnostics::Span;
+use crate::{CookParser, Rule};
+use pest::iterators::Pair;
+use pest::Parser;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Kind of a [Token].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum TokenKind {
+    /// An ingredient with its `@` and modifiers, like `@?fresh basil`.
+    IngredientName,
+    /// The amount of an ingredient or timer, like `1/2` or `1|2*`.
+    Quantity,
+    /// The unit of an ingredient or timer, like `kg`.
+    Unit,
+    /// The preparation note of an ingredient, like `(chopped)`.
+    IngredientNote,
+    /// Cookware with its `#` and braces, like `#frying pan{}`.
+    Cookware,
+    /// The `~` of a timer.
+    Timer,
+    /// The key of a `>>` line or of the front matter.
+    MetadataKey,
+    /// The value of a `>>` line or of the front matter.
+    MetadataValue,
+    /// A section header like `== Dough ==`.
+    Section,
+    /// A note like `> Tastes best warm`.
+    Note,
+    /// A comment like `// optional` or `[- TODO -]`.
+    Comment,
+}
+
+/// A highlighted part of the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Token {
+    /// What the token is.
+    pub kind: TokenKind,
+    /// Where the token is in the source.
+    pub span: Span,
+}
+
+/// Tokens of the source, in order. Source which can't be parsed has no tokens.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let cook_lang = match CookParser::parse(Rule::cook_lang, source) {
+        Ok(mut pairs) => pairs.next().unwrap(),
+        Err(_) => return tokens,
+    };
+    let mut push = |kind, start, end| match tokens.last_mut() {
+        // Numbers, separators and scaling make up one quantity.
+        Some(last)
+            if last.kind == TokenKind::Quantity && kind == last.kind && last.span.end == start =>
+        {
+            last.span.end = end
+        }
+        _ if start < end => tokens.push(Token {
+            kind,
+            span: Span::new(start, end),
+        }),
+        _ => {}
+    };
+    cook_lang
+        .into_inner()
+        .for_each(|pair| match pair.as_rule() {
+            Rule::front_matter => {
+                let body = pair.into_inner().next().unwrap();
+                let mut offset = body.as_span().start();
+                body.as_str().split_inclusive('\n').for_each(|line| {
+                    let indented = line.starts_with([' ', '\t', '-', '#']);
+                    match line.split_once(':').filter(|_| !indented) {
+                        Some((key, value)) => {
+                            let value_start = offset + key.len() + 1;
+                            let value_trimmed = value.trim_start();
+                            let value_start = value_start + value.len() - value_trimmed.len();
+                            push(
+                                TokenKind::MetadataKey,
+                                offset,
+                                offset + key.trim_end().len(),
+                            );
+                            push(
+                                TokenKind::MetadataValue,
+                                value_start,
+                                value_start + value_trimmed.trim_end().len(),
+                            );
+                        }
+                        None if !line.trim().is_empty() => {
+                            let start = offset + line.len() - line.trim_start().len();
+                            push(
+                                TokenKind::MetadataValue,
+                                start,
+                                offset + line.trim_end().len(),
+                            );
+                        }
+                        None => {}
+                    }
+                    offset += line.len();
+                });
+            }
+            Rule::metadata => {
+                let property = pair.into_inner().next().unwrap();
+                let start = property.as_span().start();
+                if property.as_rule() == Rule::servings {
+                    push(TokenKind::MetadataKey, start, start + "servings".len());
+                }
+                property.into_inner().for_each(|part| {
+                    let kind = match part.as_rule() {
+                        Rule::name => TokenKind::MetadataKey,
+                        _ => TokenKind::MetadataValue,
+                    };
+                    let value = part.as_str().trim_end();
+                    push(
+                        kind,
+                        part.as_span().start(),
+                        part.as_span().start() + value.len(),
+                    );
+                });
+            }
+            Rule::section => push(
+                TokenKind::Section,
+                pair.as_span().start(),
+                pair.as_span().end(),
+            ),
+            Rule::note => push(
+                TokenKind::Note,
+                pair.as_span().start(),
+                pair.as_span().end(),
+            ),
+            Rule::comment => push(
+                TokenKind::Comment,
+                pair.as_span().start(),
+                pair.as_span().end(),
+            ),
+            Rule::line => pair
+                .into_inner()
+                .for_each(|part| line_tokens(part, &mut push)),
+            _ => {}
+        });
+    tokens
+}
+
+/// Tokens of an ingredient, cookware, timer or comment in a line.
+fn line_tokens(part: Pair<'_, Rule>, push: &mut impl FnMut(TokenKind, usize, usize)) {
+    let (start, end) = (part.as_span().start(), part.as_span().end());
+    match part.as_rule() {
+        Rule::ingredient => {
+            let (name, amount): (Vec<_>, Vec<_>) = part.into_inner().partition(|property| {
+                matches!(
+                    property.as_rule(),
+                    Rule::ingredient_modifier | Rule::name | Rule::recipe_path | Rule::text
+                )
+            });
+            let name_end = name.last().map_or(start + 1, |name| name.as_span().end());
+            push(TokenKind::IngredientName, start, name_end);
+            amount
+                .into_iter()
+                .for_each(|property| amount_tokens(property, push));
+        }
+        Rule::cookware => push(TokenKind::Cookware, start, end),
+        Rule::timer => {
+            push(TokenKind::Timer, start, start + 1);
+            part.into_inner()
+                .for_each(|property| amount_tokens(property, push));
+        }
+        Rule::comment | Rule::block_comment => push(TokenKind::Comment, start, end),
+        _ => {}
+    }
+}
+
+/// Tokens of a part of the amount in braces.
+fn amount_tokens(part: Pair<'_, Rule>, push: &mut impl FnMut(TokenKind, usize, usize)) {
+    let kind = match part.as_rule() {
+        Rule::number | Rule::ingredient_separator | Rule::scaling => TokenKind::Quantity,
+        Rule::unit | Rule::time_unit => TokenKind::Unit,
+        Rule::modified => TokenKind::IngredientNote,
+        Rule::timer_part | Rule::timer_range => {
+            part.into_inner()
+                .for_each(|property| amount_tokens(property, push));
+            return;
+        }
+        _ => return,
+    };
+    push(kind, part.as_span().start(), part.as_span().end());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokens::{tokenize, TokenKind};
+
+    #[test]
+    fn tokenize_source() {
+        let source = "\
+>> servings: 2|4
+Fry @?fresh basil{1/2%bunch}(chopped) in a #frying pan{} for ~{2%min}. // hot
+Add @eggs{1|2*}.
+";
+        let tokens: Vec<(TokenKind, &str)> = tokenize(source)
+            .into_iter()
+            .map(|token| (token.kind, &source[token.span.start..token.span.end]))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::MetadataKey, "servings"),
+                (TokenKind::MetadataValue, "2|4"),
+                (TokenKind::IngredientName, "@?fresh basil"),
+                (TokenKind::Quantity, "1/2"),
+                (TokenKind::Unit, "bunch"),
+                (TokenKind::IngredientNote, "(chopped)"),
+                (TokenKind::Cookware, "#frying pan{}"),
+                (TokenKind::Timer, "~"),
+                (TokenKind::Quantity, "2"),
+                (TokenKind::Unit, "min"),
+                (TokenKind::Comment, "// hot"),
+                (TokenKind::IngredientName, "@eggs"),
+                (TokenKind::Quantity, "1|2*"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! Units of measurement and conversion between the metric and the imperial system.
+//!
+//! The [UnitRegistry] knows the common cooking units for mass, volume and temperature. It is used
+//! by [Recipe::convert] to rewrite the amounts of all ingredients into a [UnitSystem] and by
+//! [crate::parse_with_units] to unify different spellings of the same unit.
+//!
+//! After scaling, [Recipe::humanize_units] rewrites amounts like `1500 ml` into units which are
+//! easier to read, like `1.5 l`.
+
+use crate::{Amount, Item, Quantity, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Difference to a whole number up to which a value counts as one in [UnitRegistry::humanize].
+const WHOLE_TOLERANCE: f64 = 0.01;
+
+/// A system of units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum UnitSystem {
+    /// Grams, liters and degree Celsius.
+    Metric,
+    /// Ounces, cups and degree Fahrenheit.
+    Imperial,
+}
+
+/// The physical quantity measured by a [Unit]. Only units of the same kind can be converted
+/// into each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum PhysicalQuantity {
+    Mass,
+    Volume,
+    Temperature,
+}
+
+/// A unit of measurement.
+///
+/// A value `v` of this unit equals `v * ratio + offset` of the base unit of its
+/// [PhysicalQuantity], which is gram, milliliter or degree Celsius.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Unit {
+    /// Symbol of the unit as it is written in a recipe, e.g. `tbsp`.
+    pub symbol: String,
+    /// What this unit measures.
+    pub quantity: PhysicalQuantity,
+    /// The system this unit belongs to.
+    pub system: UnitSystem,
+    /// Factor to get from this unit to the base unit.
+    pub ratio: f64,
+    /// Offset to get from this unit to the base unit. Only used by temperatures.
+    pub offset: f64,
+}
+
+impl Unit {
+    fn new(symbol: &str, quantity: PhysicalQuantity, system: UnitSystem, ratio: f64) -> Unit {
+        Unit {
+            symbol: symbol.to_string(),
+            quantity,
+            system,
+            ratio,
+            offset: 0.0,
+        }
+    }
+
+    fn to_base_value(&self, value: f64) -> f64 {
+        value * self.ratio + self.offset
+    }
+
+    fn base_value_to_unit(&self, value: f64) -> f64 {
+        (value - self.offset) / self.ratio
+    }
+}
+
+/// A collection of known [Unit]s.
+///
+/// Units are looked up by their symbol or one of their aliases, ignoring the case, a trailing
+/// `.` and plural suffixes. `tbsp`, `Tbsp.`, `tablespoon` and `tablespoons` are all the same unit.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UnitRegistry {
+    /// Known units. Units of the same kind and system are ordered from small to large.
+    pub units: Vec<Unit>,
+    /// Alternative spellings indexed by their lowercase form, pointing to the symbol of a unit.
+    pub aliases: HashMap<String, String>,
+    /// Suffixes that are removed from a unit if it is not known in its plural form.
+    pub plural_suffixes: Vec<String>,
+}
+
+impl Default for UnitRegistry {
+    /// Registry with the built-in cooking units.
+    fn default() -> Self {
+        use PhysicalQuantity::*;
+        use UnitSystem::*;
+        UnitRegistry {
+            units: vec![
+                Unit::new("g", Mass, Metric, 1.0),
+                Unit::new("kg", Mass, Metric, 1000.0),
+                Unit::new("oz", Mass, Imperial, 28.349_523_125),
+                Unit::new("lb", Mass, Imperial, 453.592_37),
+                Unit::new("ml", Volume, Metric, 1.0),
+                Unit::new("l", Volume, Metric, 1000.0),
+                Unit::new("tsp", Volume, Imperial, 4.928_921_593_75),
+                Unit::new("tbsp", Volume, Imperial, 14.786_764_781_25),
+                Unit::new("cup", Volume, Imperial, 236.588_236_5),
+                Unit::new("°C", Temperature, Metric, 1.0),
+                Unit {
+                    offset: -160.0 / 9.0,
+                    ..Unit::new("°F", Temperature, Imperial, 5.0 / 9.0)
+                },
+            ],
+            aliases: [
+                ("g", &["gram", "gramme", "gr"][..]),
+                ("kg", &["kilogram", "kilogramme", "kilo"]),
+                ("oz", &["ounce"]),
+                ("lb", &["pound"]),
+                ("ml", &["milliliter", "millilitre"]),
+                ("l", &["liter", "litre"]),
+                ("tsp", &["teaspoon"]),
+                ("tbsp", &["tablespoon", "tbs"]),
+                ("°C", &["celsius"]),
+                ("°F", &["fahrenheit"]),
+            ]
+            .iter()
+            .flat_map(|(symbol, aliases)| {
+                aliases
+                    .iter()
+                    .map(move |alias| (alias.to_lowercase(), symbol.to_string()))
+            })
+            .collect(),
+            plural_suffixes: vec!["s".to_string(), "es".to_string()],
+        }
+    }
+}
+
+impl UnitRegistry {
+    /// Look up a unit by its symbol or one of its aliases.
+    pub fn get(&self, unit: &str) -> Option<&Unit> {
+        let unit = unit.trim().trim_end_matches('.');
+        self.find(unit).or_else(|| {
+            self.plural_suffixes
+                .iter()
+                .filter_map(|suffix| unit.strip_suffix(suffix.as_str()))
+                .find_map(|singular| self.find(singular))
+        })
+    }
+
+    fn find(&self, unit: &str) -> Option<&Unit> {
+        let lowercase = unit.to_lowercase();
+        let symbol = self.aliases.get(&lowercase).map(String::as_str);
+        self.units
+            .iter()
+            .find(|u| u.symbol == unit || Some(u.symbol.as_str()) == symbol)
+            .or_else(|| {
+                self.units
+                    .iter()
+                    .find(|u| u.symbol.to_lowercase() == lowercase)
+            })
+    }
+
+    /// Add an alternative spelling of a known unit.
+    pub fn add_alias(&mut self, alias: &str, symbol: &str) {
+        self.aliases
+            .insert(alias.to_lowercase(), symbol.to_string());
+    }
+
+    /// The symbol of the unit, or the unit itself if it is unknown to this registry.
+    pub fn normalize(&self, unit: &str) -> String {
+        match self.get(unit) {
+            Some(unit) => unit.symbol.clone(),
+            None => unit.to_string(),
+        }
+    }
+
+    /// Convert a value from one unit into another. [None] if one of the units is unknown or they
+    /// don't measure the same [PhysicalQuantity].
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Option<f64> {
+        let from = self.get(from)?;
+        let to = self.get(to)?;
+        if from.quantity != to.quantity {
+            return None;
+        }
+        Some(to.base_value_to_unit(from.to_base_value(value)))
+    }
+
+    /// Find the unit of the given system best suited to express the value, which is the largest
+    /// unit the value is at least one of. Returns the converted value and the chosen unit.
+    pub fn convert_to_system(
+        &self,
+        value: f64,
+        from: &str,
+        system: UnitSystem,
+    ) -> Option<(f64, &Unit)> {
+        let from = self.get(from)?;
+        let base = from.to_base_value(value);
+        let candidates: Vec<&Unit> = self
+            .units
+            .iter()
+            .filter(|unit| unit.quantity == from.quantity && unit.system == system)
+            .collect();
+        let unit = candidates
+            .iter()
+            .rev()
+            .find(|unit| unit.base_value_to_unit(base).abs() >= 1.0)
+            .or_else(|| candidates.first())?;
+        Some((unit.base_value_to_unit(base), unit))
+    }
+
+    /// Express the value in the units of its own system which are easiest to read, like `1600 g`
+    /// as `1.6 kg` or `4 tsp` as `1 tbsp` and `1 tsp`. [None] if the unit is unknown.
+    ///
+    /// The value is written in the largest unit it is at least one of, see
+    /// [UnitRegistry::convert_to_system]. An imperial value which isn't a multiple of a quarter
+    /// is split into the whole units and the rest in the next smaller unit, if the rest is a
+    /// whole number of it. Temperatures are left as they are.
+    pub fn humanize(&self, value: f64, unit: &str) -> Option<Vec<(f64, &Unit)>> {
+        let from = self.get(unit)?;
+        if from.quantity == PhysicalQuantity::Temperature {
+            return Some(vec![(value, from)]);
+        }
+        let (value, unit) = self.convert_to_system(value, unit, from.system)?;
+        let (whole, rest) = (value.trunc(), value.fract());
+        let quarters = rest * 4.0;
+        if from.system == UnitSystem::Metric
+            || whole < 1.0
+            || (quarters - quarters.round()).abs() < WHOLE_TOLERANCE
+        {
+            return Some(vec![(value, unit)]);
+        }
+        let smaller = self
+            .units
+            .iter()
+            .filter(|smaller| smaller.quantity == unit.quantity && smaller.system == unit.system)
+            .take_while(|smaller| smaller.ratio < unit.ratio)
+            .last();
+        match smaller {
+            Some(smaller) => {
+                let rest = rest * unit.ratio / smaller.ratio;
+                if rest.round() >= 1.0 && (rest - rest.round()).abs() < WHOLE_TOLERANCE {
+                    Some(vec![(whole, unit), (rest.round(), smaller)])
+                } else {
+                    Some(vec![(value, unit)])
+                }
+            }
+            None => Some(vec![(value, unit)]),
+        }
+    }
+}
+
+/// Error of converting a value with a unit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitError {
+    /// The unit is unknown, or of a different kind than needed.
+    UnknownUnit(String),
+    /// The value is negative or not a number.
+    InvalidValue(f64),
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitError::UnknownUnit(unit) => write!(f, "Unknown unit {}", unit),
+            UnitError::InvalidValue(value) => write!(f, "Invalid value {}", value),
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+impl Amount {
+    /// Apply a function to every number of this amount.
+    pub(crate) fn map(&self, f: impl Fn(f64) -> f64) -> Amount {
+        match self {
+            Amount::Multi(d) => Amount::Multi(f(*d)),
+            Amount::PerServing(dd) => Amount::PerServing(dd.iter().map(|d| f(*d)).collect()),
+            Amount::Single(d) => Amount::Single(f(*d)),
+        }
+    }
+
+    /// The largest number of this amount.
+    pub(crate) fn max_value(&self) -> f64 {
+        match self {
+            Amount::Multi(d) | Amount::Single(d) => *d,
+            Amount::PerServing(dd) => dd.iter().cloned().fold(0.0, f64::max),
+        }
+    }
+}
+
+impl Recipe {
+    /// Convert the amounts of all ingredients and the temperatures of the steps into the given
+    /// [UnitSystem].
+    ///
+    /// Ingredients with a unit unknown to the built-in [UnitRegistry] are left untouched.
+    pub fn convert(&mut self, system: UnitSystem) {
+        self.convert_with(system, &UnitRegistry::default())
+    }
+
+    /// Convert the amounts of all ingredients and the temperatures of the steps into the given
+    /// [UnitSystem] using the units of the registry.
+    pub fn convert_with(&mut self, system: UnitSystem, registry: &UnitRegistry) {
+        self.steps
+            .iter_mut()
+            .flat_map(|step| step.items.iter_mut())
+            .for_each(|item| {
+                if let Item::Temperature { value, unit } = item {
+                    if let Some((converted, target)) =
+                        registry.convert_to_system(*value, unit, system)
+                    {
+                        *value = converted;
+                        *unit = target.symbol.clone();
+                    }
+                }
+            });
+        let metadata = &mut self.metadata;
+        for (key, ingredient) in metadata.ingredients.iter_mut() {
+            // Mentionings in the unit of the ingredient use the unit best suited for the total
+            // amount, so they are still consistent with the ingredient afterwards.
+            let target = match (&ingredient.unit, &ingredient.amount) {
+                (Some(unit), Some(amount)) => registry
+                    .convert_to_system(amount.max_value(), unit, system)
+                    .map(|(_, target)| target),
+                _ => None,
+            };
+
+            let quantity_target = |quantity: &Quantity| {
+                let unit = quantity.unit.as_ref()?;
+                match target {
+                    Some(target) if ingredient.unit.as_ref() == Some(unit) => Some(target),
+                    _ => registry
+                        .convert_to_system(quantity.amount.max_value(), unit, system)
+                        .map(|(_, target)| target),
+                }
+            };
+            let convert = |quantity: &Quantity, target: &Unit| {
+                let unit = quantity.unit.as_ref().unwrap();
+                quantity
+                    .amount
+                    .map(|value| registry.convert(value, unit, &target.symbol).unwrap())
+            };
+
+            // Every mentioning is converted from its own quantity, references and mentionings
+            // which were not added to the ingredient included.
+            for specifier in metadata
+                .ingredients_specifiers
+                .iter_mut()
+                .filter(|specifier| specifier.ingredient_key() == key)
+            {
+                let quantity = match specifier.quantity() {
+                    Some(quantity) => quantity,
+                    None => continue,
+                };
+                if let Some(target) = quantity_target(&quantity) {
+                    specifier.amount_in_step = convert(&quantity, target);
+                    specifier.unit = Some(target.symbol.clone());
+                }
+            }
+            let quantities = ingredient.quantities.quantities.iter().map(|quantity| {
+                match quantity_target(quantity) {
+                    Some(target) => Quantity {
+                        amount: convert(quantity, target),
+                        unit: Some(target.symbol.clone()),
+                    },
+                    None => quantity.clone(),
+                }
+            });
+            let quantities = quantities.collect();
+
+            ingredient.quantities.quantities = quantities;
+            if let (Some(target), Some(unit), Some(amount)) =
+                (target, &ingredient.unit, &ingredient.amount)
+            {
+                ingredient.amount = Some(
+                    amount.map(|value| registry.convert(value, unit, &target.symbol).unwrap()),
+                );
+                ingredient.unit = Some(target.symbol.clone());
+            }
+        }
+    }
+
+    /// Rewrite the amounts of all ingredients into the units which are easiest to read, see
+    /// [UnitRegistry::humanize]. Meant for scaled recipes, in which `250 ml` became `1500 ml`.
+    ///
+    /// Ingredients with a unit unknown to the built-in [UnitRegistry] are left untouched.
+    pub fn humanize_units(&mut self) {
+        self.humanize_units_with(&UnitRegistry::default())
+    }
+
+    /// Rewrite the amounts of all ingredients into the units which are easiest to read using the
+    /// units of the registry.
+    ///
+    /// A quantity of an ingredient may be split into several, like `1 tbsp` and `1 tsp`. The
+    /// amounts in the steps are written in a single unit.
+    pub fn humanize_units_with(&mut self, registry: &UnitRegistry) {
+        let metadata = &mut self.metadata;
+        metadata
+            .ingredients_specifiers
+            .iter_mut()
+            .for_each(|specifier| {
+                let quantity = match specifier.quantity() {
+                    Some(quantity) => quantity,
+                    None => return,
+                };
+                if let Some(humanized) = humanize_quantity(registry, &quantity, false).pop() {
+                    specifier.amount_in_step = humanized.amount;
+                    specifier.unit = humanized.unit;
+                }
+            });
+        metadata.ingredients.values_mut().for_each(|ingredient| {
+            ingredient.quantities.quantities = ingredient
+                .quantities
+                .quantities
+                .iter()
+                .flat_map(|quantity| humanize_quantity(registry, quantity, true))
+                .collect();
+            if let Some(total) = ingredient.total().quantities.into_iter().next() {
+                ingredient.amount = Some(total.amount);
+                ingredient.unit = total.unit;
+            }
+        });
+    }
+}
+
+/// The quantity in the units easiest to read. Only a single amount is split into several units
+/// and only if `split` is set.
+fn humanize_quantity(registry: &UnitRegistry, quantity: &Quantity, split: bool) -> Vec<Quantity> {
+    let unit = match &quantity.unit {
+        Some(unit) if quantity.amount.max_value() > 0.0 => unit,
+        _ => return vec![quantity.clone()],
+    };
+    let parts = match registry.humanize(quantity.amount.max_value(), unit) {
+        Some(parts) => parts,
+        None => return vec![quantity.clone()],
+    };
+    match (&quantity.amount, split) {
+        (Amount::Single(_), true) => parts
+            .into_iter()
+            .map(|(value, unit)| Quantity {
+                amount: Amount::Single(value),
+                unit: Some(unit.symbol.clone()),
+            })
+            .collect(),
+        (amount, _) => {
+            let target = &parts[0].1.symbol;
+            vec![Quantity {
+                amount: amount.map(|value| registry.convert(value, unit, target).unwrap()),
+                unit: Some(target.clone()),
+            }]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::{UnitRegistry, UnitSystem};
+    use crate::Quantity;
+    use crate::Amount;
+    use crate::{parse, parse_with, parse_with_units, DuplicateMentions, ParseOptions};
+
+    #[test]
+    fn convert_units() {
+        let registry = UnitRegistry::default();
+        assert!((registry.convert(1.0, "lb", "g").unwrap() - 453.59).abs() < 0.01);
+        assert!((registry.convert(180.0, "°C", "°F").unwrap() - 356.0).abs() < 0.01);
+        assert!((registry.convert(212.0, "°F", "°C").unwrap() - 100.0).abs() < 0.01);
+        assert!(registry.convert(1.0, "g", "ml").is_none());
+        assert!(registry.get("c").is_none());
+        assert_eq!(registry.get("Celsius").unwrap().symbol, "°C");
+
+        let (value, unit) = registry
+            .convert_to_system(30.0, "ml", UnitSystem::Imperial)
+            .unwrap();
+        assert_eq!(unit.symbol, "tbsp");
+        assert!((value - 2.03).abs() < 0.01);
+    }
+
+    #[test]
+    fn convert_recipe() {
+        let mut recipe =
+            parse("Mix @flour{500%g} with @milk{1%cup} and more @flour{1000%g}\n").unwrap();
+        recipe.convert(UnitSystem::Metric);
+
+        let flour = &recipe.metadata.ingredients["flour"];
+        assert_eq!(flour.unit.as_deref(), Some("kg"));
+        assert!(matches!(flour.amount, Some(Amount::Single(d)) if (d - 1.5).abs() < 0.001));
+        assert!(matches!(
+            recipe.metadata.ingredients_specifiers[0].amount_in_step,
+            Amount::Single(d) if (d - 0.5).abs() < 0.001
+        ));
+
+        let quantity = &flour.quantities.quantities[0];
+        assert_eq!(quantity.unit.as_deref(), Some("kg"));
+        assert!(matches!(quantity.amount, Amount::Single(d) if (d - 0.5).abs() < 0.001));
+
+        let milk = &recipe.metadata.ingredients["milk"];
+        assert_eq!(milk.unit.as_deref(), Some("ml"));
+        assert!(matches!(milk.amount, Some(Amount::Single(d)) if (d - 236.59).abs() < 0.01));
+        let milk = &recipe.metadata.ingredients_specifiers[1];
+        assert_eq!(milk.unit.as_deref(), Some("ml"));
+
+        let steps = |source: &str, duplicates| -> Vec<String> {
+            let options = ParseOptions {
+                duplicates,
+                ..ParseOptions::default()
+            };
+            let mut recipe = parse_with(source, &options).unwrap().recipe;
+            recipe.convert(UnitSystem::Metric);
+            let specifiers = recipe.metadata.ingredients_specifiers.iter();
+            specifiers
+                .map(|specifier| specifier.quantity().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            steps(
+                "Mix @flour{1%cup}, then @&flour{2%cup} and @flour{3%cup}\n",
+                DuplicateMentions::Sum
+            ),
+            vec!["0.24 l", "0.47 l", "0.71 l"]
+        );
+        assert_eq!(
+            steps(
+                "Mix @flour{1%cup} and @flour{2%cup}\n",
+                DuplicateMentions::FirstWins
+            ),
+            vec!["236.59 ml", "473.18 ml"]
+        );
+    }
+
+    #[test]
+    fn humanize_units() {
+        let registry = UnitRegistry::default();
+        let humanize = |value, unit| -> Vec<String> {
+            registry
+                .humanize(value, unit)
+                .unwrap()
+                .iter()
+                .map(|(value, unit)| format!("{:.2} {}", value, unit.symbol))
+                .collect()
+        };
+        assert_eq!(humanize(1500.0, "ml"), vec!["1.50 l"]);
+        assert_eq!(humanize(1600.0, "g"), vec!["1.60 kg"]);
+        assert_eq!(humanize(0.25, "kg"), vec!["250.00 g"]);
+        assert_eq!(humanize(4.0, "tsp"), vec!["1.00 tbsp", "1.00 tsp"]);
+        assert_eq!(humanize(6.0, "tbsp"), vec!["6.00 tbsp"]);
+        assert_eq!(humanize(24.0, "tbsp"), vec!["1.50 cup"]);
+        assert!(registry.humanize(3.0, "pinch").is_none());
+
+        let source = "Add @milk{1500%ml}, @sugar{4%tsp} and @flour{1/2*%kg}.\n";
+        let mut recipe = parse(source).unwrap();
+        recipe.humanize_units();
+        let quantities = |name: &str| -> Vec<String> {
+            recipe.metadata.ingredients[name]
+                .quantities
+                .quantities
+                .iter()
+                .map(Quantity::to_string)
+                .collect()
+        };
+        assert_eq!(quantities("milk"), vec!["1.5 l"]);
+        assert_eq!(quantities("sugar"), vec!["1 tbsp", "1 tsp"]);
+        assert_eq!(recipe.metadata.ingredients["sugar"].unit.as_deref(), Some("tbsp"));
+        assert_eq!(quantities("flour"), vec!["500 per serving g"]);
+        let specifier = &recipe.metadata.ingredients_specifiers[2];
+        assert_eq!(specifier.amount_in_step, Amount::Multi(500.0));
+        assert_eq!(specifier.unit.as_deref(), Some("g"));
+        let specifier = &recipe.metadata.ingredients_specifiers[1];
+        assert!(
+            matches!(specifier.amount_in_step, Amount::Single(d) if (d - 4.0 / 3.0).abs() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn normalize_units() {
+        let mut registry = UnitRegistry::default();
+        for unit in &["tbsp", "Tbsp.", "tablespoon", "tablespoons", "TBS"] {
+            assert_eq!(registry.normalize(unit), "tbsp");
+        }
+        assert_eq!(registry.normalize("cups"), "cup");
+        assert_eq!(registry.normalize("pinch"), "pinch");
+
+        registry.add_alias("EL", "tbsp");
+        let recipe =
+            parse_with_units("Add @butter{1%tbsp} and @butter{2%EL}\n", &registry).unwrap();
+        let butter = &recipe.metadata.ingredients["butter"];
+        assert_eq!(butter.unit.as_deref(), Some("tbsp"));
+        assert!(matches!(butter.amount, Some(Amount::Single(d)) if d == 3.0));
+    }
+}
@@ -0,0 +1,211 @@
+//! Validation of recipes, e.g. as a check before publishing them.
+//!
+//! Unlike the configurable [Linter](crate::lint::Linter), [Recipe::validate] runs a fixed set of
+//! checks and returns the found [Issue]s as data, so they can be shown next to a form field or
+//! stored.
+//!
+//! ```
+//! use cook_with_rust_parser::parse;
+//! use cook_with_rust_parser::validate::Issue;
+//!
+//! let recipe = parse("Season with @salt and bake for ~{20%fortnights}.\n").unwrap();
+//! let issues = recipe.validate();
+//! assert!(matches!(&issues[0], Issue::MissingAmount { ingredient, .. } if ingredient == "salt"));
+//! assert!(matches!(&issues[1], Issue::UnknownTimerUnit { unit, .. } if unit == "fortnights"));
+//! ```
+
+use crate::diagnostics::{Diagnostic, Span};
+use crate::lint::{unmentioned, LintContext};
+use crate::time::unit_seconds;
+use crate::{QuantityError, Recipe};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An issue found by [Recipe::validate].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Issue {
+    /// An ingredient mentioned in a step without an amount.
+    MissingAmount {
+        /// Name of the ingredient.
+        ingredient: String,
+        /// The mention in the source.
+        span: Span,
+    },
+    /// Amounts per serving like `@eggs{1|2}` whose number doesn't match the servings.
+    ServingsMismatch {
+        /// Name of the ingredient.
+        ingredient: String,
+        /// Number of the amounts.
+        amounts: usize,
+        /// Number of the recipe's servings.
+        servings: usize,
+        /// The mention in the source.
+        span: Span,
+    },
+    /// A timer without a unit or a unit which isn't a unit of time.
+    UnknownTimerUnit {
+        /// The unit as written, empty if there is none.
+        unit: String,
+        /// The timer in the source.
+        span: Span,
+    },
+    /// Cookware listed in the `cookware` metadata, but never used in a step.
+    UnusedCookware {
+        /// Name of the cookware.
+        cookware: String,
+        /// The metadata in the source.
+        span: Span,
+    },
+}
+
+impl Issue {
+    /// Position of the issue in the [source](Recipe::source).
+    pub fn span(&self) -> Span {
+        match self {
+            Issue::MissingAmount { span, .. }
+            | Issue::ServingsMismatch { span, .. }
+            | Issue::UnknownTimerUnit { span, .. }
+            | Issue::UnusedCookware { span, .. } => *span,
+        }
+    }
+
+    /// The issue as a warning, e.g. to print it with
+    /// [Diagnostic::render](crate::diagnostics::Diagnostic::render).
+    pub fn diagnostic(&self) -> Diagnostic {
+        let hint = match self {
+            Issue::MissingAmount { .. } => "Add an amount, like @salt{1%tsp}",
+            Issue::ServingsMismatch { .. } => "Give an amount for each of the servings",
+            Issue::UnknownTimerUnit { .. } => "Use a unit of time, like ~{10%minutes}",
+            Issue::UnusedCookware { .. } => "Mention it in a step or remove it from the metadata",
+        };
+        Diagnostic::warning(self.span(), self.to_string()).with_hint(hint)
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MissingAmount { ingredient, .. } => {
+                write!(f, "Ingredient {} has no amount", ingredient)
+            }
+            Issue::ServingsMismatch {
+                ingredient,
+                amounts,
+                servings,
+                ..
+            } => write!(
+                f,
+                "Ingredient {} has {} amounts for {} servings",
+                ingredient, amounts, servings
+            ),
+            Issue::UnknownTimerUnit { unit, .. } if unit.is_empty() => {
+                write!(f, "Timer has no unit")
+            }
+            Issue::UnknownTimerUnit { unit, .. } => write!(f, "Unknown unit of time {}", unit),
+            Issue::UnusedCookware { cookware, .. } => {
+                write!(f, "Cookware {} is never used", cookware)
+            }
+        }
+    }
+}
+
+impl Recipe {
+    /// Check the recipe for issues, sorted by their position in the source:
+    ///
+    /// * ingredients mentioned without an amount,
+    /// * amounts per serving which don't match the [servings](crate::Metadata::servings),
+    /// * timers without a unit of time,
+    /// * cookware listed in the metadata, but never used.
+    ///
+    /// References to ingredients and recipes don't need an amount.
+    pub fn validate(&self) -> Vec<Issue> {
+        let context = LintContext::new(self);
+        let metadata = &self.metadata;
+        let mut issues = vec![];
+        let mentions = metadata
+            .ingredients_specifiers
+            .iter()
+            .zip(&context.ingredients);
+        for (specifier, span) in mentions {
+            let ingredient = specifier.ingredient.clone();
+            let span = *span;
+            if specifier.reference || specifier.recipe {
+                continue;
+            }
+            if specifier.quantity().is_none() {
+                issues.push(Issue::MissingAmount { ingredient, span });
+                continue;
+            }
+            let servings = metadata.servings.as_ref().map_or(0, Vec::len);
+            if let Err(QuantityError::ServingsMismatch(amounts, servings)) =
+                specifier.amount_in_step.check_servings(servings)
+            {
+                issues.push(Issue::ServingsMismatch {
+                    ingredient,
+                    amounts,
+                    servings,
+                    span,
+                });
+            }
+        }
+        metadata
+            .timer
+            .iter()
+            .zip(&context.timers)
+            .filter(|(timer, _)| unit_seconds(&timer.unit).is_none())
+            .for_each(|(timer, span)| {
+                issues.push(Issue::UnknownTimerUnit {
+                    unit: timer.unit.clone(),
+                    span: *span,
+                })
+            });
+        let used = metadata.cookware.iter().map(String::as_str);
+        unmentioned(&context, "cookware", used).for_each(|(cookware, span)| {
+            issues.push(Issue::UnusedCookware {
+                cookware: cookware.to_string(),
+                span,
+            })
+        });
+        issues.sort_by_key(|issue| issue.span().start);
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::Issue;
+    use crate::{parse, parse_with, ParseOptions};
+
+    #[test]
+    fn validate() {
+        let source = "\
+---
+servings: 2|4
+cookware: pot, whisk
+---
+Boil @eggs{1|2|3} and @water in a #pot for ~{10%minutes}, then rest for ~{1%nap}.
+Serve with the &eggs.
+";
+        let recipe = parse_with(source, &ParseOptions::lenient()).unwrap().recipe;
+        let issues = recipe.validate();
+        let messages: Vec<String> = issues.iter().map(Issue::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Cookware whisk is never used",
+                "Ingredient eggs has 3 amounts for 2 servings",
+                "Ingredient water has no amount",
+                "Unknown unit of time nap",
+            ]
+        );
+        let span = issues[2].span();
+        assert_eq!(&source[span.start..span.end], "@water");
+        assert_eq!(issues[3].diagnostic().message, "Unknown unit of time nap");
+
+        let source = ">> servings: 2|4\nBoil @eggs{1|2} with @salt{0%g} for ~{5%min}.\n";
+        assert!(parse(source).unwrap().validate().is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+//! Bindings for JavaScript, e.g. to parse recipes in the browser.
+//!
+//! Requires the `wasm` feature. Build with `wasm-pack build parser --features wasm` to get a
+//! package for the web. Recipes and tokens are passed to JavaScript as JSON, which can be read
+//! with `JSON.parse`. Errors are thrown as strings.
+
+use crate::render::{annotate_source, to_html};
+use crate::{parse, tokens};
+use wasm_bindgen::prelude::*;
+
+/// Parse the source into a [Recipe](crate::Recipe) as JSON.
+#[wasm_bindgen(js_name = parseToJson)]
+pub fn parse_to_json(source: &str) -> Result<String, JsValue> {
+    let recipe = parse(source).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    serde_json::to_string(&recipe).map_err(|error| JsValue::from_str(&error.to_string()))
+}
+
+/// The [tokens](crate::tokens::tokenize) of the source as a JSON array.
+#[wasm_bindgen]
+pub fn tokenize(source: &str) -> String {
+    serde_json::to_string(&tokens::tokenize(source)).unwrap()
+}
+
+/// Parse the source and render it as HTML, see [to_html].
+#[wasm_bindgen(js_name = renderHtml)]
+pub fn render_html(source: &str) -> Result<String, JsValue> {
+    let recipe = parse(source).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    Ok(to_html(&recipe))
+}
+
+/// Parse the source and write it as highlighted HTML, see [annotate_source].
+#[wasm_bindgen(js_name = annotateSource)]
+pub fn annotate(source: &str) -> Result<String, JsValue> {
+    let recipe = parse(source).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    Ok(annotate_source(&recipe))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wasm::{annotate, parse_to_json, render_html, tokenize};
+
+    #[test]
+    fn bindings() {
+        let source = "Boil @water{1%l} in a #pot.\n";
+        let json: serde_json::Value =
+            serde_json::from_str(&parse_to_json(source).unwrap()).unwrap();
+        assert_eq!(json["instruction"], "Boil @ in a #.\n");
+        let tokens: serde_json::Value = serde_json::from_str(&tokenize(source)).unwrap();
+        assert_eq!(tokens[0]["kind"], "IngredientName");
+        assert!(render_html(source).unwrap().contains("water"));
+        assert!(annotate(source).unwrap().starts_with("Boil <span class=\"ingredient\""));
+    }
+}
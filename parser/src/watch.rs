@@ -0,0 +1,141 @@
+//! Watching a [Collection] for changes. Requires the `watch` feature.
+//!
+//! A [CollectionWatcher] keeps the collection up to date while recipe files are added, changed
+//! or removed, so a server can keep its recipes in memory without restarting. Every change is
+//! [reloaded](Collection::reload) and reported as a [CollectionEvent].
+//!
+//! ```no_run
+//! # async fn run() -> notify::Result<()> {
+//! use cook_with_rust_parser::{Collection, ParseOptions};
+//!
+//! let collection = Collection::load_dir("recipes")?;
+//! let mut watcher = collection.watch(ParseOptions::default())?;
+//! while let Some(event) = watcher.next().await {
+//!     println!("{:?}, {} recipes", event, watcher.collection().recipes.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::collection::{Collection, CollectionEvent};
+use crate::ParseOptions;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+/// A [Collection] kept up to date with its directory. See the [module](crate::watch).
+pub struct CollectionWatcher {
+    collection: Collection,
+    options: ParseOptions,
+    /// Changed paths, sent by the watcher.
+    changes: UnboundedReceiver<PathBuf>,
+    /// Events of reloaded changes, not returned yet.
+    events: VecDeque<CollectionEvent>,
+    // Watching stops when the watcher is dropped.
+    _watcher: RecommendedWatcher,
+}
+
+impl Collection {
+    /// Watch the [root](Collection::root) of the collection for changes. Changed recipes are
+    /// parsed with the options.
+    pub fn watch(self, options: ParseOptions) -> notify::Result<CollectionWatcher> {
+        let (sender, changes) = unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if !event.kind.is_access() => event.paths.into_iter().for_each(|path| {
+                    let _ = sender.send(path);
+                }),
+                _ => {}
+            })?;
+        watcher.watch(&self.root, RecursiveMode::Recursive)?;
+        Ok(CollectionWatcher {
+            collection: self,
+            options,
+            changes,
+            events: VecDeque::new(),
+            _watcher: watcher,
+        })
+    }
+}
+
+impl CollectionWatcher {
+    /// The collection with all changes returned by [CollectionWatcher::next] so far.
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    /// Stop watching.
+    pub fn into_collection(self) -> Collection {
+        self.collection
+    }
+
+    /// Wait for the next change of the collection. Changes arriving at the same time are
+    /// reloaded together. [None] if watching has stopped.
+    pub async fn next(&mut self) -> Option<CollectionEvent> {
+        while self.events.is_empty() {
+            let mut paths = vec![self.changes.recv().await?];
+            while let Ok(path) = self.changes.try_recv() {
+                paths.push(path);
+            }
+            paths.sort();
+            paths.dedup();
+            for path in paths {
+                let events = self.collection.reload(&path, &self.options);
+                events.into_iter().for_each(|event| {
+                    if !self.events.contains(&event) {
+                        self.events.push_back(event);
+                    }
+                });
+            }
+        }
+        self.events.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collection::{Collection, CollectionEvent};
+    use crate::ParseOptions;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn watch() {
+        let root = std::env::temp_dir().join(format!("cook-watch-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Tea.cook"), "Steep @tea{1%bag}.\n").unwrap();
+        let collection = Collection::load_dir(&root).unwrap();
+        let mut watcher = collection.watch(ParseOptions::default()).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let mut next = |expected: CollectionEvent| {
+            runtime.block_on(async {
+                let timeout = Duration::from_secs(10);
+                loop {
+                    let event = tokio::time::timeout(timeout, watcher.next()).await;
+                    match event.expect("no change noticed").unwrap() {
+                        event if event == expected => break,
+                        _ => {}
+                    }
+                }
+            })
+        };
+
+        fs::write(root.join("Coffee.cook"), "Brew @coffee{20%g}.\n").unwrap();
+        next(CollectionEvent::Added(PathBuf::from("Coffee.cook")));
+        fs::write(root.join("Tea.cook"), "Steep @tea{2%bags}.\n").unwrap();
+        next(CollectionEvent::Updated(PathBuf::from("Tea.cook")));
+        fs::remove_file(root.join("Coffee.cook")).unwrap();
+        next(CollectionEvent::Removed(PathBuf::from("Coffee.cook")));
+        let collection = watcher.into_collection();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(collection.recipes.len(), 1);
+        let tea = &collection.by_path("Tea").unwrap().recipe;
+        assert_eq!(tea.source, "Steep @tea{2%bags}.\n");
+    }
+}
@@ -0,0 +1,174 @@
+//! Serialization of a [Recipe] back into CookLang source.
+
+use crate::{instruction_parts, Amount, IngredientSpecifier, InstructionPart, Recipe, Timer};
+
+impl Recipe {
+    /// Regenerate CookLang source from this recipe.
+    ///
+    /// A parsed recipe is written as it was parsed, see [Layout](crate::layout::Layout), with
+    /// only the changed ingredients, cookware and timers written anew.
+    ///
+    /// Otherwise, metadata is written first, followed by the instructions with every ingredient,
+    /// cookware and timer written back in place of its mentioning. Comments aren't part of the
+    /// [instruction](Recipe::instruction) and are therefore not written, see [Recipe::comments].
+    pub fn to_cooklang(&self) -> String {
+        if let Some(layout) = self.layout.as_ref().filter(|layout| layout.fits(self)) {
+            return layout.write(self);
+        }
+        let mut ingredient_specifier_iter = self.metadata.ingredients_specifiers.iter();
+        let mut cookware_iter = self.metadata.cookware.iter();
+        let mut timer_iter = self.metadata.timer.iter();
+        let mut result_string = String::new();
+
+        if !self.metadata.servings_labels.is_empty() {
+            let servings = self.metadata.servings_labels.join("|");
+            result_string += &format!(">> servings: {}\n", servings);
+        } else if let Some(servings) = &self.metadata.servings {
+            let servings: Vec<String> = servings.iter().map(|s| s.to_string()).collect();
+            result_string += &format!(">> servings: {}\n", servings.join("|"));
+        }
+        self.metadata.entries.iter().for_each(|(key, value)| {
+            result_string += &format!(">> {}: {}\n", key, value);
+        });
+
+        self.instruction
+            .split_inclusive('\n')
+            .filter(|line| !line.trim_start().starts_with(">>"))
+            .for_each(|line| {
+                instruction_parts(line).for_each(|part| match part {
+                    InstructionPart::Ingredient => {
+                        let specifier = ingredient_specifier_iter.next().unwrap();
+                        result_string += &ingredient_to_cooklang(self, specifier);
+                    }
+                    InstructionPart::Cookware => {
+                        result_string += &cookware_to_cooklang(cookware_iter.next().unwrap());
+                    }
+                    InstructionPart::Timer => {
+                        result_string += &timer_to_cooklang(timer_iter.next().unwrap());
+                    }
+                    InstructionPart::Text(char) => {
+                        if char == '@' || char == '#' || char == '~' {
+                            result_string.push('\\');
+                        }
+                        result_string.push(char);
+                    }
+                });
+            });
+
+        result_string
+    }
+}
+
+/// A mentioning of the ingredient, like `@?flour{1/2%kg}(sifted)`.
+pub(crate) fn ingredient_to_cooklang(recipe: &Recipe, specifier: &IngredientSpecifier) -> String {
+    let unit = specifier.unit.as_deref().or_else(|| {
+        recipe
+            .metadata
+            .ingredients
+            .get(specifier.ingredient_key())
+            .and_then(|ingredient| ingredient.unit.as_deref())
+    });
+    let mut result = String::from('@');
+    if specifier.reference {
+        result.push('&');
+    }
+    if specifier.optional {
+        result.push('?');
+    }
+    if specifier.hidden {
+        result.push('-');
+    }
+    result.push_str(&specifier.ingredient);
+    match amount_to_cooklang(&specifier.amount_in_step) {
+        Some(amount) => {
+            result.push('{');
+            result.push_str(&amount);
+            if let Some(unit) = unit {
+                result.push('%');
+                result.push_str(unit);
+            }
+            result.push('}');
+        }
+        None if specifier.ingredient.contains(' ') => result.push_str("{}"),
+        None => {}
+    }
+    if let Some(note) = &specifier.note {
+        result.push('(');
+        result.push_str(note);
+        result.push(')');
+    }
+    result
+}
+
+/// A mentioning of the cookware, like `#pot` or `#frying pan{}`.
+pub(crate) fn cookware_to_cooklang(cookware: &str) -> String {
+    match cookware.contains(' ') {
+        true => format!("#{}{{}}", cookware),
+        false => format!("#{}", cookware),
+    }
+}
+
+/// A mentioning of the timer, like `~{10%minutes}`.
+pub(crate) fn timer_to_cooklang(timer: &Timer) -> String {
+    let mut amount = number_to_cooklang(timer.amount);
+    if let Some(max) = timer.max {
+        amount += &format!("-{}", number_to_cooklang(max));
+    }
+    // `~{10%}` wouldn't be a timer, so the `%` is left out without a unit.
+    match timer.unit.is_empty() {
+        true => format!("~{{{}}}", amount),
+        false => format!("~{{{}%{}}}", amount, timer.unit),
+    }
+}
+
+/// Amount inside of the braces of an ingredient. [None] if the ingredient has no amount.
+fn amount_to_cooklang(amount: &Amount) -> Option<String> {
+    match amount {
+        Amount::Single(d) if *d == 0.0 => None,
+        Amount::Single(d) => Some(number_to_cooklang(*d)),
+        Amount::Multi(d) => Some(format!("{}*", number_to_cooklang(*d))),
+        Amount::PerServing(dd) => {
+            let servings: Vec<String> = dd.iter().map(|d| number_to_cooklang(*d)).collect();
+            Some(servings.join("|"))
+        }
+    }
+}
+
+/// CookLang only knows whole numbers and fractions, so decimals are written as a fraction.
+pub(crate) fn number_to_cooklang(number: f64) -> String {
+    if number.fract() == 0.0 {
+        return number.to_string();
+    }
+    (2..=16)
+        .find_map(|denominator| {
+            let numerator = number * denominator as f64;
+            if (numerator - numerator.round()).abs() < 0.0001 {
+                Some(format!("{}/{}", numerator.round(), denominator))
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| number.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn round_trip() {
+        let source = "\
+>> servings: 1|2|3\n\
+Get some @fruit salat ananas{1/2*%kg}(peeled) and @salt\n\
+Use the #big potato masher{} and a #pot\n\
+Start the timer ~{10%minutes}\n\
+Wait ~{10}\n\
+Serve @water{1|3/2|2%l}\n";
+
+        let recipe = parse(source).unwrap();
+        assert_eq!(recipe.to_cooklang(), source);
+
+        let reparsed = parse(&recipe.to_cooklang()).unwrap();
+        assert_eq!(reparsed.to_cooklang(), source);
+    }
+}